@@ -69,7 +69,7 @@ vec4 sample_ship(vec2 world_coords, vec3 player_position) {
     vec4 ship = get_sprite(ShapeTexture, vec2(4, 2), vec2(0,1), coords);
     
     return ship;
-};
+}
 
 
 
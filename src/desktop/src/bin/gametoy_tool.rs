@@ -0,0 +1,212 @@
+//! Command line tools for working with GameToy projects. Build with
+//! `cargo build --bin gametoy_tool --features tool`.
+//!
+//! Usage:
+//!     gametoy_tool import-shadertoy <shadertoy export.json> <output folder>
+//!     gametoy_tool export-shadertoy <project folder> <output folder>
+//!     gametoy_tool validate <project folder>
+//!     gametoy_tool schema [output file]
+//!     gametoy_tool pack <project folder> <datapack.tar>
+use gametoy::data_source::{self, GameDataSource};
+use gametoy::describe_error;
+use gametoy::diagnostics::Severity;
+use gametoy::gamedata::{GameData, GameDataError};
+use gametoy::shadertoy;
+use gametoy::tar;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const USAGE: &str = "Usage:
+    gametoy_tool import-shadertoy <shadertoy export.json> <output folder>
+    gametoy_tool export-shadertoy <project folder> <output folder>
+    gametoy_tool validate <project folder>
+    gametoy_tool schema [output file]
+    gametoy_tool pack <project folder> <datapack.tar>";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let result = match args.get(1).map(|s| s.as_str()) {
+        Some("import-shadertoy") if args.len() == 4 => import_shadertoy(&args[2], &args[3]),
+        Some("export-shadertoy") if args.len() == 4 => export_shadertoy(&args[2], &args[3]),
+        Some("validate") if args.len() == 3 => validate(&args[2]),
+        Some("schema") if args.len() <= 3 => write_schema(args.get(2)),
+        Some("pack") if args.len() == 4 => pack(&args[2], &args[3]),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    }
+}
+
+/// Converts a Shadertoy export into a project folder containing a data.json
+/// and the shaders.
+fn import_shadertoy(export_path: &str, output_folder: &str) -> Result<(), String> {
+    let export = fs::read_to_string(export_path)
+        .map_err(|e| format!("Unable to read {}: {}", export_path, e))?;
+    let project = shadertoy::import_shadertoy(&export)
+        .map_err(|e| format!("Unable to import {}: {}", export_path, describe_error(&e)))?;
+
+    let output_folder = Path::new(output_folder);
+    fs::create_dir_all(output_folder)
+        .map_err(|e| format!("Unable to create {:?}: {}", output_folder, e))?;
+
+    let config_text = serde_json::to_string_pretty(&project.config_file)
+        .map_err(|e| format!("Unable to serialize config: {}", e))?;
+    let mut files: Vec<(&str, &str)> = vec![(gametoy::gamedata::CONFIG_FILE_NAME, &config_text)];
+    files.extend(
+        project
+            .shader_sources
+            .iter()
+            .map(|(path, source)| (path.as_str(), source.as_str())),
+    );
+    for (path, contents) in files {
+        let file_path = output_folder.join(path);
+        fs::write(&file_path, contents)
+            .map_err(|e| format!("Unable to write {:?}: {}", file_path, e))?;
+        println!("[OK] Wrote {:?}", file_path);
+    }
+
+    for warning in project.warnings.iter() {
+        println!("[WARN] {}", warning);
+    }
+    for (path, url) in project.missing_files.iter() {
+        println!("[TODO] Download {} and save it as {}", url, path);
+    }
+    Ok(())
+}
+
+/// Loads a project the same way the desktop player does. This is usually a
+/// folder, but can also be a zip or tar of one.
+fn load_project_folder(project_folder: &str) -> Result<GameData, String> {
+    data_source::open_path(project_folder)
+        .and_then(GameData::from_source)
+        .map_err(|e| format!("Unable to load {}: {}", project_folder, describe_error(&e)))
+}
+
+/// Converts a project folder into a file of code for each Shadertoy tab and
+/// a report.txt describing how to set them up.
+fn export_shadertoy(project_folder: &str, output_folder: &str) -> Result<(), String> {
+    let gamedata = load_project_folder(project_folder)?;
+    let exported = shadertoy::export_shadertoy(&gamedata.config_file, &gamedata).map_err(|e| {
+        format!(
+            "Unable to export {}: {}",
+            project_folder,
+            describe_error(&e)
+        )
+    })?;
+
+    let output_folder = Path::new(output_folder);
+    fs::create_dir_all(output_folder)
+        .map_err(|e| format!("Unable to create {:?}: {}", output_folder, e))?;
+
+    let report = exported.report_text();
+    let mut files: Vec<(String, &str)> = vec![("report.txt".to_string(), &report)];
+    if !exported.common.is_empty() {
+        files.push(("common.glsl".to_string(), &exported.common));
+    }
+    for pass in exported.passes.iter() {
+        let file_name = format!("{}.glsl", pass.tab.to_lowercase().replace(' ', "_"));
+        files.push((file_name, &pass.code));
+    }
+    for (path, contents) in files {
+        let file_path = output_folder.join(path);
+        fs::write(&file_path, contents)
+            .map_err(|e| format!("Unable to write {:?}: {}", file_path, e))?;
+        println!("[OK] Wrote {:?}", file_path);
+    }
+
+    for problem in exported.report.iter() {
+        println!("[WARN] {}", problem);
+    }
+    Ok(())
+}
+
+/// Checks the config file of a project folder, and that it's shaders
+/// compile without needing a GPU. Fails if there are any errors.
+fn validate(project_folder: &str) -> Result<(), String> {
+    let gamedata = load_project_folder(project_folder)?;
+    let mut diagnostics = gamedata.config_file.validate(&gamedata);
+    diagnostics.extend(gametoy::validate::validate_project(
+        &gamedata.config_file,
+        &gamedata,
+    ));
+    for diagnostic in diagnostics.iter() {
+        println!("{}", diagnostic);
+    }
+
+    let num_errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    if num_errors > 0 {
+        Err(format!("{} has {} error(s)", project_folder, num_errors))
+    } else {
+        println!("[OK] {} is valid", project_folder);
+        Ok(())
+    }
+}
+
+/// Writes a JSON Schema for data.json files, or prints it if there is no
+/// output file. Point the `"$schema"` key of a data.json at it to have
+/// editors check and autocomplete the file.
+fn write_schema(output_file: Option<&String>) -> Result<(), String> {
+    let schema = serde_json::to_string_pretty(&gametoy::config_file::json_schema())
+        .map_err(|e| format!("Unable to serialize schema: {}", e))?;
+    match output_file {
+        Some(output_file) => {
+            fs::write(output_file, schema)
+                .map_err(|e| format!("Unable to write {}: {}", output_file, e))?;
+            println!("[OK] Wrote {}", output_file);
+        }
+        None => println!("{}", schema),
+    }
+    Ok(())
+}
+
+/// Packs a project folder, including the folders inside it, into a tar that
+/// the web player can download. The tar is compressed with gzip, or with zstd
+/// if the output file ends in `.zst`. Players detect the compression from the
+/// contents, so the file can keep the name `datapack.tar`.
+fn pack(project_folder: &str, output_file: &str) -> Result<(), String> {
+    let mut builder = tar::Builder::new(Vec::new());
+    data_source::Directory::new(project_folder)
+        .visit_files(&mut |path, file| {
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents)
+                .map_err(|e| GameDataError::ReadError(path.to_string(), e))?;
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, &contents[..])
+                .map_err(GameDataError::TarError)?;
+            println!("[OK] Packed {}", path);
+            Ok(())
+        })
+        .map_err(|e| format!("Unable to pack {}: {}", project_folder, describe_error(&e)))?;
+
+    let tar_data = builder
+        .into_inner()
+        .map_err(|e| format!("Unable to pack {}: {}", project_folder, e))?;
+    let compression = if output_file.ends_with(".zst") {
+        data_source::Compression::Zstd
+    } else {
+        data_source::Compression::Gzip
+    };
+    let bytes = compression
+        .compress(&tar_data)
+        .map_err(|e| format!("Unable to compress {}: {}", project_folder, e))?;
+    fs::write(output_file, &bytes)
+        .map_err(|e| format!("Unable to write {}: {}", output_file, e))?;
+    println!(
+        "[OK] Wrote {} ({:?}, {} bytes, {} uncompressed)",
+        output_file,
+        compression,
+        bytes.len(),
+        tar_data.len()
+    );
+    Ok(())
+}
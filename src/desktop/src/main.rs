@@ -1,13 +1,14 @@
 use gametoy;
+use gametoy::data_source::{self, GameDataSource};
 use gametoy::glow;
-use gametoy::tar;
 use std::env;
-use std::fs;
+use std::path::PathBuf;
 
 use glutin::event::{Event, WindowEvent};
 use glutin::event_loop::ControlFlow;
 
 const TAR_FILE: &'static str = "datapack.tar";
+const ZIP_FILE: &str = "datapack.zip";
 const DATA_FOLDER: &'static str = "data";
 
 fn main() {
@@ -15,7 +16,14 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     println!("{:?}", args);
 
-    let tar = load_tar(args).expect("Unable to load TAR");
+    let source = find_data_source(args).expect("Unable to find game data");
+    let game_data = match gametoy::gamedata::GameData::from_source(source) {
+        Ok(game_data) => game_data,
+        Err(err) => {
+            println!("{}", gametoy::describe_error(&err));
+            return;
+        }
+    };
 
     // Create our window
     let (gl, window, event_loop) = {
@@ -38,35 +46,48 @@ fn main() {
         (gl, window, event_loop)
     };
 
-    let mut toy = match gametoy::GameToy::new(&gl, tar, true) {
+    let mut toy = match gametoy::GameToy::from_game_data(&gl, game_data, true) {
         Ok(toy) => toy,
         Err(gametoy::GameToyError::NodeCreateError(
             nodename,
             gametoy::nodes::NodeError::ShaderError(
                 gametoy::shader::ShaderError::ShaderCompileError {
                     shader_type: _,
-                    compiler_output,
+                    compiler_output: _,
                     shader_text,
+                    diagnostics,
                 },
             ),
         )) => {
-            let lines = shader_text.split('\n');
-            for (line_id, line_text) in lines.enumerate() {
-                println!("{:4} | {}", line_id + 1, line_text);
+            println!("Error creating node: \"{}\"\n", nodename);
+            let lines: Vec<&str> = shader_text.split('\n').collect();
+            for diagnostic in diagnostics.iter() {
+                println!("{}", diagnostic);
+                // Errors in generated code can't be found in the users files,
+                // so show the offending line
+                if let (None, Some(line)) = (&diagnostic.file, diagnostic.line) {
+                    if let Some(line_text) = line.checked_sub(1).and_then(|l| lines.get(l)) {
+                        println!("{:4} | {}", line, line_text);
+                    }
+                }
+            }
+            return;
+        }
+        Err(gametoy::GameToyError::InvalidConfig(diagnostics)) => {
+            for diagnostic in diagnostics.iter() {
+                println!("{}", diagnostic);
             }
-
-            println!(
-                "Error creating node: \"{}\"\n\n{}",
-                nodename, compiler_output
-            );
             return;
         }
         Err(err) => {
-            println!("{:?}", err);
+            println!("{}", gametoy::describe_error(&err));
             return;
         }
     };
 
+    let mut num_diagnostics_shown = 0;
+    print_new_diagnostics(&toy, &mut num_diagnostics_shown);
+
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Wait;
         match event {
@@ -85,6 +106,7 @@ fn main() {
 
                 toy.render(&gl, since_the_epoch.as_secs_f64())
                     .expect("Failed to render");
+                print_new_diagnostics(&toy, &mut num_diagnostics_shown);
                 window.swap_buffers().unwrap();
             }
             Event::WindowEvent { ref event, .. } => match event {
@@ -115,58 +137,45 @@ fn main() {
     });
 }
 
-fn load_tar(args: Vec<String>) -> Option<tar::Archive<fs::File>> {
+/// Prints the diagnostics the toy has found since the last call
+fn print_new_diagnostics(toy: &gametoy::GameToy, num_shown: &mut usize) {
+    for diagnostic in toy.diagnostics().iter().skip(*num_shown) {
+        println!("{}", diagnostic);
+    }
+    *num_shown = toy.diagnostics().len();
+}
+
+/// Finds the game to run: the folder, zip or tar given on the command line,
+/// or else the first of the data folder, zip and tar next to the executable.
+fn find_data_source(args: Vec<String>) -> Option<Box<dyn GameDataSource>> {
     let exe_path = env::current_exe().expect("Failed to determine executable location");
 
     let mut exe_dir = exe_path.clone();
     exe_dir.pop();
 
-    let data_folder = {
-        if args.len() == 2 {
-            let mut data_folder = env::current_dir().expect("Unable to determine CWD").clone();
-            data_folder.push(args[1].clone());
-            println!("[OK] Using override data directory: {:?}", data_folder);
-            data_folder
-        } else {
-            let mut data_folder = exe_dir.clone();
-            data_folder.push(DATA_FOLDER);
-            println!("[OK] Using default data directory: {:?}", data_folder);
-            data_folder
-        }
+    let candidates: Vec<PathBuf> = if args.len() == 2 {
+        let mut data_path = env::current_dir().expect("Unable to determine CWD").clone();
+        data_path.push(args[1].clone());
+        vec![data_path]
+    } else {
+        [DATA_FOLDER, ZIP_FILE, TAR_FILE]
+            .iter()
+            .map(|name| exe_dir.join(name))
+            .collect()
     };
 
-    let mut tar_file_path = exe_dir.clone();
-    tar_file_path.push(TAR_FILE);
-
-    // If there is a folder for data, assemble it into a package and run
-    // the game with that.
-    if let Ok(entries) = fs::read_dir(&data_folder) {
-        println!("[OK] Found data directory. Creating Bundle");
-        let file = fs::File::create(tar_file_path.clone()).unwrap();
-        let mut a = tar::Builder::new(file);
-
-        for entry in entries {
-            let entry = entry.expect("Failed to read directory");
-            let path = entry.path();
-            let metadata = fs::metadata(&path).expect("File Deleted while bundling");
-            if metadata.is_file() {
-                println!("[OK] Bundling file: {:?}", &path);
-                a.append_file(
-                    path.file_name().expect("No filename???"),
-                    &mut fs::File::open(path.clone()).expect("Failed to open file for packing"),
-                )
-                .unwrap();
+    for path in candidates {
+        if !path.exists() {
+            println!("[OK] No game data at {:?}", path);
+            continue;
+        }
+        match data_source::open_path(&path) {
+            Ok(source) => {
+                println!("[OK] Running with game data: {:?}", path);
+                return Some(source);
             }
+            Err(err) => println!("[WRN] {}", gametoy::describe_error(&err)),
         }
-    } else {
-        println!("[OK] No data directory: {:?}", data_folder);
-    }
-
-    if let Ok(file) = fs::File::open(tar_file_path.clone()) {
-        println!("[OK] Running with package: {:?}", tar_file_path);
-        return Some(tar::Archive::new(file));
-    } else {
-        println!("[WRN] No tar bundle found at path {:?}", tar_file_path);
     }
 
     None
@@ -1,11 +1,23 @@
+use crate::migration;
+use crate::nodes;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
 
 /// The game is configured through a config file. In the future this
 /// may be generated using some sort of tool.
 /// This file contains everything needed to construct the game except
 /// for some "big" data such as textures and shaders
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ConfigFile {
+    /// The version of the config file format this was written in. Older
+    /// files are upgraded when they are loaded, see the `migration` module.
+    /// Files without one are from before it existed.
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub format_version: u32,
+
     /// Data about the game, author etc.
     pub metadata: MetaData,
 
@@ -13,8 +25,190 @@ pub struct ConfigFile {
     pub graph: GraphConfig,
 }
 
+/// The languages a config file can be written in. Each has it's own file
+/// name in the datapack, see `ConfigFileFormat::file_name`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFileFormat {
+    Json,
+    Ron,
+    Toml,
+}
+
+impl ConfigFileFormat {
+    pub const ALL: [ConfigFileFormat; 3] = [Self::Json, Self::Ron, Self::Toml];
+
+    /// The file extension used for this format, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Ron => "ron",
+            Self::Toml => "toml",
+        }
+    }
+
+    /// The name of a config file in this format within a datapack
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Json => "data.json",
+            Self::Ron => "data.ron",
+            Self::Toml => "data.toml",
+        }
+    }
+
+    /// Picks the format from a path's extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|format| format.extension() == extension)
+    }
+}
+
+/// Why a config file couldn't be loaded or saved
+#[derive(Debug)]
+pub enum ConfigFileError {
+    /// The file couldn't be read, or isn't valid UTF-8
+    ReadError(std::io::Error),
+
+    /// The file isn't valid JSON, or doesn't match the layout of a `ConfigFile`
+    JsonError(serde_json::Error),
+
+    /// The file isn't valid RON, or doesn't match the layout of a
+    /// `ConfigFile` in the version it was written in
+    RonReadError(ron::error::SpannedError),
+
+    /// The config file couldn't be written as RON
+    RonWriteError(ron::Error),
+
+    /// The file isn't valid TOML, or doesn't match the layout of a `ConfigFile`
+    TomlReadError(toml::de::Error),
+
+    /// The config file couldn't be written as TOML, such as when a value
+    /// has no TOML equivalent
+    TomlWriteError(toml::ser::Error),
+
+    /// The file couldn't be upgraded from the version it was written in
+    MigrationError(migration::MigrationError),
+
+    /// The RON file is from before RON config files could be written, see
+    /// `migration::FIRST_RON_VERSION`. u32 is the version of the file
+    RonVersionMismatch(u32),
+}
+
+impl fmt::Display for ConfigFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadError(_) => write!(f, "the config file could not be read"),
+            Self::JsonError(_) | Self::RonReadError(_) | Self::TomlReadError(_) => {
+                write!(f, "the config file could not be parsed")
+            }
+            Self::RonWriteError(_) | Self::TomlWriteError(_) => {
+                write!(f, "the config file could not be written")
+            }
+            Self::MigrationError(_) => write!(f, "the config file could not be upgraded"),
+            Self::RonVersionMismatch(version) => write!(
+                f,
+                "the config file is version {}, but RON config files start at version {}",
+                version,
+                migration::FIRST_RON_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ReadError(err) => Some(err),
+            Self::JsonError(err) => Some(err),
+            Self::RonReadError(err) => Some(err),
+            Self::RonWriteError(err) => Some(err),
+            Self::TomlReadError(err) => Some(err),
+            Self::TomlWriteError(err) => Some(err),
+            Self::MigrationError(err) => Some(err),
+            Self::RonVersionMismatch(_) => None,
+        }
+    }
+}
+
+impl ConfigFile {
+    /// Reads a config file, upgrading it first if it was written in an
+    /// older format version.
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        format: ConfigFileFormat,
+    ) -> Result<Self, ConfigFileError> {
+        let mut document: serde_json::Value = match format {
+            ConfigFileFormat::Json => {
+                serde_json::from_reader(reader).map_err(ConfigFileError::JsonError)?
+            }
+            ConfigFileFormat::Ron => {
+                let mut text = String::new();
+                reader
+                    .read_to_string(&mut text)
+                    .map_err(ConfigFileError::ReadError)?;
+                Self::read_ron(&text)?
+            }
+            ConfigFileFormat::Toml => {
+                let mut text = String::new();
+                reader
+                    .read_to_string(&mut text)
+                    .map_err(ConfigFileError::ReadError)?;
+                toml::from_str(&text).map_err(ConfigFileError::TomlReadError)?
+            }
+        };
+        migration::migrate(&mut document).map_err(ConfigFileError::MigrationError)?;
+        serde_json::from_value(document).map_err(ConfigFileError::JsonError)
+    }
+
+    /// Reads a RON document into JSON using the layout of the version it
+    /// was written in, so that it can be migrated like the other formats
+    fn read_ron(text: &str) -> Result<serde_json::Value, ConfigFileError> {
+        /// Reads just the version, which decides the layout of the rest
+        #[derive(Deserialize)]
+        struct Version {
+            #[serde(default)]
+            format_version: u32,
+        }
+        let version: Version = ron::from_str(text).map_err(ConfigFileError::RonReadError)?;
+        migration::read_ron(version.format_version, text)
+    }
+
+    /// Writes the config file in a layout meant for reading and diffing:
+    /// indented, one field per line, in a consistent order and ending with
+    /// a newline.
+    pub fn to_string_pretty(&self, format: ConfigFileFormat) -> Result<String, ConfigFileError> {
+        let mut text = match format {
+            ConfigFileFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(ConfigFileError::JsonError)?
+            }
+            ConfigFileFormat::Ron => {
+                ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new())
+                    .map_err(ConfigFileError::RonWriteError)?
+            }
+            ConfigFileFormat::Toml => {
+                toml::to_string_pretty(self).map_err(ConfigFileError::TomlWriteError)?
+            }
+        };
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        Ok(text)
+    }
+}
+
+/// A JSON Schema describing config files, including the documentation of
+/// each field. Editors such as VS Code can use it to check and autocomplete
+/// `data.json` files by adding a `"$schema"` key pointing at it.
+#[cfg(feature = "schema")]
+pub fn json_schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(ConfigFile)
+}
+
 /// Contains information about the game/author etc.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MetaData {
     /// The name of the game. This is displayed while loading, on the window title etc.
     pub game_name: String,
@@ -37,6 +231,7 @@ pub struct MetaData {
 
 /// Configures the rendergraph - the thing that actually runs the game.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GraphConfig {
     /// A node is a "process" that takes some inputs and generates some output
     /// Note that the order is important - the order in here defines
@@ -49,6 +244,7 @@ pub struct GraphConfig {
 
 /// Connects two nodes in the rendergraph
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Link {
     /// The name of the node to start on
     pub start_node: String,
@@ -64,6 +260,7 @@ pub struct Link {
 /// A node in the rendergraph. A rendergraph node takes a bunch of
 /// texture inputs and processes them to create some outputs
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Node {
     /// A static image as defined in a file
     Image(ImageConfig),
@@ -75,10 +272,123 @@ pub enum Node {
     Output(OutputConfig),
 
     Keyboard(KeyboardConfig),
+
+    /// Six square images making up the faces of a cube. Sampled in GLSL
+    /// using a `samplerCube`
+    Cubemap(CubemapConfig),
+
+    /// A list of same-sized images stacked into layers. Sampled in GLSL
+    /// using a `sampler2DArray`
+    TextureArray(TextureArrayConfig),
+
+    /// An image that changes over time, loaded from an APNG or a
+    /// sequence of numbered PNG's
+    AnimatedImage(AnimatedImageConfig),
+
+    /// An image containing many sprites, along with a sprite sheet
+    /// describing where each one is
+    Atlas(AtlasConfig),
+
+    /// A font rasterised into a glyph atlas for drawing text
+    Font(FontConfig),
+
+    /// A map made in the Tiled editor, with it's layers stored in textures
+    TiledMap(TiledMapConfig),
+
+    /// A table of numbers loaded from a CSV or JSON file
+    DataTable(DataTableConfig),
+
+    /// A texture whose contents are supplied by the application embedding
+    /// GameToy through `GameToy::upload_node_data`
+    HostData(HostDataConfig),
+
+    /// Copies part of it's input texture back to the CPU so the host
+    /// application can read it with `GameToy::get_readback`
+    Readback(ReadbackConfig),
+}
+
+impl Node {
+    /// The name of this node. This is unique within the graph.
+    pub fn get_name(&self) -> &str {
+        match self {
+            Node::Image(config) => &config.name,
+            Node::RenderPass(config) => &config.name,
+            Node::Output(config) => &config.name,
+            Node::Keyboard(config) => &config.name,
+            Node::Cubemap(config) => &config.name,
+            Node::TextureArray(config) => &config.name,
+            Node::AnimatedImage(config) => &config.name,
+            Node::Atlas(config) => &config.name,
+            Node::Font(config) => &config.name,
+            Node::TiledMap(config) => &config.name,
+            Node::DataTable(config) => &config.name,
+            Node::HostData(config) => &config.name,
+            Node::Readback(config) => &config.name,
+        }
+    }
+
+    /// Uniforms that are passed along links from this node in addition to the
+    /// texture. A renderpass can read these as `<input slot name>_<uniform name>`.
+    /// The values returned here are placeholders that define the uniform type.
+    pub fn get_link_uniforms(&self) -> Vec<(String, UniformValue)> {
+        match self {
+            Node::AnimatedImage(_) => vec![("frame".to_string(), UniformValue::Int(0))],
+            Node::DataTable(_) => vec![("size".to_string(), UniformValue::Vec2([0.0, 0.0]))],
+            Node::HostData(_) => vec![("size".to_string(), UniformValue::Vec2([0.0, 0.0]))],
+            Node::TiledMap(_) => vec![
+                ("size".to_string(), UniformValue::Vec2([0.0, 0.0])),
+                ("tile_size".to_string(), UniformValue::Vec2([0.0, 0.0])),
+            ],
+            _ => vec![],
+        }
+    }
+
+    /// The names of the slots that links can end on
+    pub fn get_input_slots(&self) -> Vec<String> {
+        match self {
+            Node::Output(_) => vec![nodes::Output::INPUT_BUFFER_NAME.to_string()],
+            Node::Readback(_) => vec![nodes::Readback::INPUT_BUFFER_NAME.to_string()],
+            Node::RenderPass(config) => config
+                .input_texture_slots
+                .iter()
+                .map(|slot| slot.name.clone())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// The names of the slots that links can start from
+    pub fn get_output_slots(&self) -> Vec<String> {
+        match self {
+            Node::Image(_) => vec![nodes::Image::OUTPUT_BUFFER_NAME.to_string()],
+            Node::Keyboard(_) => vec![nodes::Keyboard::OUTPUT_BUFFER_NAME.to_string()],
+            Node::Cubemap(_) => vec![nodes::Cubemap::OUTPUT_BUFFER_NAME.to_string()],
+            Node::TextureArray(_) => vec![nodes::TextureArray::OUTPUT_BUFFER_NAME.to_string()],
+            Node::AnimatedImage(_) => vec![nodes::AnimatedImage::OUTPUT_BUFFER_NAME.to_string()],
+            Node::Atlas(_) => vec![
+                nodes::Atlas::OUTPUT_BUFFER_NAME.to_string(),
+                nodes::Atlas::FRAMES_BUFFER_NAME.to_string(),
+            ],
+            Node::Font(_) => vec![
+                nodes::Font::OUTPUT_BUFFER_NAME.to_string(),
+                nodes::Font::METRICS_BUFFER_NAME.to_string(),
+            ],
+            Node::TiledMap(config) => config.layers.clone(),
+            Node::DataTable(_) => vec![nodes::DataTable::OUTPUT_BUFFER_NAME.to_string()],
+            Node::HostData(_) => vec![nodes::HostData::OUTPUT_BUFFER_NAME.to_string()],
+            Node::RenderPass(config) => config
+                .output_texture_slots
+                .iter()
+                .map(|slot| slot.name.clone())
+                .collect(),
+            Node::Output(_) | Node::Readback(_) => vec![],
+        }
+    }
 }
 
 /// A node containing a static image/texture
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ImageConfig {
     /// the path to read the texture from
     pub name: String,
@@ -87,23 +397,362 @@ pub struct ImageConfig {
     /// Generate mipmaps for this buffer
     #[serde(default = "get_false")]
     pub generate_mipmap: bool,
+
+    /// The colour space the image file is stored in. Images stored in sRGB
+    /// are converted to linear values by the GPU when they are sampled.
+    #[serde(default = "get_linear")]
+    pub color_space: ColorSpace,
 }
 
 /// The node that actually writes to the screen
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct OutputConfig {
     pub name: String,
+
+    /// The colour space of the texture connected to the output. If this is
+    /// linear, the output node converts it to sRGB before it is displayed.
+    #[serde(default = "get_srgb")]
+    pub color_space: ColorSpace,
+}
+
+/// How the values in a texture relate to the colours shown on screen.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ColorSpace {
+    /// Values are proportional to light intensity. Lighting maths
+    /// should be done in this space.
+    Linear,
+
+    /// Values are gamma-encoded as expected by the display. Most
+    /// image editors save PNG's in this space.
+    Srgb,
+}
+
+/// A node containing a static cubemap
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CubemapConfig {
+    pub name: String,
+
+    /// Where to read the faces of the cube from
+    pub faces: CubemapFaces,
+
+    /// Generate mipmaps for this buffer
+    #[serde(default = "get_false")]
+    pub generate_mipmap: bool,
+
+    /// The colour space the image files are stored in
+    #[serde(default = "get_linear")]
+    pub color_space: ColorSpace,
+}
+
+/// How the images for a cubemap are laid out
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum CubemapFaces {
+    /// Each face is in it's own file
+    Separate {
+        positive_x: String,
+        negative_x: String,
+        positive_y: String,
+        negative_y: String,
+        positive_z: String,
+        negative_z: String,
+    },
+
+    /// All the faces are in a single image four faces wide and three
+    /// faces high arranged as a horizontal cross:
+    /// ```text
+    ///      +Y
+    /// -X   +Z   +X   -Z
+    ///      -Y
+    /// ```
+    Cross(String),
+}
+
+/// A node containing a static texture array
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TextureArrayConfig {
+    pub name: String,
+
+    /// The paths to read each layer from. All the images must be the
+    /// same size and pixel format.
+    pub paths: Vec<String>,
+
+    /// Generate mipmaps for this buffer
+    #[serde(default = "get_false")]
+    pub generate_mipmap: bool,
+
+    /// The colour space the image files are stored in
+    #[serde(default = "get_linear")]
+    pub color_space: ColorSpace,
+}
+
+/// A node containing an animated image
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AnimatedImageConfig {
+    pub name: String,
+
+    /// Where to read the frames of the animation from
+    pub source: AnimationSource,
+
+    /// How many frames to advance per second
+    pub fps: f32,
+
+    /// What happens when the animation reaches the final frame
+    pub mode: AnimationMode,
+
+    /// Generate mipmaps for this buffer
+    #[serde(default = "get_false")]
+    pub generate_mipmap: bool,
+
+    /// The colour space the image files are stored in
+    #[serde(default = "get_linear")]
+    pub color_space: ColorSpace,
+}
+
+/// Where the frames of an `AnimatedImage` come from
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum AnimationSource {
+    /// A single animated PNG
+    Apng(String),
+
+    /// A sequence of PNG's. The run of `#` characters in the path is replaced
+    /// by the zero-padded frame number, so `walk_###.png` loads `walk_000.png`,
+    /// `walk_001.png` etc. Numbering may start at zero or one, and continues
+    /// until a file is missing.
+    Sequence(String),
+}
+
+/// How an `AnimatedImage` plays through it's frames
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum AnimationMode {
+    /// Return to the first frame after the last one
+    Loop,
+
+    /// Play forwards and then backwards
+    PingPong,
+
+    /// Play once and then stay on the last frame
+    Once,
+}
+
+/// A sprite atlas. Outputs the image as `tex` and the rectangle of each
+/// sprite as `frames`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AtlasConfig {
+    pub name: String,
+
+    /// The path of the image containing the sprites
+    pub path: String,
+
+    /// The path of a JSON sprite sheet describing where the sprites are.
+    /// Both the hash and array layouts exported by Aseprite and
+    /// TexturePacker are supported, as are plain `{"x", "y", "w", "h"}`
+    /// rectangles in place of the `frame` object.
+    pub sheet_path: String,
+
+    /// Generate mipmaps for this buffer
+    #[serde(default = "get_false")]
+    pub generate_mipmap: bool,
+
+    /// The colour space the image file is stored in
+    #[serde(default = "get_linear")]
+    pub color_space: ColorSpace,
+}
+
+/// A font rasterised into a glyph atlas. Outputs the atlas as `tex` and the
+/// position and size of each glyph as `metrics`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FontConfig {
+    pub name: String,
+
+    /// Where to read the font from
+    pub source: FontSource,
+
+    /// The height of a line of text in pixels
+    pub size: f32,
+
+    /// The characters to rasterise. A character's position in this string is
+    /// it's glyph index in shaders. Defaults to printable ASCII, so the glyph
+    /// index is the character code minus 32.
+    #[serde(default = "get_printable_ascii")]
+    pub characters: String,
+}
+
+/// Which font a `Font` node rasterises
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum FontSource {
+    /// A `.ttf` or `.otf` file in the game data
+    File(String),
+
+    /// The built-in proportional font (Ubuntu Light)
+    Proportional,
+
+    /// The built-in monospace font (ProggyClean)
+    Monospace,
+}
+
+/// A map from the Tiled editor, loaded from a `.tmx` or `.tmj` file. Each
+/// layer listed in `layers` becomes an output slot with the same name:
+///  - Tile layers are `R16UI` textures of global tile ids, where zero is an
+///    empty cell. Row zero is the top of the map.
+///  - Object layers are single row `RGBA32F` textures with one texel per
+///    object containing `(x, y, width, height)` in pixels. Points have no size.
+///
+/// Along with each texture, the `size` (in cells or objects) and the
+/// map's `tile_size` (in pixels) are passed to linked renderpasses.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TiledMapConfig {
+    pub name: String,
+
+    /// The path of the `.tmx` or `.tmj` file
+    pub path: String,
+
+    /// The names of the layers to output
+    pub layers: Vec<String>,
+}
+
+/// A table of numbers stored in a float texture, read in shaders with
+/// `texelFetch`. The `size` of the texture (in texels) is passed to linked
+/// renderpasses.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DataTableConfig {
+    pub name: String,
+
+    /// The path of a `.csv` file, or a `.json` file containing either an
+    /// array of numbers (a single row) or an array of arrays of numbers
+    pub path: String,
+
+    /// How many consecutive cells are packed into each texel
+    pub format: DataTableFormat,
+
+    /// Whether rows of the table become rows or columns of the texture
+    pub layout: DataTableLayout,
+
+    /// Skip the first line of a CSV file
+    #[serde(default = "get_false")]
+    pub has_header: bool,
+}
+
+/// The texture format of a `DataTable`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DataTableFormat {
+    /// One cell per texel
+    R32F,
+
+    /// Four consecutive cells per texel. Rows are padded with zeros to a
+    /// multiple of four cells.
+    RGBA32F,
+}
+
+/// How the rows of a `DataTable` are arranged in the texture
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DataTableLayout {
+    /// Each row of the table is a row of the texture, so cell `(row, column)`
+    /// is read from texel `(column, row)`
+    Rows,
+
+    /// Each row of the table is a column of the texture, so cell
+    /// `(row, column)` is read from texel `(row, column)`
+    Columns,
+}
+
+/// A texture filled in by the host application. It starts out zeroed and
+/// keeps whatever was last uploaded to it. The `size` of the texture (in
+/// texels) is passed to linked renderpasses.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HostDataConfig {
+    pub name: String,
+
+    /// Width and height of the texture in texels
+    pub size: [u32; 2],
+
+    /// The format of the texture. Uploaded data must be tightly packed
+    /// texels of this format.
+    pub format: OutputBufferFormat,
+}
+
+/// Reads a region of a texture back to the CPU every frame. The copy is
+/// done asynchronously where possible, so the result lags the GPU by a
+/// frame or two.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ReadbackConfig {
+    pub name: String,
+
+    /// The bottom left corner of the region to read, in texels
+    pub offset: [u32; 2],
+
+    /// Width and height of the region to read, in texels. Keep this small:
+    /// it is copied every frame.
+    pub size: [u32; 2],
+
+    /// The format the texels are read in. This must suit the input texture
+    pub format: ReadbackFormat,
+}
+
+/// The format of the data returned by a `Readback` node
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ReadbackFormat {
+    /// Four bytes per texel, for normalised textures such as `RGBA8`
+    RGBA8,
+
+    /// Four floats per texel, for floating point textures
+    RGBA32F,
+
+    /// Four signed integers per texel, for signed integer textures
+    RGBA32I,
+
+    /// Four unsigned integers per texel, for unsigned integer textures
+    RGBA32UI,
+}
+
+impl ReadbackFormat {
+    /// The format and type to pass to `glReadPixels`
+    pub fn to_format_and_type(&self) -> (u32, u32) {
+        match self {
+            Self::RGBA8 => (glow::RGBA, glow::UNSIGNED_BYTE),
+            Self::RGBA32F => (glow::RGBA, glow::FLOAT),
+            Self::RGBA32I => (glow::RGBA_INTEGER, glow::INT),
+            Self::RGBA32UI => (glow::RGBA_INTEGER, glow::UNSIGNED_INT),
+        }
+    }
+
+    /// The number of bytes a single texel is read as
+    pub fn to_bytes_per_pixel(&self) -> usize {
+        match self {
+            Self::RGBA8 => 4,
+            Self::RGBA32F | Self::RGBA32I | Self::RGBA32UI => 16,
+        }
+    }
 }
 
 /// A texture that contains the state of the keyboard.
 /// The texture is
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct KeyboardConfig {
     pub name: String,
 }
 
 /// A node that runs a GLSL shader on it's inputs.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RenderPassConfig {
     /// the name of the renderpass
     pub name: String,
@@ -123,10 +772,67 @@ pub struct RenderPassConfig {
 
     /// When the renderpass should execute
     pub execution_mode: ExecutionMode,
+
+    /// Tweakable values that are declared as uniforms in the shader. These
+    /// can be changed at runtime with `GameToy::set_uniform`.
+    #[serde(default)]
+    pub uniforms: Vec<UniformConfig>,
+
+    /// Treat the shader as one copied from Shadertoy: it defines
+    /// `mainImage(out vec4 fragColor, in vec2 fragCoord)` instead of `main`,
+    /// and the first four input slots can be read as `iChannel0..3`.
+    #[serde(default = "get_false")]
+    pub shadertoy_compat: bool,
+}
+
+/// A uniform declared by the user rather than by GameToy
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UniformConfig {
+    /// The name of the uniform in the shader
+    pub name: String,
+
+    /// The starting value. This also defines the type of the uniform.
+    pub value: UniformValue,
+
+    /// The smallest value allowed. For vectors this applies to each component.
+    #[serde(default)]
+    pub min: Option<f32>,
+
+    /// The largest value allowed. For vectors this applies to each component.
+    #[serde(default)]
+    pub max: Option<f32>,
+
+    /// How much the value changes by when dragged in the editor
+    #[serde(default)]
+    pub step: Option<f32>,
+}
+
+impl UniformConfig {
+    /// Limits a value to this uniform's `min` and `max`
+    pub fn clamp(&self, value: &UniformValue) -> UniformValue {
+        let clamp = |x: f32| {
+            let x = self.min.map_or(x, |min| x.max(min));
+            self.max.map_or(x, |max| x.min(max))
+        };
+        match value {
+            UniformValue::Float(x) => UniformValue::Float(clamp(*x)),
+            // Going through f32 would round large ints, so only the bounds are converted
+            UniformValue::Int(x) => {
+                let x = self.min.map_or(*x, |min| (*x).max(min.ceil() as i32));
+                UniformValue::Int(self.max.map_or(x, |max| x.min(max.floor() as i32)))
+            }
+            UniformValue::Vec2(v) => UniformValue::Vec2(v.map(clamp)),
+            UniformValue::Vec3(v) => UniformValue::Vec3(v.map(clamp)),
+            UniformValue::Vec4(v) => UniformValue::Vec4(v.map(clamp)),
+            UniformValue::Color(_) | UniformValue::Bool(_) => value.clone(),
+        }
+    }
 }
 
 /// An output channel from a `RenderPass
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct OutputBufferConfig {
     /// The name of the output channel
     pub name: String,
@@ -141,13 +847,91 @@ pub struct OutputBufferConfig {
 
 /// An input channel for a `RenderPass`
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct InputBufferConfig {
     /// the name of the input channel
     pub name: String,
+
+    /// What sort of texture this input channel accepts
+    #[serde(default = "get_sampler_2d")]
+    pub sampler_type: SamplerType,
+}
+
+/// The type of texture an input channel accepts. This must match the
+/// node connected to it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum SamplerType {
+    /// A normal texture, such as those from `RenderPass` and `Image`
+    Sampler2D,
+    /// A texture from a `Cubemap`
+    SamplerCube,
+    /// A texture from a `TextureArray`
+    Sampler2DArray,
+    /// An unsigned integer texture, such as a tile layer from a `TiledMap`
+    USampler2D,
+}
+
+impl SamplerType {
+    /// The GLSL type used to declare a uniform of this type
+    pub fn to_glsl_type(&self) -> &'static str {
+        match self {
+            Self::Sampler2D => "sampler2D",
+            Self::SamplerCube => "samplerCube",
+            Self::Sampler2DArray => "sampler2DArray",
+            Self::USampler2D => "usampler2D",
+        }
+    }
+
+    /// The texture target this should be bound to
+    pub fn to_texture_target(&self) -> u32 {
+        match self {
+            Self::Sampler2D => glow::TEXTURE_2D,
+            Self::SamplerCube => glow::TEXTURE_CUBE_MAP,
+            Self::Sampler2DArray => glow::TEXTURE_2D_ARRAY,
+            Self::USampler2D => glow::TEXTURE_2D,
+        }
+    }
+}
+
+/// A typed value that can be uploaded to a shader uniform
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum UniformValue {
+    Float(f32),
+    Int(i32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+
+    /// A linear RGBA colour. This is a `vec4` in the shader.
+    Color([f32; 4]),
+    Bool(bool),
+}
+
+impl UniformValue {
+    /// The GLSL type used to declare a uniform holding this value
+    pub fn to_glsl_type(&self) -> &'static str {
+        match self {
+            Self::Float(_) => "float",
+            Self::Int(_) => "int",
+            Self::Vec2(_) => "vec2",
+            Self::Vec3(_) => "vec3",
+            Self::Vec4(_) => "vec4",
+            Self::Color(_) => "vec4",
+            Self::Bool(_) => "bool",
+        }
+    }
+
+    /// Whether a value can be stored in a uniform declared with this value's type
+    pub fn is_same_type(&self, other: &UniformValue) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
 }
 
 /// How the resolution of a `RenderPass` is configured
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ResolutionScalingMode {
     /// The resolution is a fixed size and does not change
     /// These are i32's because for some reason it's the way openGL wants it
@@ -159,6 +943,7 @@ pub enum ResolutionScalingMode {
 
 /// When should the renderpass execute
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ExecutionMode {
     /// Draw on every frame
     Always,
@@ -178,6 +963,7 @@ pub enum ExecutionMode {
 /// For a list of supposedly working ones see:
 /// https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texImage2D
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[allow(non_camel_case_types)]
 pub enum OutputBufferFormat {
     R8,
@@ -445,10 +1231,43 @@ impl OutputBufferFormat {
             Self::RGBA32UI => 4,
         }
     }
+
+    /// The number of bytes a single texel takes up when uploaded using
+    /// `to_format` and `to_type`
+    pub fn to_bytes_per_pixel(&self) -> usize {
+        match self.to_type() {
+            glow::UNSIGNED_BYTE | glow::BYTE => self.to_channel_count(),
+            glow::UNSIGNED_SHORT | glow::SHORT | glow::HALF_FLOAT => self.to_channel_count() * 2,
+            glow::UNSIGNED_INT | glow::INT | glow::FLOAT => self.to_channel_count() * 4,
+            glow::UNSIGNED_SHORT_5_6_5 | glow::UNSIGNED_SHORT_4_4_4_4 => 2,
+            // The remaining types pack a whole texel into 32 bits
+            _ => 4,
+        }
+    }
 }
 
 
 /// Helper function for serde defaults
 fn get_false() -> bool{
     false
-}
\ No newline at end of file
+}
+
+/// Helper function for serde defaults
+fn get_sampler_2d() -> SamplerType {
+    SamplerType::Sampler2D
+}
+
+/// Helper function for serde defaults
+fn get_linear() -> ColorSpace {
+    ColorSpace::Linear
+}
+
+/// Helper function for serde defaults
+fn get_srgb() -> ColorSpace {
+    ColorSpace::Srgb
+}
+
+/// Helper function for serde defaults
+fn get_printable_ascii() -> String {
+    (32..=126u8).map(|c| c as char).collect()
+}
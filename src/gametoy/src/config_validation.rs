@@ -0,0 +1,829 @@
+/*!
+Config Validation
+-----------------
+
+`ConfigFile::validate` checks a project for problems before any GPU resources
+are created. `GameToy::new` stops at the first problem it runs into, and some
+problems (such as a link to a slot that doesn't exist) only show up once the
+game is running, if at all. Validation reports everything it can find:
+
+ - Errors stop the project from running correctly: duplicate names, links to
+   nodes or slots that don't exist, missing files and textures linked to an
+   input that can't read them.
+ - Warnings run, but probably aren't what was intended: inputs that are never
+   linked, nodes that don't contribute to any output, renderpass outputs that
+   are never read and formats that can't be rendered to in WebGL.
+ - Info points out behaviour that is easy to miss, such as a renderpass
+   reading the previous frame of a node that runs after it.
+
+Validation only looks at the config file, which files exist and the sprite
+sheets of atlases, as those add to the shaders. It doesn't decode images or
+compile shaders.
+
+!*/
+
+use crate::config_file::{
+    AnimationSource, ColorSpace, ConfigFile, CubemapFaces, ExecutionMode, FontSource, Node,
+    OutputBufferFormat, ReadbackFormat, RenderPassConfig, ResolutionScalingMode, SamplerType,
+};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::gamedata::GameData;
+use crate::nodes::{linked_sprite_defines, Atlas, Font, NodeError};
+use crate::preprocessor::Preprocessor;
+use std::collections::{HashMap, HashSet};
+
+impl ConfigFile {
+    /// Checks the project for problems. The diagnostics are in the order
+    /// the problems were found, which is roughly the order of the nodes.
+    pub fn validate(&self, gamedata: &GameData) -> Vec<Diagnostic> {
+        let mut validator = Validator {
+            config: self,
+            gamedata,
+            diagnostics: vec![],
+        };
+        validator.check_names();
+        for node in self.graph.nodes.iter() {
+            validator.check_node(node);
+        }
+        validator.check_links();
+        validator.check_reachability();
+        validator.diagnostics
+    }
+}
+
+/// The texels stored in a texture, which decides what can read it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TexelType {
+    Normalized,
+    Float,
+    UnsignedInt,
+    SignedInt,
+}
+
+impl TexelType {
+    fn from_format(format: &OutputBufferFormat) -> Self {
+        let integer = matches!(
+            format.to_format(),
+            glow::RED_INTEGER | glow::RG_INTEGER | glow::RGB_INTEGER | glow::RGBA_INTEGER
+        );
+        match format.to_type() {
+            glow::BYTE | glow::SHORT | glow::INT if integer => Self::SignedInt,
+            _ if integer => Self::UnsignedInt,
+            glow::FLOAT
+            | glow::HALF_FLOAT
+            | glow::UNSIGNED_INT_10F_11F_11F_REV
+            | glow::UNSIGNED_INT_5_9_9_9_REV => Self::Float,
+            _ => Self::Normalized,
+        }
+    }
+}
+
+/// The texture an output slot produces, as far as can be told without
+/// loading any files
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextureType {
+    /// The texel type is None if it depends on the contents of a file
+    Texture2D(Option<TexelType>),
+    Cube,
+    Array,
+}
+
+impl TextureType {
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Texture2D(None) => "a texture",
+            Self::Texture2D(Some(TexelType::Normalized)) => "a normalised texture",
+            Self::Texture2D(Some(TexelType::Float)) => "a floating point texture",
+            Self::Texture2D(Some(TexelType::UnsignedInt)) => "an unsigned integer texture",
+            Self::Texture2D(Some(TexelType::SignedInt)) => "a signed integer texture",
+            Self::Cube => "a cubemap",
+            Self::Array => "a texture array",
+        }
+    }
+
+    /// Whether the texture can be read by a normal sampler, and so
+    /// displayed or blended
+    fn is_floating(&self) -> bool {
+        matches!(
+            self,
+            Self::Texture2D(None | Some(TexelType::Normalized) | Some(TexelType::Float))
+        )
+    }
+}
+
+struct Validator<'a> {
+    config: &'a ConfigFile,
+    gamedata: &'a GameData,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, severity: Severity, node: &str, message: String) {
+        self.diagnostics
+            .push(Diagnostic::new(severity, message).in_node(node));
+    }
+
+    /// Reports a problem with a file used by a node
+    fn report_file(&mut self, severity: Severity, node: &str, file: &str, message: String) {
+        let mut diagnostic = Diagnostic::new(severity, message).in_node(node);
+        diagnostic.file = Some(file.to_string());
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn find_node(&self, name: &str) -> Option<(usize, &'a Node)> {
+        self.config
+            .graph
+            .nodes
+            .iter()
+            .enumerate()
+            .find(|(_, node)| node.get_name() == name)
+    }
+
+    fn check_names(&mut self) {
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+        for node in self.config.graph.nodes.iter() {
+            let name = node.get_name();
+            if name.is_empty() {
+                self.diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    "a node has no name, so it can't be linked to".to_string(),
+                ));
+            }
+            if !seen.insert(name) && reported.insert(name) {
+                self.report(
+                    Severity::Error,
+                    name,
+                    "more than one node has this name".to_string(),
+                );
+            }
+        }
+
+        let outputs: Vec<&str> = self
+            .config
+            .graph
+            .nodes
+            .iter()
+            .filter(|node| matches!(node, Node::Output(_)))
+            .map(Node::get_name)
+            .collect();
+        if outputs.is_empty() {
+            self.diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                "there is no Output node, so nothing will be displayed".to_string(),
+            ));
+        }
+        for name in outputs.iter().skip(1) {
+            self.report(
+                Severity::Error,
+                name,
+                format!(
+                    "\"{}\" is already an Output node, and only one can be displayed",
+                    outputs[0]
+                ),
+            );
+        }
+    }
+
+    fn check_node(&mut self, node: &Node) {
+        let name = node.get_name();
+        match node {
+            Node::Image(config) => self.require_texture(name, &config.path),
+            Node::RenderPass(config) => self.check_renderpass(config),
+            Node::Output(_) | Node::Keyboard(_) => {}
+            Node::Cubemap(config) => match &config.faces {
+                CubemapFaces::Separate {
+                    positive_x,
+                    negative_x,
+                    positive_y,
+                    negative_y,
+                    positive_z,
+                    negative_z,
+                } => {
+                    for path in [
+                        positive_x, negative_x, positive_y, negative_y, positive_z, negative_z,
+                    ] {
+                        self.require_texture(name, path);
+                    }
+                }
+                CubemapFaces::Cross(path) => self.require_texture(name, path),
+            },
+            Node::TextureArray(config) => {
+                if config.paths.is_empty() {
+                    self.report(
+                        Severity::Error,
+                        name,
+                        "texture array has no layers".to_string(),
+                    );
+                }
+                for path in config.paths.iter() {
+                    self.require_texture(name, path);
+                }
+            }
+            Node::AnimatedImage(config) => {
+                match &config.source {
+                    AnimationSource::Apng(path) => self.require_texture(name, path),
+                    AnimationSource::Sequence(pattern) => self.check_sequence(name, pattern),
+                }
+                if config.fps <= 0.0 {
+                    self.report(
+                        Severity::Warning,
+                        name,
+                        format!("fps is {}, so the animation will never advance", config.fps),
+                    );
+                }
+            }
+            Node::Atlas(config) => {
+                self.require_texture(name, &config.path);
+                self.require_data_file(name, &config.sheet_path);
+            }
+            Node::Font(config) => {
+                if let FontSource::File(path) = &config.source {
+                    self.require_data_file(name, path);
+                }
+                if config.characters.is_empty() {
+                    self.report(
+                        Severity::Error,
+                        name,
+                        "font has no characters to rasterise".to_string(),
+                    );
+                }
+                if config.size <= 0.0 {
+                    self.report(
+                        Severity::Error,
+                        name,
+                        format!("font size must be positive, not {}", config.size),
+                    );
+                }
+            }
+            Node::TiledMap(config) => {
+                self.require_data_file(name, &config.path);
+                if config.layers.is_empty() {
+                    self.report(
+                        Severity::Warning,
+                        name,
+                        "map doesn't output any layers".to_string(),
+                    );
+                }
+                self.check_unique(name, "layer", config.layers.iter());
+            }
+            Node::DataTable(config) => self.require_data_file(name, &config.path),
+            Node::HostData(config) => {
+                if config.size.contains(&0) {
+                    self.report(
+                        Severity::Error,
+                        name,
+                        format!("texture size {:?} is empty", config.size),
+                    );
+                }
+            }
+            Node::Readback(config) => {
+                if config.size.contains(&0) {
+                    self.report(
+                        Severity::Warning,
+                        name,
+                        format!("region size {:?} is empty, so nothing is read", config.size),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_renderpass(&mut self, config: &RenderPassConfig) {
+        let name = &config.name;
+
+        if config.output_texture_slots.is_empty() {
+            self.report(
+                Severity::Warning,
+                name,
+                "renderpass has no output slots, so nothing can read what it draws".to_string(),
+            );
+        }
+        self.check_unique(
+            name,
+            "input slot",
+            config.input_texture_slots.iter().map(|slot| &slot.name),
+        );
+        self.check_unique(
+            name,
+            "output slot",
+            config.output_texture_slots.iter().map(|slot| &slot.name),
+        );
+
+        for slot in config.output_texture_slots.iter() {
+            if !is_webgl_renderable(&slot.format) {
+                self.report(
+                    Severity::Warning,
+                    name,
+                    format!(
+                        "output slot \"{}\" uses {:?}, which can't be rendered to in WebGL",
+                        slot.name, slot.format
+                    ),
+                );
+            }
+            let texel_type = TexelType::from_format(&slot.format);
+            if slot.generate_mipmap
+                && matches!(texel_type, TexelType::UnsignedInt | TexelType::SignedInt)
+            {
+                self.report(
+                    Severity::Warning,
+                    name,
+                    format!(
+                        "output slot \"{}\" uses the integer format {:?}, which can't have mipmaps",
+                        slot.name, slot.format
+                    ),
+                );
+            }
+        }
+
+        let valid_resolution = match config.resolution_scaling_mode {
+            ResolutionScalingMode::Fixed(x, y) => x > 0 && y > 0,
+            ResolutionScalingMode::ViewportScale(x, y) => x > 0.0 && y > 0.0,
+        };
+        if !valid_resolution {
+            self.report(
+                Severity::Error,
+                name,
+                format!(
+                    "resolution {:?} must be positive",
+                    config.resolution_scaling_mode
+                ),
+            );
+        }
+
+        if config.fragment_shader_paths.is_empty() {
+            self.report(
+                Severity::Error,
+                name,
+                "renderpass has no fragment shaders".to_string(),
+            );
+        }
+        // Expanding the includes the same way the renderpass will finds
+        // missing includes and cycles
+        let mut preprocessor = Preprocessor::new(&self.gamedata.shader_sources);
+        for path in config.fragment_shader_paths.iter() {
+            if self.gamedata.get_shader_source(path).is_none() {
+                self.report_file(Severity::Error, name, path, "file not found".to_string());
+                continue;
+            }
+            if let Err(err) = preprocessor.expand(path) {
+                self.report_include_error(name, path, err);
+            }
+        }
+
+        // Sprite defines are added to the shader, so a clash stops it compiling.
+        // Missing sprite sheets are reported by the atlas.
+        if let Err(err @ NodeError::DuplicateSpriteDefine(..)) =
+            linked_sprite_defines(&self.config.graph, name, self.gamedata)
+        {
+            self.report(Severity::Error, name, err.to_string());
+        }
+
+        self.check_unique(
+            name,
+            "uniform",
+            config.uniforms.iter().map(|uniform| &uniform.name),
+        );
+        for uniform in config.uniforms.iter() {
+            if let (Some(min), Some(max)) = (uniform.min, uniform.max) {
+                if min > max {
+                    self.report(
+                        Severity::Warning,
+                        name,
+                        format!(
+                            "uniform \"{}\" has a min of {} which is larger than it's max of {}",
+                            uniform.name, min, max
+                        ),
+                    );
+                    continue;
+                }
+            }
+            if uniform.clamp(&uniform.value) != uniform.value {
+                self.report(
+                    Severity::Warning,
+                    name,
+                    format!(
+                        "uniform \"{}\" starts at {:?}, which is outside it's min and max",
+                        uniform.name, uniform.value
+                    ),
+                );
+            }
+        }
+    }
+
+    fn report_include_error(&mut self, node: &str, path: &str, err: NodeError) {
+        match err {
+            NodeError::MissingResource(include) => self.report_file(
+                Severity::Error,
+                node,
+                path,
+                format!("included file \"{}\" not found", include),
+            ),
+            NodeError::IncludeCycle(cycle) => self.report_file(
+                Severity::Error,
+                node,
+                path,
+                format!("files include each other: {}", cycle.join(" -> ")),
+            ),
+            NodeError::MalformedInclude(file, line) => {
+                let mut diagnostic = Diagnostic::new(
+                    Severity::Error,
+                    "#include must be followed by a quoted path".to_string(),
+                )
+                .in_node(node);
+                diagnostic.file = Some(file);
+                diagnostic.line = Some(line);
+                self.diagnostics.push(diagnostic);
+            }
+            err => self.report_file(Severity::Error, node, path, err.to_string()),
+        }
+    }
+
+    /// Reports any names that appear more than once
+    fn check_unique<'b>(
+        &mut self,
+        node: &str,
+        what: &str,
+        names: impl Iterator<Item = &'b String>,
+    ) {
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+        for name in names {
+            if !seen.insert(name) && reported.insert(name) {
+                self.report(
+                    Severity::Error,
+                    node,
+                    format!("more than one {} is called \"{}\"", what, name),
+                );
+            }
+        }
+    }
+
+    fn require_texture(&mut self, node: &str, path: &str) {
+        if self.gamedata.get_texture(path).is_none() {
+            self.report_file(Severity::Error, node, path, "image not found".to_string());
+        }
+    }
+
+    fn require_data_file(&mut self, node: &str, path: &str) {
+        if self.gamedata.get_file(path).is_none() {
+            self.report_file(Severity::Error, node, path, "file not found".to_string());
+        }
+    }
+
+    /// Checks that the first frame of a numbered sequence exists, numbering
+    /// from zero or one as `AnimatedImage` does
+    fn check_sequence(&mut self, node: &str, pattern: &str) {
+        let start = match pattern.find('#') {
+            Some(start) => start,
+            None => {
+                self.report_file(
+                    Severity::Error,
+                    node,
+                    pattern,
+                    "sequence has no run of '#' to replace with the frame number".to_string(),
+                );
+                return;
+            }
+        };
+        let digits = pattern[start..].chars().take_while(|c| *c == '#').count();
+        let frame_path = |frame_number: usize| {
+            format!(
+                "{}{:0width$}{}",
+                &pattern[..start],
+                frame_number,
+                &pattern[start + digits..],
+                width = digits
+            )
+        };
+        if self.gamedata.get_texture(&frame_path(0)).is_none()
+            && self.gamedata.get_texture(&frame_path(1)).is_none()
+        {
+            self.report_file(
+                Severity::Error,
+                node,
+                pattern,
+                format!("no frames found, expected \"{}\"", frame_path(0)),
+            );
+        }
+    }
+
+    fn check_links(&mut self) {
+        let mut linked_inputs: HashMap<(&str, &str), usize> = HashMap::new();
+
+        for link in self.config.graph.links.iter() {
+            let start = self.find_node(&link.start_node);
+            let end = self.find_node(&link.end_node);
+            if start.is_none() {
+                self.report(
+                    Severity::Error,
+                    &link.end_node,
+                    format!(
+                        "input slot \"{}\" is linked from \"{}\", which doesn't exist",
+                        link.end_input_slot, link.start_node
+                    ),
+                );
+            }
+            if end.is_none() {
+                self.report(
+                    Severity::Error,
+                    &link.start_node,
+                    format!(
+                        "output slot \"{}\" is linked to \"{}\", which doesn't exist",
+                        link.start_output_slot, link.end_node
+                    ),
+                );
+            }
+            let ((start_index, start_node), (end_index, end_node)) = match (start, end) {
+                (Some(start), Some(end)) => (start, end),
+                _ => continue,
+            };
+
+            let has_output = start_node
+                .get_output_slots()
+                .contains(&link.start_output_slot);
+            let has_input = end_node.get_input_slots().contains(&link.end_input_slot);
+            if !has_output {
+                self.report(
+                    Severity::Error,
+                    &link.start_node,
+                    format!(
+                        "there is no output slot \"{}\" to link to \"{}\"",
+                        link.start_output_slot, link.end_node
+                    ),
+                );
+            }
+            if !has_input {
+                self.report(
+                    Severity::Error,
+                    &link.end_node,
+                    format!(
+                        "there is no input slot \"{}\" to link from \"{}\"",
+                        link.end_input_slot, link.start_node
+                    ),
+                );
+            }
+            if !(has_output && has_input) {
+                continue;
+            }
+
+            let count = linked_inputs
+                .entry((&link.end_node, &link.end_input_slot))
+                .or_insert(0);
+            *count += 1;
+            if *count == 2 {
+                self.report(
+                    Severity::Warning,
+                    &link.end_node,
+                    format!(
+                        "input slot \"{}\" is linked more than once, only the last link is used",
+                        link.end_input_slot
+                    ),
+                );
+            }
+
+            if let Some(texture_type) = output_texture_type(start_node, &link.start_output_slot) {
+                self.check_link_type(
+                    end_node,
+                    &link.end_input_slot,
+                    &link.start_node,
+                    texture_type,
+                );
+            }
+            if start_index > end_index {
+                self.check_order(end_node, &link.start_node);
+            }
+        }
+
+        for node in self.config.graph.nodes.iter() {
+            for slot in node.get_input_slots() {
+                if !linked_inputs.contains_key(&(node.get_name(), slot.as_str())) {
+                    self.report(
+                        Severity::Warning,
+                        node.get_name(),
+                        format!("input slot \"{}\" isn't linked to anything", slot),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Checks that the texture from `start_node` can be read by the input slot
+    fn check_link_type(
+        &mut self,
+        end_node: &Node,
+        input_slot: &str,
+        start_node: &str,
+        texture_type: TextureType,
+    ) {
+        let (accepted, expected) = match end_node {
+            Node::RenderPass(config) => {
+                let sampler_type = match config
+                    .input_texture_slots
+                    .iter()
+                    .find(|slot| slot.name == input_slot)
+                {
+                    Some(slot) => &slot.sampler_type,
+                    None => return,
+                };
+                let accepted = match sampler_type {
+                    SamplerType::Sampler2D => texture_type.is_floating(),
+                    SamplerType::USampler2D => matches!(
+                        texture_type,
+                        TextureType::Texture2D(None | Some(TexelType::UnsignedInt))
+                    ),
+                    SamplerType::SamplerCube => texture_type == TextureType::Cube,
+                    SamplerType::Sampler2DArray => texture_type == TextureType::Array,
+                };
+                (accepted, format!("a {:?} input", sampler_type))
+            }
+            Node::Output(_) => (
+                texture_type.is_floating(),
+                "the Output node, which can only display normalised and floating point textures"
+                    .to_string(),
+            ),
+            Node::Readback(config) => {
+                let texel_type = match config.format {
+                    ReadbackFormat::RGBA8 => TexelType::Normalized,
+                    ReadbackFormat::RGBA32F => TexelType::Float,
+                    ReadbackFormat::RGBA32I => TexelType::SignedInt,
+                    ReadbackFormat::RGBA32UI => TexelType::UnsignedInt,
+                };
+                let accepted = matches!(texture_type, TextureType::Texture2D(None))
+                    || texture_type == TextureType::Texture2D(Some(texel_type));
+                (
+                    accepted,
+                    format!(
+                        "a Readback in the {:?} format, which reads {}",
+                        config.format,
+                        TextureType::Texture2D(Some(texel_type)).describe()
+                    ),
+                )
+            }
+            _ => return,
+        };
+        if !accepted {
+            self.report(
+                Severity::Error,
+                end_node.get_name(),
+                format!(
+                    "input slot \"{}\" is linked to \"{}\", which is {}, but this is {}",
+                    input_slot,
+                    start_node,
+                    texture_type.describe(),
+                    expected
+                ),
+            );
+        }
+    }
+
+    /// Reports reading from a node that runs later in the frame
+    fn check_order(&mut self, end_node: &Node, start_node: &str) {
+        match end_node {
+            Node::RenderPass(config) if matches!(config.execution_mode, ExecutionMode::CreationOrResized) => {
+                self.report(
+                    Severity::Warning,
+                    &config.name,
+                    format!(
+                        "only runs when created or resized, which is before \"{}\" has drawn anything",
+                        start_node
+                    ),
+                )
+            }
+            Node::RenderPass(config) => self.report(
+                Severity::Info,
+                &config.name,
+                format!(
+                    "reads the previous frame of \"{}\", as it runs later",
+                    start_node
+                ),
+            ),
+            _ => self.report(
+                Severity::Warning,
+                end_node.get_name(),
+                format!(
+                    "runs before \"{}\", so it is always a frame behind",
+                    start_node
+                ),
+            ),
+        }
+    }
+
+    /// Reports nodes that nothing is displayed or read back from, and
+    /// renderpass outputs that are drawn but never read
+    fn check_reachability(&mut self) {
+        let nodes = &self.config.graph.nodes;
+        let mut reached: HashSet<&str> = nodes
+            .iter()
+            .filter(|node| matches!(node, Node::Output(_) | Node::Readback(_)))
+            .map(Node::get_name)
+            .collect();
+        if reached.is_empty() {
+            return;
+        }
+
+        // Walk the links backwards from the outputs until nothing changes
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for link in self.config.graph.links.iter() {
+                if reached.contains(link.end_node.as_str())
+                    && reached.insert(link.start_node.as_str())
+                {
+                    changed = true;
+                }
+            }
+        }
+
+        for node in nodes.iter() {
+            if !reached.contains(node.get_name()) {
+                self.report(
+                    Severity::Warning,
+                    node.get_name(),
+                    "doesn't contribute to any Output or Readback node".to_string(),
+                );
+                continue;
+            }
+
+            // Other nodes make their outputs whether or not they are read
+            if let Node::RenderPass(config) = node {
+                for slot in config.output_texture_slots.iter() {
+                    let is_read = self.config.graph.links.iter().any(|link| {
+                        link.start_node == config.name && link.start_output_slot == slot.name
+                    });
+                    if !is_read {
+                        self.report(
+                            Severity::Warning,
+                            &config.name,
+                            format!("output slot \"{}\" isn't read by anything", slot.name),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The type of texture a node outputs through a slot, or None if the slot
+/// doesn't exist
+fn output_texture_type(node: &Node, slot: &str) -> Option<TextureType> {
+    let from_color_space = |color_space: &ColorSpace| match color_space {
+        // Only 8 bit images can be stored as sRGB
+        ColorSpace::Srgb => TextureType::Texture2D(Some(TexelType::Normalized)),
+        // 16 bit images are stored as unsigned integers
+        ColorSpace::Linear => TextureType::Texture2D(None),
+    };
+    let texture_type = match node {
+        Node::Image(config) => from_color_space(&config.color_space),
+        Node::AnimatedImage(config) => from_color_space(&config.color_space),
+        Node::Atlas(config) if slot == Atlas::OUTPUT_BUFFER_NAME => {
+            from_color_space(&config.color_space)
+        }
+        Node::Atlas(_) => TextureType::Texture2D(Some(TexelType::Float)),
+        Node::Font(_) if slot == Font::OUTPUT_BUFFER_NAME => {
+            TextureType::Texture2D(Some(TexelType::Normalized))
+        }
+        Node::Font(_) => TextureType::Texture2D(Some(TexelType::Float)),
+        Node::Keyboard(_) => TextureType::Texture2D(Some(TexelType::Normalized)),
+        Node::Cubemap(_) => TextureType::Cube,
+        Node::TextureArray(_) => TextureType::Array,
+        // Tile layers are integers and object layers are floats. Which is
+        // which is only known once the map is loaded.
+        Node::TiledMap(_) => TextureType::Texture2D(None),
+        Node::DataTable(_) => TextureType::Texture2D(Some(TexelType::Float)),
+        Node::HostData(config) => {
+            TextureType::Texture2D(Some(TexelType::from_format(&config.format)))
+        }
+        Node::RenderPass(config) => {
+            let slot = config
+                .output_texture_slots
+                .iter()
+                .find(|output| output.name == slot)?;
+            TextureType::Texture2D(Some(TexelType::from_format(&slot.format)))
+        }
+        Node::Output(_) | Node::Readback(_) => return None,
+    };
+    Some(texture_type)
+}
+
+/// Whether a format can be rendered to in WebGL 2 with the
+/// `EXT_color_buffer_float` extension
+fn is_webgl_renderable(format: &OutputBufferFormat) -> bool {
+    !matches!(
+        format,
+        OutputBufferFormat::R8_SNORM
+            | OutputBufferFormat::RG8_SNORM
+            | OutputBufferFormat::RGB8_SNORM
+            | OutputBufferFormat::RGBA8_SNORM
+            | OutputBufferFormat::SRGB8
+            | OutputBufferFormat::RGB9_E5
+            | OutputBufferFormat::RGB16F
+            | OutputBufferFormat::RGB32F
+            | OutputBufferFormat::RGB8UI
+            | OutputBufferFormat::RGB8I
+            | OutputBufferFormat::RGB16UI
+            | OutputBufferFormat::RGB16I
+            | OutputBufferFormat::RGB32UI
+            | OutputBufferFormat::RGB32I
+    )
+}
@@ -0,0 +1,251 @@
+/*!
+Data Sources
+------------
+
+`GameData` can be loaded from anywhere that implements `GameDataSource`:
+
+ - A `tar::Archive`, which is how datapacks are bundled for the web. Use
+   `open_tar` so that tars compressed with gzip or zstd are decompressed.
+ - A `zip::ZipArchive`, which is how games are usually shipped on sites
+   like itch.io.
+ - A `Directory`, which is read directly so a project can be run while it
+   is being worked on without packing it first.
+
+Whatever the source, files are identified by their path relative to the
+root of the source with `/` between folders, eg `textures/player.png`. Paths
+that aren't valid UTF-8 can't be named in a config file, so are an error.
+
+!*/
+
+use crate::gamedata::GameDataError;
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// Called with the path and contents of each file in a source
+pub type FileVisitor<'a> = dyn FnMut(&str, &mut dyn Read) -> Result<(), GameDataError> + 'a;
+
+/// Somewhere the files making up a game can be read from
+pub trait GameDataSource {
+    /// Calls `visit` for every file in the source. Folders themselves aren't
+    /// visited. Stops at the first error, from either the source or `visit`.
+    fn visit_files(&mut self, visit: &mut FileVisitor) -> Result<(), GameDataError>;
+}
+
+impl<R: Read> GameDataSource for tar::Archive<R> {
+    fn visit_files(&mut self, visit: &mut FileVisitor) -> Result<(), GameDataError> {
+        let entries = self.entries().map_err(GameDataError::TarError)?;
+        for file in entries {
+            // Make sure there wasn't an I/O error
+            let mut file = file.map_err(GameDataError::TarError)?;
+            if !file.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = {
+                let path = file.header().path().map_err(GameDataError::TarError)?;
+                path.to_str()
+                    .ok_or_else(|| GameDataError::InvalidPath(path.to_string_lossy().into()))?
+                    .to_string()
+            };
+            visit(&path, &mut file)?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> GameDataSource for zip::ZipArchive<R> {
+    fn visit_files(&mut self, visit: &mut FileVisitor) -> Result<(), GameDataError> {
+        for index in 0..self.len() {
+            let mut file = self.by_index(index).map_err(GameDataError::ZipError)?;
+            if !file.is_file() {
+                continue;
+            }
+            let path = file.name().to_string();
+            visit(&path, &mut file)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: GameDataSource + ?Sized> GameDataSource for Box<S> {
+    fn visit_files(&mut self, visit: &mut FileVisitor) -> Result<(), GameDataError> {
+        (**self).visit_files(visit)
+    }
+}
+
+/// Tidies a path from a source into the form used to look files up: `/`
+/// between folders, without a leading `./` or any `.` or empty folders.
+/// Archives made on Windows can use backslashes, which are also treated as `/`.
+/// A `..` that would leave the root of the source is an error.
+pub fn normalise_path(path: &str) -> Result<String, GameDataError> {
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if components.pop().is_none() {
+                    return Err(GameDataError::PathOutsideRoot(path.to_string()));
+                }
+            }
+            component => components.push(component),
+        }
+    }
+    Ok(components.join("/"))
+}
+
+/// The magic bytes at the start of a gzip stream
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// The magic bytes at the start of a zstd frame
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// How a datapack is compressed. Both decompressors are pure rust, so work
+/// in the web player as well as on desktop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Works out the compression from the first few bytes of the data
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(GZIP_MAGIC) {
+            Self::Gzip
+        } else if data.starts_with(ZSTD_MAGIC) {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Self::Zstd => Ok(ruzstd::encoding::compress_to_vec(
+                data,
+                ruzstd::encoding::CompressionLevel::Fastest,
+            )),
+        }
+    }
+}
+
+/// Wraps a reader so that gzip or zstd data is decompressed as it is read.
+/// Anything else is read as it is.
+pub fn decompress<'a, R: Read + 'a>(mut reader: R) -> Result<Box<dyn Read + 'a>, GameDataError> {
+    // Read enough to see the magic bytes, then put them back in front of
+    // the rest of the data
+    let mut start = Vec::with_capacity(ZSTD_MAGIC.len());
+    (&mut reader)
+        .take(ZSTD_MAGIC.len() as u64)
+        .read_to_end(&mut start)
+        .map_err(GameDataError::DecompressError)?;
+    let compression = Compression::detect(&start);
+    let reader = io::Cursor::new(start).chain(reader);
+
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+        Compression::Zstd => Box::new(
+            ruzstd::decoding::StreamingDecoder::new(reader).map_err(GameDataError::ZstdError)?,
+        ),
+    })
+}
+
+/// Opens a tar, which may be compressed with gzip or zstd
+pub fn open_tar<'a, R: Read + 'a>(
+    reader: R,
+) -> Result<tar::Archive<Box<dyn Read + 'a>>, GameDataError> {
+    Ok(tar::Archive::new(decompress(reader)?))
+}
+
+/// Opens game data on disk, which can be a folder, a `.zip` or a `.tar`.
+/// Anything that isn't a folder or a zip is assumed to be a tar, which may be
+/// compressed.
+pub fn open_path<P: AsRef<Path>>(path: P) -> Result<Box<dyn GameDataSource>, GameDataError> {
+    let path = path.as_ref();
+    if path.is_dir() {
+        return Ok(Box::new(Directory::new(path)));
+    }
+    let file = fs::File::open(path)
+        .map_err(|e| GameDataError::ReadError(path.display().to_string(), e))?;
+    let is_zip = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"));
+    if is_zip {
+        let archive = zip::ZipArchive::new(file).map_err(GameDataError::ZipError)?;
+        Ok(Box::new(archive))
+    } else {
+        Ok(Box::new(open_tar(file)?))
+    }
+}
+
+/// A folder on disk, including all the folders inside it. Symlinks to files
+/// are read, but symlinks to folders are skipped as they could loop back
+/// into the directory.
+pub struct Directory {
+    root: PathBuf,
+}
+
+impl Directory {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    fn visit_folder(&self, folder: &Path, visit: &mut FileVisitor) -> Result<(), GameDataError> {
+        let read_error = |e| GameDataError::ReadError(folder.display().to_string(), e);
+        // The file type of an entry is that of the symlink, not what it points to
+        let mut entries = fs::read_dir(folder)
+            .map_err(read_error)?
+            .map(|entry| entry.and_then(|entry| Ok((entry.path(), entry.file_type()?))))
+            .collect::<Result<Vec<(PathBuf, fs::FileType)>, _>>()
+            .map_err(read_error)?;
+        // So that files are always loaded in the same order
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (path, file_type) in entries {
+            if file_type.is_dir() {
+                self.visit_folder(&path, visit)?;
+            } else if path.is_file() {
+                let name = self.relative_path(&path)?;
+                let mut file = fs::File::open(&path)
+                    .map_err(|e| GameDataError::ReadError(path.display().to_string(), e))?;
+                visit(&name, &mut file)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The path of a file within the directory, with `/` between folders
+    /// whatever the platform uses
+    fn relative_path(&self, path: &Path) -> Result<String, GameDataError> {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let components = relative
+            .components()
+            .map(|component| {
+                component
+                    .as_os_str()
+                    .to_str()
+                    .ok_or_else(|| GameDataError::InvalidPath(relative.to_string_lossy().into()))
+            })
+            .collect::<Result<Vec<&str>, _>>()?;
+        Ok(components.join("/"))
+    }
+}
+
+impl GameDataSource for Directory {
+    fn visit_files(&mut self, visit: &mut FileVisitor) -> Result<(), GameDataError> {
+        let root = self.root.clone();
+        self.visit_folder(&root, visit)
+    }
+}
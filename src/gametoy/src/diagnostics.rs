@@ -0,0 +1,187 @@
+/*!
+Diagnostics
+-----------
+
+A problem found in a project, with enough context (file, line and column) to
+point the user at the cause. These are plain data so that every frontend can
+display them in whatever way suits it.
+
+Shader compilers report errors in an info log whose format depends on the
+driver. `parse_info_log` understands the common ones:
+
+ - Mesa: `0:12(5): error: 'foo' undeclared`
+ - NVIDIA: `0(12) : error C1008: undefined variable "foo"`
+ - ANGLE, AMD, Apple and most others: `ERROR: 0:12: 'foo' : undeclared identifier`
+
+Lines it doesn't understand are kept as diagnostics without a location, so
+nothing the driver says is lost.
+
+!*/
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Info => write!(f, "info"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+
+    /// The name of the node the problem was found in
+    pub node: Option<String>,
+
+    /// The file the problem is in. This is None if the problem is in code
+    /// generated by GameToy or the file isn't known, in which case `line`
+    /// refers to the complete shader that was compiled.
+    pub file: Option<String>,
+
+    /// Counting from one
+    pub line: Option<usize>,
+
+    /// Counting from one
+    pub column: Option<usize>,
+}
+
+impl Diagnostic {
+    /// A diagnostic with no location
+    pub fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            message,
+            node: None,
+            file: None,
+            line: None,
+            column: None,
+        }
+    }
+
+    /// Records which node the problem was found in
+    pub fn in_node(mut self, node: &str) -> Self {
+        self.node = Some(node.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(node) = &self.node {
+            write!(f, "\"{}\": ", node)?;
+        }
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}:", file, line)?,
+            (Some(file), None) => write!(f, "{}:", file)?,
+            (None, Some(line)) => write!(f, "<generated>:{}:", line)?,
+            (None, None) => {}
+        }
+        if let (Some(_), Some(column)) = (self.line, self.column) {
+            write!(f, "{}:", column)?;
+        }
+        if self.line.is_some() || self.file.is_some() {
+            write!(f, " ")?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Converts a shader compiler's info log into diagnostics. Lines refer to
+/// the shader text that was compiled.
+pub fn parse_info_log(log: &str) -> Vec<Diagnostic> {
+    log.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            parse_info_log_line(line).unwrap_or_else(|| match split_severity(line) {
+                Some((severity, message)) => Diagnostic::new(severity, message.trim().to_string()),
+                None if line.to_ascii_lowercase().contains("warning") => {
+                    Diagnostic::new(Severity::Warning, line.to_string())
+                }
+                None => Diagnostic::new(Severity::Error, line.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Parses a line with a location in any of the supported formats
+fn parse_info_log_line(line: &str) -> Option<Diagnostic> {
+    // ANGLE style, where the severity comes first
+    let (mut severity, rest) = match split_severity(line) {
+        Some((severity, rest)) => (Some(severity), rest.trim_start()),
+        None => (None, line),
+    };
+
+    // The first number is the index of the source string, which is always 0
+    // as GameToy only passes one.
+    let (_, rest) = split_number(rest)?;
+    let (line_number, column, rest) = if let Some(rest) = rest.strip_prefix(':') {
+        // Mesa and ANGLE: `0:12(5):` or `0:12:`
+        let (line_number, rest) = split_number(rest)?;
+        let (column, rest) = match rest.strip_prefix('(') {
+            Some(rest) => {
+                let (column, rest) = split_number(rest)?;
+                (Some(column), rest.strip_prefix(')')?)
+            }
+            None => (None, rest),
+        };
+        (line_number, column, rest)
+    } else {
+        // NVIDIA: `0(12) :`
+        let rest = rest.strip_prefix('(')?;
+        let (line_number, rest) = split_number(rest)?;
+        (line_number, None, rest.strip_prefix(')')?)
+    };
+
+    let mut message = rest.trim_start().strip_prefix(':')?.trim_start();
+    if severity.is_none() {
+        let (line_severity, rest) = split_severity(message)?;
+        severity = Some(line_severity);
+        message = rest.trim_start();
+    }
+
+    Some(Diagnostic {
+        severity: severity?,
+        message: message.to_string(),
+        node: None,
+        file: None,
+        line: Some(line_number),
+        column,
+    })
+}
+
+/// Splits a leading `error:`, `WARNING:` or `error C1008:` from a message
+fn split_severity(text: &str) -> Option<(Severity, &str)> {
+    let (word, rest) = text.split_once(':')?;
+    let word = word.trim();
+    // NVIDIA follows the severity with an error code
+    let word = word.split_whitespace().next()?;
+    let severity = match word.to_ascii_lowercase().as_str() {
+        "error" => Severity::Error,
+        "warning" => Severity::Warning,
+        "info" | "note" => Severity::Info,
+        _ => return None,
+    };
+    Some((severity, rest))
+}
+
+/// Splits the number at the start of some text from the rest of it
+fn split_number(text: &str) -> Option<(usize, &str)> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let number = text[..end].parse().ok()?;
+    Some((number, &text[end..]))
+}
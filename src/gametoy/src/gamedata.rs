@@ -1,78 +1,217 @@
-use serde_json;
 use std::collections::HashMap;
-use std::io::Read;
+use std::fmt;
 
 use super::config_file;
+use super::config_file::ConfigFileFormat;
+use super::data_source::{self, GameDataSource};
 
+/// The name of the config file in new projects. A datapack can instead
+/// have a `data.ron` or `data.toml`, see `ConfigFileFormat`, but only one.
 pub const CONFIG_FILE_NAME: &'static str = "data.json";
 
+/// Files with these extensions are stored in `GameData::shader_sources`
+const SHADER_FILE_EXTENSIONS: &[&str] = &[".frag", ".glsl"];
+
 #[derive(Debug)]
 pub enum GameDataError {
     TarError(std::io::Error),
-    ConfigFileParseError(serde_json::Error),
+    ZipError(zip::result::ZipError),
+    /// The start of the datapack could not be read to work out how it is
+    /// compressed
+    DecompressError(std::io::Error),
+    /// The datapack starts like a zstd frame, but the frame is invalid
+    ZstdError(ruzstd::decoding::errors::FrameDecoderError),
+    /// String is the name of the config file
+    ConfigFileParseError(String, config_file::ConfigFileError),
     MissingConfigFile,
+
+    /// The datapack has config files in more than one format. Strings are
+    /// the names of two of them
+    MultipleConfigFiles(String, String),
+
+    /// A file in the datapack has a path that isn't valid UTF-8.
+    /// String is the path with the invalid parts replaced
+    InvalidPath(String),
+
+    /// A path in the datapack, or one used to look a file up, has a `..`
+    /// that leaves the root of the datapack. String is the path
+    PathOutsideRoot(String),
+
+    /// Two files in the datapack have paths that are the same once
+    /// normalised, such as `shaders/a.glsl` and `shaders\\a.glsl`. Strings
+    /// are the two paths
+    DuplicatePath(String, String),
+
+    /// A file in the datapack could not be read, or a text file isn't
+    /// valid UTF-8. String is the path of the file
+    ReadError(String, std::io::Error),
+}
+
+impl fmt::Display for GameDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TarError(_)
+            | Self::ZipError(_)
+            | Self::DecompressError(_)
+            | Self::ZstdError(_) => {
+                write!(f, "the datapack could not be read")
+            }
+            Self::ConfigFileParseError(path, _) => {
+                write!(f, "{}: the config file could not be loaded", path)
+            }
+            Self::MissingConfigFile => {
+                let names: Vec<&str> = ConfigFileFormat::ALL
+                    .iter()
+                    .map(|format| format.file_name())
+                    .collect();
+                write!(f, "the datapack has no config file ({})", names.join(", "))
+            }
+            Self::MultipleConfigFiles(first, second) => write!(
+                f,
+                "the datapack has both {} and {}, but can only have one config file",
+                first, second
+            ),
+            Self::InvalidPath(path) => write!(f, "{}: the path isn't valid UTF-8", path),
+            Self::PathOutsideRoot(path) => {
+                write!(f, "{}: the path leaves the root of the datapack", path)
+            }
+            Self::DuplicatePath(first, second) => write!(
+                f,
+                "{} and {} are the same file, so only one can be in the datapack",
+                first, second
+            ),
+            Self::ReadError(path, _) => write!(f, "{}: the file could not be read", path),
+        }
+    }
+}
+
+impl std::error::Error for GameDataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::TarError(err) => Some(err),
+            Self::ZipError(err) => Some(err),
+            Self::DecompressError(err) => Some(err),
+            Self::ZstdError(err) => Some(err),
+            Self::ConfigFileParseError(_, err) => Some(err),
+            Self::ReadError(_, err) => Some(err),
+            Self::MissingConfigFile
+            | Self::MultipleConfigFiles(_, _)
+            | Self::InvalidPath(_)
+            | Self::PathOutsideRoot(_)
+            | Self::DuplicatePath(_, _) => None,
+        }
+    }
 }
 
 pub struct GameData {
     pub config_file: config_file::ConfigFile,
     pub textures: HashMap<String, Vec<u8>>,
     pub shader_sources: HashMap<String, String>,
+
+    /// Every other file, such as sprite sheets, fonts and maps. Files of
+    /// any type are kept so that nodes can load whatever they need.
+    pub files: HashMap<String, Vec<u8>>,
 }
 
 impl GameData {
-    pub fn from_tar<R>(mut data: tar::Archive<R>) -> Result<Self, GameDataError>
-    where
-        R: Read,
-    {
-        let mut config_file: Option<config_file::ConfigFile> = None;
+    /// Loads the config file and every other file from a source, such as a
+    /// `tar::Archive` or a `data_source::Directory`. Files are keyed by their
+    /// path from the root of the source, see `data_source::normalise_path`.
+    pub fn from_source<S: GameDataSource>(mut source: S) -> Result<Self, GameDataError> {
+        // The config file along with it's name
+        let mut config_file: Option<(String, config_file::ConfigFile)> = None;
         let mut textures = HashMap::new();
         let mut shader_sources = HashMap::new();
+        let mut files = HashMap::new();
+        // The path each file was found at, by normalised path
+        let mut source_paths: HashMap<String, String> = HashMap::new();
 
-        let entries = data.entries().map_err(GameDataError::TarError)?;
-        for file in entries {
-            // Make sure there wasn't an I/O error
-            let mut file = file.map_err(GameDataError::TarError)?;
-
-            // Inspect metadata about the file
-
-            let filename_string = {
-                let filename = file.header().path().map_err(GameDataError::TarError)?;
-                filename.to_str().unwrap().to_string()
-            };
+        source.visit_files(&mut |source_path, file| {
+            let filename_string = data_source::normalise_path(source_path)?;
+            if let Some(existing) =
+                source_paths.insert(filename_string.clone(), source_path.to_string())
+            {
+                return Err(GameDataError::DuplicatePath(
+                    existing,
+                    source_path.to_string(),
+                ));
+            }
             println!("Loading {:?}", filename_string);
 
-            if filename_string == CONFIG_FILE_NAME {
-                //config_file = ConfigFile::
-                // files implement the Read trait
-                //let mut s = String::new();
-                //file.read_to_string(&mut s).unwrap();
-
-                config_file = Some(
-                    serde_json::from_reader(file).map_err(GameDataError::ConfigFileParseError)?,
-                );
-            } else if filename_string.ends_with(".frag") {
+            let config_file_format = ConfigFileFormat::ALL
+                .iter()
+                .copied()
+                .find(|format| format.file_name() == filename_string);
+            if let Some(format) = config_file_format {
+                if let Some((existing, _)) = config_file.take() {
+                    return Err(GameDataError::MultipleConfigFiles(
+                        existing,
+                        filename_string,
+                    ));
+                }
+                let loaded = config_file::ConfigFile::from_reader(file, format)
+                    .map_err(|e| GameDataError::ConfigFileParseError(filename_string.clone(), e))?;
+                config_file = Some((filename_string, loaded));
+            } else if SHADER_FILE_EXTENSIONS
+                .iter()
+                .any(|extension| filename_string.ends_with(extension))
+            {
                 let file_string = {
                     let mut s = String::new();
-                    file.read_to_string(&mut s).unwrap();
+                    file.read_to_string(&mut s)
+                        .map_err(|e| GameDataError::ReadError(filename_string.clone(), e))?;
                     s
                 };
                 shader_sources.insert(filename_string, file_string);
             } else if filename_string.ends_with(".png") {
                 let file_data = {
                     let mut d = Vec::new();
-                    file.read_to_end(&mut d).unwrap();
+                    file.read_to_end(&mut d)
+                        .map_err(|e| GameDataError::ReadError(filename_string.clone(), e))?;
                     d
                 };
                 textures.insert(filename_string, file_data);
+            } else {
+                let file_data = {
+                    let mut d = Vec::new();
+                    file.read_to_end(&mut d)
+                        .map_err(|e| GameDataError::ReadError(filename_string.clone(), e))?;
+                    d
+                };
+                files.insert(filename_string, file_data);
             }
-        }
+            Ok(())
+        })?;
 
-        let config_file = config_file.ok_or(GameDataError::MissingConfigFile)?;
+        let (_, config_file) = config_file.ok_or(GameDataError::MissingConfigFile)?;
 
         Ok(Self {
             config_file,
             textures,
             shader_sources,
+            files,
         })
     }
+
+    /// The PNG at a path from the config file, which is normalised the same
+    /// way as the paths of the files in the datapack
+    pub fn get_texture(&self, path: &str) -> Option<&Vec<u8>> {
+        get_normalised(&self.textures, path)
+    }
+
+    /// The source of the shader at a path from the config file
+    pub fn get_shader_source(&self, path: &str) -> Option<&String> {
+        get_normalised(&self.shader_sources, path)
+    }
+
+    /// Any other file at a path from the config file
+    pub fn get_file(&self, path: &str) -> Option<&Vec<u8>> {
+        get_normalised(&self.files, path)
+    }
+}
+
+/// Looks a path up in one of the maps of files. Paths that leave the root of
+/// the datapack can't be in it.
+fn get_normalised<'a, T>(files: &'a HashMap<String, T>, path: &str) -> Option<&'a T> {
+    files.get(&data_source::normalise_path(path).ok()?)
 }
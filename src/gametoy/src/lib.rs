@@ -2,16 +2,26 @@ pub use glow;
 use glow::HasContext;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Read;
+use std::fmt;
 use std::rc::Rc;
 
 pub use tar;
+pub use zip;
 
 pub mod config_file;
+mod config_validation;
+pub mod data_source;
+pub mod diagnostics;
 pub mod gamedata;
+pub mod migration;
 pub mod nodes;
+pub mod preprocessor;
 mod quad;
 pub mod shader;
+pub mod shadertoy;
+pub mod source_map;
+#[cfg(feature = "validate")]
+pub mod validate;
 
 mod gamestate;
 
@@ -20,6 +30,11 @@ pub use gamestate::GameState;
 #[derive(Debug)]
 pub enum GameToyError {
     DataLoadError(gamedata::GameDataError),
+
+    /// `ConfigFile::validate` found errors in the config file. This contains
+    /// everything it found, including warnings.
+    InvalidConfig(Vec<diagnostics::Diagnostic>),
+
     QuadCreateError(quad::QuadError),
     NodeCreateError(String, nodes::NodeError),
     DuplicateNodeName(String),
@@ -28,10 +43,103 @@ pub enum GameToyError {
     BindInputTextureFailed(String, nodes::NodeError),
     SelfReferenceSetupFailed(String, nodes::NodeError),
 
+    /// Raised when the host application's data could not be uploaded to a node.
+    /// String is the name of the node
+    UploadNodeDataFailed(String, nodes::NodeError),
+
+    /// Raised when data read back from the GPU was requested from a node that
+    /// can't provide it. String is the name of the node
+    GetReadbackFailed(String, nodes::NodeError),
+
+    /// Raised when a uniform could not be set on a node.
+    /// String is the name of the node
+    SetUniformFailed(String, nodes::NodeError),
+
+    /// No renderpass declares a uniform with this name
+    NoSuchUniform(String),
+
     /// Raised whenever the internal mapping of node->links does not have an entry for a specific node.
     InvalidLinkVec(),
 }
 
+impl fmt::Display for GameToyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DataLoadError(_) => write!(f, "failed to load the game data"),
+            Self::InvalidConfig(diagnostics) => {
+                write!(f, "the config file has errors")?;
+                for diagnostic in diagnostics.iter() {
+                    if diagnostic.severity == diagnostics::Severity::Error {
+                        write!(f, "\n{}", diagnostic)?;
+                    }
+                }
+                Ok(())
+            }
+            Self::QuadCreateError(_) => write!(f, "failed to create the quad to draw with"),
+            Self::NodeCreateError(node, _) => write!(f, "failed to create node \"{}\"", node),
+            Self::DuplicateNodeName(node) => {
+                write!(f, "more than one node is called \"{}\"", node)
+            }
+            Self::NoSuchNodeName(node) => write!(f, "there is no node called \"{}\"", node),
+            Self::GetInputTextureFailed(node, _) => {
+                write!(f, "failed to find the texture to input to \"{}\"", node)
+            }
+            Self::BindInputTextureFailed(node, _) => {
+                write!(f, "failed to bind an input texture of \"{}\"", node)
+            }
+            Self::SelfReferenceSetupFailed(node, _) => {
+                write!(f, "failed to link \"{}\" to itself", node)
+            }
+            Self::UploadNodeDataFailed(node, _) => {
+                write!(f, "failed to upload data to \"{}\"", node)
+            }
+            Self::GetReadbackFailed(node, _) => {
+                write!(f, "failed to get the data read back by \"{}\"", node)
+            }
+            Self::SetUniformFailed(node, _) => {
+                write!(f, "failed to set a uniform of \"{}\"", node)
+            }
+            Self::NoSuchUniform(name) => {
+                write!(f, "no renderpass has a uniform called \"{}\"", name)
+            }
+            Self::InvalidLinkVec() => write!(f, "a node has no list of links"),
+        }
+    }
+}
+
+impl std::error::Error for GameToyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::DataLoadError(err) => Some(err),
+            Self::QuadCreateError(err) => Some(err),
+            Self::NodeCreateError(_, err)
+            | Self::GetInputTextureFailed(_, err)
+            | Self::BindInputTextureFailed(_, err)
+            | Self::SelfReferenceSetupFailed(_, err)
+            | Self::UploadNodeDataFailed(_, err)
+            | Self::GetReadbackFailed(_, err)
+            | Self::SetUniformFailed(_, err) => Some(err),
+            Self::InvalidConfig(_)
+            | Self::DuplicateNodeName(_)
+            | Self::NoSuchNodeName(_)
+            | Self::NoSuchUniform(_)
+            | Self::InvalidLinkVec() => None,
+        }
+    }
+}
+
+/// Describes an error followed by each of the errors that caused it, so that
+/// frontends can show the whole story rather than just the outermost error.
+pub fn describe_error(error: &dyn std::error::Error) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        description += &format!("\n  caused by: {}", cause);
+        source = cause.source();
+    }
+    description
+}
+
 type NodeRef = Rc<RefCell<Box<dyn nodes::Node>>>;
 
 struct Link {
@@ -56,22 +164,45 @@ pub struct GameToy {
 
     resolution: [i32; 2],
     resolution_dirty: bool,
+
+    /// Problems found while creating and running the game
+    diagnostics: Vec<diagnostics::Diagnostic>,
 }
 
 impl GameToy {
-    pub fn new<R>(
-        gl: &glow::Context,
-        data: tar::Archive<R>,
-        enable_output: bool,
-    ) -> Result<Self, GameToyError>
+    /// Loads the game from a source, such as a `tar::Archive`, a
+    /// `zip::ZipArchive` or a `data_source::Directory`, and creates it.
+    pub fn new<S>(gl: &glow::Context, data: S, enable_output: bool) -> Result<Self, GameToyError>
     where
-        R: Read,
+        S: data_source::GameDataSource,
     {
-        unsafe {
-            assert_eq!(gl.get_error(), glow::NO_ERROR);
+        let game_data =
+            gamedata::GameData::from_source(data).map_err(GameToyError::DataLoadError)?;
+        Self::from_game_data(gl, game_data, enable_output)
+    }
+
+    /// Creates the game from data that has already been loaded. The config
+    /// file is validated first, and nothing is created on the GPU if it
+    /// contains errors.
+    pub fn from_game_data(
+        gl: &glow::Context,
+        game_data: gamedata::GameData,
+        enable_output: bool,
+    ) -> Result<Self, GameToyError> {
+        let mut diagnostics = game_data.config_file.validate(&game_data);
+        if diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == diagnostics::Severity::Error)
+        {
+            return Err(GameToyError::InvalidConfig(diagnostics));
         }
 
-        let game_data = gamedata::GameData::from_tar(data).map_err(GameToyError::DataLoadError)?;
+        if let Some(error) = take_gl_error(gl) {
+            diagnostics.push(diagnostics::Diagnostic::new(
+                diagnostics::Severity::Error,
+                format!("{} was raised before GameToy was created", error),
+            ));
+        }
 
         let quad = quad::Quad::new(gl).map_err(GameToyError::QuadCreateError)?;
 
@@ -91,7 +222,10 @@ impl GameToy {
                     Rc::new(RefCell::new(Box::new(new_pass)))
                 }
                 config_file::Node::Output(output_config) => {
-                    let output = nodes::Output::create_from_config(gl, output_config);
+                    let output =
+                        nodes::Output::create_from_config(gl, output_config).map_err(|e| {
+                            GameToyError::NodeCreateError(output_config.name.clone(), e)
+                        })?;
                     let output_node: NodeRef = Rc::new(RefCell::new(Box::new(output)));
                     output_node_maybe = Some(output_node.clone());
                     output_node
@@ -106,9 +240,78 @@ impl GameToy {
                         .map_err(|e| GameToyError::NodeCreateError(image_config.name.clone(), e))?;
                     Rc::new(RefCell::new(Box::new(image)))
                 }
+                config_file::Node::Cubemap(cubemap_config) => {
+                    let cubemap =
+                        nodes::Cubemap::create_from_config(gl, &game_data, cubemap_config)
+                            .map_err(|e| {
+                                GameToyError::NodeCreateError(cubemap_config.name.clone(), e)
+                            })?;
+                    Rc::new(RefCell::new(Box::new(cubemap)))
+                }
+                config_file::Node::TextureArray(array_config) => {
+                    let array =
+                        nodes::TextureArray::create_from_config(gl, &game_data, array_config)
+                            .map_err(|e| {
+                                GameToyError::NodeCreateError(array_config.name.clone(), e)
+                            })?;
+                    Rc::new(RefCell::new(Box::new(array)))
+                }
+                config_file::Node::AnimatedImage(anim_config) => {
+                    let anim =
+                        nodes::AnimatedImage::create_from_config(gl, &game_data, anim_config)
+                            .map_err(|e| {
+                                GameToyError::NodeCreateError(anim_config.name.clone(), e)
+                            })?;
+                    Rc::new(RefCell::new(Box::new(anim)))
+                }
+                config_file::Node::Atlas(atlas_config) => {
+                    let atlas = nodes::Atlas::create_from_config(gl, &game_data, atlas_config)
+                        .map_err(|e| GameToyError::NodeCreateError(atlas_config.name.clone(), e))?;
+                    Rc::new(RefCell::new(Box::new(atlas)))
+                }
+                config_file::Node::Font(font_config) => {
+                    let font = nodes::Font::create_from_config(gl, &game_data, font_config)
+                        .map_err(|e| GameToyError::NodeCreateError(font_config.name.clone(), e))?;
+                    Rc::new(RefCell::new(Box::new(font)))
+                }
+                config_file::Node::TiledMap(map_config) => {
+                    let map = nodes::TiledMap::create_from_config(gl, &game_data, map_config)
+                        .map_err(|e| GameToyError::NodeCreateError(map_config.name.clone(), e))?;
+                    Rc::new(RefCell::new(Box::new(map)))
+                }
+                config_file::Node::DataTable(table_config) => {
+                    let table = nodes::DataTable::create_from_config(gl, &game_data, table_config)
+                        .map_err(|e| GameToyError::NodeCreateError(table_config.name.clone(), e))?;
+                    Rc::new(RefCell::new(Box::new(table)))
+                }
+                config_file::Node::HostData(host_config) => {
+                    let host_data = nodes::HostData::create_from_config(gl, host_config)
+                        .map_err(|e| GameToyError::NodeCreateError(host_config.name.clone(), e))?;
+                    Rc::new(RefCell::new(Box::new(host_data)))
+                }
+                config_file::Node::Readback(readback_config) => {
+                    let readback = nodes::Readback::create_from_config(gl, readback_config)
+                        .map_err(|e| {
+                            GameToyError::NodeCreateError(readback_config.name.clone(), e)
+                        })?;
+                    Rc::new(RefCell::new(Box::new(readback)))
+                }
             };
-            unsafe {
-                assert_eq!(gl.get_error(), glow::NO_ERROR);
+            {
+                let new_node = new_node.borrow();
+                let name = new_node.get_name();
+                for diagnostic in new_node.get_diagnostics() {
+                    diagnostics.push(diagnostic.in_node(name));
+                }
+                if let Some(error) = take_gl_error(gl) {
+                    diagnostics.push(
+                        diagnostics::Diagnostic::new(
+                            diagnostics::Severity::Error,
+                            format!("{} was raised while creating the node", error),
+                        )
+                        .in_node(name),
+                    );
+                }
             }
             if links
                 .insert(new_node.borrow().get_name().clone(), vec![])
@@ -168,6 +371,7 @@ impl GameToy {
             output_node_maybe,
             resolution: [1920, 1080],
             resolution_dirty: false,
+            diagnostics,
         })
     }
 
@@ -206,7 +410,7 @@ impl GameToy {
                     .ok_or(GameToyError::InvalidLinkVec())?
                     .iter()
                 {
-                    assert!(Rc::ptr_eq(node, &link.end_node));
+                    debug_assert!(Rc::ptr_eq(node, &link.end_node));
 
                     let tex = {
                         if Rc::ptr_eq(node, &link.start_node) {
@@ -238,10 +442,40 @@ impl GameToy {
                         .map_err(|e| {
                             GameToyError::BindInputTextureFailed(node_mut.get_name().clone(), e)
                         })?;
+
+                    let uniforms = if Rc::ptr_eq(node, &link.start_node) {
+                        node_mut.get_output_uniforms(&link.start_output_slot)
+                    } else {
+                        link.start_node
+                            .borrow()
+                            .get_output_uniforms(&link.start_output_slot)
+                    };
+                    node_mut.set_input_uniforms(&link.end_input_slot, uniforms);
                 }
                 node_mut.bind(gl, &self.quad, &self.game_state);
-                gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
-                node_mut.post_draw(gl, &self.game_state);
+                if node_mut.draws() {
+                    gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
+                }
+                if let Err(err) = node_mut.post_draw(gl, &self.game_state) {
+                    push_unique(
+                        &mut self.diagnostics,
+                        diagnostics::Diagnostic::new(
+                            diagnostics::Severity::Error,
+                            err.to_string(),
+                        )
+                        .in_node(node_mut.get_name()),
+                    );
+                }
+                if let Some(error) = take_gl_error(gl) {
+                    push_unique(
+                        &mut self.diagnostics,
+                        diagnostics::Diagnostic::new(
+                            diagnostics::Severity::Error,
+                            format!("{} was raised while rendering the node", error),
+                        )
+                        .in_node(node_mut.get_name()),
+                    );
+                }
             }
         }
 
@@ -251,12 +485,43 @@ impl GameToy {
         Ok(())
     }
 
+    /// Problems found while creating and running the game. This starts with
+    /// the warnings from `ConfigFile::validate`, followed by those found
+    /// while creating the nodes. Problems found while rendering, such as GL
+    /// errors, are added as they happen. Each distinct problem is only
+    /// recorded once.
+    pub fn diagnostics(&self) -> &[diagnostics::Diagnostic] {
+        &self.diagnostics
+    }
+
     // Sets the size to render at
     pub fn resize(&mut self, x_pixels: u32, y_pixels: u32) {
         self.resolution = [x_pixels as i32, y_pixels as i32];
         self.resolution_dirty = true;
     }
 
+    /// Changes the value of a uniform declared in the `uniforms` of one or more
+    /// renderpasses. Every renderpass declaring it is updated. The value must be
+    /// the same type as the one in the config, and is clamped to it's range.
+    pub fn set_uniform(
+        &mut self,
+        name: &str,
+        value: config_file::UniformValue,
+    ) -> Result<(), GameToyError> {
+        let mut found = false;
+        for node in self.nodes.iter() {
+            let mut node = node.borrow_mut();
+            found |= node.set_uniform(name, &value).map_err(|e| {
+                GameToyError::SetUniformFailed(node.get_name().clone(), e)
+            })?;
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(GameToyError::NoSuchUniform(name.to_string()))
+        }
+    }
+
     /// Used for keyboard input into GameToy.
     /// Note that the keycode should be equivalent to the Javascript one for
     /// compatibility
@@ -264,6 +529,56 @@ impl GameToy {
         self.game_state.set_key_state(key_code as usize, key_down);
     }
 
+    /// Replaces the contents of a `HostData` node's texture. The data must be
+    /// tightly packed texels in the node's format, starting at the first
+    /// texel of row zero. The texture is updated during the next render.
+    pub fn upload_node_data(&mut self, name: &str, data: &[u8]) -> Result<(), GameToyError> {
+        self.queue_node_data(name, None, data)
+    }
+
+    /// Replaces part of a `HostData` node's texture. `offset` and `size` are
+    /// in texels and the data must be tightly packed texels covering just
+    /// that region.
+    pub fn upload_node_data_rect(
+        &mut self,
+        name: &str,
+        offset: [u32; 2],
+        size: [u32; 2],
+        data: &[u8],
+    ) -> Result<(), GameToyError> {
+        let region = [offset[0], offset[1], size[0], size[1]];
+        self.queue_node_data(name, Some(region), data)
+    }
+
+    /// Returns the most recent data a `Readback` node has copied back from the
+    /// GPU, or None if nothing has arrived yet. The data is tightly packed
+    /// texels in the node's format, starting with the bottom row of the region.
+    pub fn get_readback(&self, name: &str) -> Result<Option<Vec<u8>>, GameToyError> {
+        let node = self
+            .nodes
+            .iter()
+            .find(|x| x.borrow().get_name() == name)
+            .ok_or_else(|| GameToyError::NoSuchNodeName(name.to_string()))?;
+        let readback = node.borrow().get_readback();
+        readback.map_err(|e| GameToyError::GetReadbackFailed(name.to_string(), e))
+    }
+
+    fn queue_node_data(
+        &mut self,
+        name: &str,
+        region: Option<[u32; 4]>,
+        data: &[u8],
+    ) -> Result<(), GameToyError> {
+        let node = self
+            .nodes
+            .iter()
+            .find(|x| x.borrow().get_name() == name)
+            .ok_or_else(|| GameToyError::NoSuchNodeName(name.to_string()))?;
+        node.borrow_mut()
+            .queue_host_data(region, data)
+            .map_err(|e| GameToyError::UploadNodeDataFailed(name.to_string(), e))
+    }
+
     /*
     fn destroy() {
 
@@ -278,3 +593,38 @@ impl GameToy {
     }
     */
 }
+
+/// Returns the name of the oldest GL error that has been raised, or None if
+/// there hasn't been one. Any other errors are discarded.
+fn take_gl_error(gl: &glow::Context) -> Option<String> {
+    let error = unsafe { gl.get_error() };
+    let name = match error {
+        glow::NO_ERROR => return None,
+        glow::INVALID_ENUM => "GL_INVALID_ENUM".to_string(),
+        glow::INVALID_VALUE => "GL_INVALID_VALUE".to_string(),
+        glow::INVALID_OPERATION => "GL_INVALID_OPERATION".to_string(),
+        glow::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION".to_string(),
+        glow::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY".to_string(),
+        other => format!("GL error 0x{:X}", other),
+    };
+    // Each kind of error is flagged separately, so there can be several
+    // waiting. The limit guards against a lost context, which reports an
+    // error every time.
+    for _ in 0..8 {
+        if unsafe { gl.get_error() } == glow::NO_ERROR {
+            break;
+        }
+    }
+    Some(name)
+}
+
+/// Records a diagnostic unless an identical one already has been, so that
+/// problems repeated every frame are only reported once
+fn push_unique(
+    diagnostics: &mut Vec<diagnostics::Diagnostic>,
+    diagnostic: diagnostics::Diagnostic,
+) {
+    if !diagnostics.contains(&diagnostic) {
+        diagnostics.push(diagnostic);
+    }
+}
@@ -0,0 +1,174 @@
+/*!
+Config File Migration
+---------------------
+
+Every config file records the `format_version` it was written in. When the
+layout of `ConfigFile` changes, older files are upgraded step by step on the
+raw JSON before being deserialised, so projects keep loading after GameToy
+is updated.
+
+Files written before `format_version` existed don't have one, and are
+treated as version 0.
+
+Migrations run on JSON. TOML config files are converted to JSON first. RON
+can't be converted directly, as it's enums only make sense to something that
+knows the layout they are read into. Instead each version RON files could be
+written in has a reader in `RON_READERS`, which reads the file into the types
+`ConfigFile` had in that version and serialises them to JSON. The first
+version that could be written in RON was 1.
+
+To change the format:
+ 1. Write a function that upgrades a document from the current version to
+    the next one and add it to the end of `MIGRATIONS`.
+ 2. Copy `ConfigFile` and the types it uses, as they are now, into a module
+    named after the current version (eg `migration::v1`). Point the last
+    entry of `RON_READERS` at the copy, and add `ron_to_json::<ConfigFile>`
+    after it. GameToy won't build until there is a reader for every version.
+ 3. Change `ConfigFile` to match. `CURRENT_FORMAT_VERSION` follows from the
+    length of `MIGRATIONS`.
+
+Migrations are never edited or removed once released, as there may be files
+in that version somewhere.
+
+!*/
+
+use crate::config_file::{ConfigFile, ConfigFileError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// The name of the field holding the version in a config file
+pub const FORMAT_VERSION_KEY: &str = "format_version";
+
+/// Upgrades the top level object of a document by one version. It doesn't
+/// need to update `format_version`. Returns a description of the problem if
+/// the document can't be upgraded.
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// `MIGRATIONS[n]` upgrades a document from version `n` to version `n + 1`
+const MIGRATIONS: &[Migration] = &[from_unversioned];
+
+/// The version of the config files written by this version of GameToy
+pub const CURRENT_FORMAT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// The first version config files could be written in RON
+pub const FIRST_RON_VERSION: u32 = 1;
+
+/// Reads a RON document into JSON so that it can be migrated
+type RonReader = fn(&str) -> Result<Value, ConfigFileError>;
+
+/// `RON_READERS[n]` reads a RON document written in version
+/// `FIRST_RON_VERSION + n`. The last one reads the current `ConfigFile`.
+const RON_READERS: &[RonReader] = &[ron_to_json::<ConfigFile>];
+
+// Adding a migration turns the current layout into an old one, which RON
+// files can only be read from with a copy of it's types
+const _: () = assert!(
+    RON_READERS.len() as u32 == CURRENT_FORMAT_VERSION + 1 - FIRST_RON_VERSION,
+    "every version since FIRST_RON_VERSION needs a reader in RON_READERS"
+);
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The document isn't a JSON object, so can't be a config file
+    NotAnObject,
+
+    /// The `format_version` isn't a whole number
+    InvalidVersion(Value),
+
+    /// The file was written by a newer version of GameToy than this one.
+    /// u64 is the version of the file
+    NewerVersion(u64),
+
+    /// A migration step couldn't upgrade the document. u32 is the version
+    /// being upgraded from
+    StepFailed(u32, String),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAnObject => write!(f, "the config file isn't a JSON object"),
+            Self::InvalidVersion(version) => {
+                write!(f, "{} isn't a valid {}", version, FORMAT_VERSION_KEY)
+            }
+            Self::NewerVersion(version) => write!(
+                f,
+                "the config file is version {}, but this version of GameToy only understands up to {}",
+                version, CURRENT_FORMAT_VERSION
+            ),
+            Self::StepFailed(version, message) => write!(
+                f,
+                "failed to upgrade the config file from version {}: {}",
+                version, message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// The version a document was written in
+pub fn format_version(document: &Value) -> Result<u32, MigrationError> {
+    let object = document.as_object().ok_or(MigrationError::NotAnObject)?;
+    match object.get(FORMAT_VERSION_KEY) {
+        None => Ok(0),
+        Some(version) => {
+            let number = version
+                .as_u64()
+                .ok_or_else(|| MigrationError::InvalidVersion(version.clone()))?;
+            if number > CURRENT_FORMAT_VERSION as u64 {
+                return Err(MigrationError::NewerVersion(number));
+            }
+            Ok(number as u32)
+        }
+    }
+}
+
+/// Upgrades a document to `CURRENT_FORMAT_VERSION`. Documents that are
+/// already current are left unchanged.
+pub fn migrate(document: &mut Value) -> Result<(), MigrationError> {
+    let version = format_version(document)?;
+    let object = document
+        .as_object_mut()
+        .ok_or(MigrationError::NotAnObject)?;
+
+    for (from_version, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let from_version = from_version as u32;
+        migration(object).map_err(|message| MigrationError::StepFailed(from_version, message))?;
+        object.insert(
+            FORMAT_VERSION_KEY.to_string(),
+            Value::from(from_version + 1),
+        );
+    }
+    Ok(())
+}
+
+/// Reads a RON document written in `version` into JSON, ready to be passed
+/// to `migrate`. `version` is the document's `format_version`.
+pub(crate) fn read_ron(version: u32, text: &str) -> Result<Value, ConfigFileError> {
+    if version > CURRENT_FORMAT_VERSION {
+        return Err(ConfigFileError::MigrationError(
+            MigrationError::NewerVersion(version as u64),
+        ));
+    }
+    let reader = version
+        .checked_sub(FIRST_RON_VERSION)
+        .and_then(|index| RON_READERS.get(index as usize))
+        .ok_or(ConfigFileError::RonVersionMismatch(version))?;
+    reader(text)
+}
+
+/// Reads a RON document into `T`, the layout of `ConfigFile` in the version
+/// it was written in, and converts it to JSON
+fn ron_to_json<T: DeserializeOwned + Serialize>(text: &str) -> Result<Value, ConfigFileError> {
+    let config_file: T = ron::from_str(text).map_err(ConfigFileError::RonReadError)?;
+    serde_json::to_value(config_file).map_err(ConfigFileError::JsonError)
+}
+
+/// 0 -> 1: Files from before `format_version` existed. The layout didn't
+/// change, so all this does is give them a version.
+fn from_unversioned(_document: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
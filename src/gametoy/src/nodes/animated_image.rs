@@ -0,0 +1,332 @@
+/*!
+The Animated Image Node
+-----------------------
+
+Plays back an animation loaded from either an APNG or a sequence of
+numbered PNG's. Every frame is uploaded into it's own texture when the
+node is created, and the output texture is switched as time advances,
+so nothing is uploaded to the GPU while the game is running.
+
+The index of the current frame is passed along links as the `frame`
+uniform, so a renderpass with an input slot called `sprite` can read
+it as `sprite_frame`.
+
+!*/
+
+use super::image::{load_png, mip_levels, set_texture_parameters, to_rgba8, DecodedImage};
+use super::{Node, NodeError};
+use crate::config_file::{
+    AnimatedImageConfig, AnimationMode, AnimationSource, ColorSpace, OutputBufferFormat,
+    UniformValue,
+};
+use crate::gamedata::GameData;
+use crate::quad::Quad;
+use crate::GameState;
+use glow::HasContext;
+use png::{BlendOp, DisposeOp, FrameControl};
+
+pub struct AnimatedImage {
+    pub name: String,
+
+    /// One texture for each frame of the animation
+    textures: Vec<glow::Texture>,
+
+    /// Which texture is currently being output
+    current_frame: usize,
+
+    fps: f32,
+    mode: AnimationMode,
+}
+
+impl AnimatedImage {
+    pub const OUTPUT_BUFFER_NAME: &'static str = "tex";
+
+    pub fn create_from_config(
+        gl: &glow::Context,
+        gamedata: &GameData,
+        config: &AnimatedImageConfig,
+    ) -> Result<Self, NodeError> {
+        let frames = match &config.source {
+            AnimationSource::Apng(path) => decode_apng(gamedata, path, &config.color_space)?,
+            AnimationSource::Sequence(pattern) => {
+                load_sequence(gamedata, pattern, &config.color_space)?
+            }
+        };
+
+        let first_frame = frames.first().ok_or(NodeError::NoImages)?;
+        if frames.iter().any(|frame| {
+            frame.width != first_frame.width
+                || frame.height != first_frame.height
+                || frame.format != first_frame.format
+        }) {
+            return Err(NodeError::ImageSizeMismatch(config.name.clone()));
+        }
+
+        let mut textures = vec![];
+        for frame in frames.iter() {
+            let new_tex = unsafe {
+                gl.create_texture()
+                    .map_err(NodeError::CreateTextureFailed)?
+            };
+
+            unsafe {
+                gl.active_texture(glow::TEXTURE1);
+                gl.bind_texture(glow::TEXTURE_2D, Some(new_tex));
+
+                let levels = mip_levels(config.generate_mipmap, frame.width);
+                gl.tex_storage_2d(
+                    glow::TEXTURE_2D,
+                    levels,
+                    frame.format.to_sized_internal_format(),
+                    frame.width as i32,
+                    frame.height as i32,
+                );
+
+                set_texture_parameters(gl, glow::TEXTURE_2D, glow::REPEAT);
+
+                gl.tex_sub_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    frame.width as i32,
+                    frame.height as i32,
+                    frame.format.to_format(),
+                    frame.format.to_type(),
+                    glow::PixelUnpackData::Slice(&frame.data),
+                );
+                if levels > 1 {
+                    gl.generate_mipmap(glow::TEXTURE_2D);
+                }
+            }
+            textures.push(new_tex);
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            textures,
+            current_frame: 0,
+            fps: config.fps,
+            mode: config.mode.clone(),
+        })
+    }
+}
+
+/// Works out which frame should be displayed after `frames_elapsed` frames of
+/// playback for an animation with `num_frames` frames.
+fn frame_index(mode: &AnimationMode, frames_elapsed: usize, num_frames: usize) -> usize {
+    match mode {
+        AnimationMode::Loop => frames_elapsed % num_frames,
+        AnimationMode::Once => usize::min(frames_elapsed, num_frames - 1),
+        AnimationMode::PingPong => {
+            if num_frames < 2 {
+                return 0;
+            }
+            // Going up and back down again without repeating the end frames
+            let period = num_frames * 2 - 2;
+            let position = frames_elapsed % period;
+            if position < num_frames {
+                position
+            } else {
+                period - position
+            }
+        }
+    }
+}
+
+/// Decodes every frame of an APNG, compositing each one onto the previous
+/// frames as described by the frame control chunks. Normal PNG's decode
+/// as a single frame.
+fn decode_apng(
+    gamedata: &GameData,
+    path: &str,
+    color_space: &ColorSpace,
+) -> Result<Vec<DecodedImage>, NodeError> {
+    let data = gamedata
+        .get_texture(path)
+        .ok_or(NodeError::MissingResource(path.to_string()))?;
+
+    let mut decoder = png::Decoder::new(data.as_slice());
+    // Compositing works on 8 bit RGBA, so expand palettes and strip 16 bit channels
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder
+        .read_info()
+        .map_err(|e| NodeError::InvalidImage(path.to_string(), e))?;
+    let num_frames = reader
+        .info()
+        .animation_control
+        .map(|control| control.num_frames)
+        .unwrap_or(1) as usize;
+
+    let format = match color_space {
+        ColorSpace::Linear => OutputBufferFormat::RGBA8,
+        ColorSpace::Srgb => OutputBufferFormat::SRGB8_ALPHA8,
+    };
+    let whole_image = FrameControl {
+        width: info.width,
+        height: info.height,
+        ..Default::default()
+    };
+
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let mut canvas = vec![0; (info.width * info.height * 4) as usize];
+    let mut frames = vec![];
+
+    while frames.len() < num_frames {
+        reader
+            .next_frame(&mut buf)
+            .map_err(|e| NodeError::InvalidImage(path.to_string(), e))?;
+        let frame_control = match reader.info().frame_control {
+            Some(frame_control) => frame_control,
+            None if num_frames == 1 => whole_image,
+            // The default image of an APNG is not always part of the animation
+            None => continue,
+        };
+
+        let (color_type, _) = reader.output_color_type();
+        let pixel_count = (frame_control.width * frame_control.height) as usize;
+        let pixels = to_rgba8(&buf[..pixel_count * color_type.samples()], color_type);
+
+        let previous_canvas = canvas.clone();
+        composite_frame(&mut canvas, info.width, &pixels, &frame_control);
+        frames.push(DecodedImage {
+            width: info.width,
+            height: info.height,
+            format: format.clone(),
+            data: canvas.clone(),
+        });
+
+        match frame_control.dispose_op {
+            DisposeOp::None => {}
+            DisposeOp::Background => {
+                for_each_frame_pixel(info.width, &frame_control, |canvas_offset, _| {
+                    canvas[canvas_offset..canvas_offset + 4].copy_from_slice(&[0, 0, 0, 0]);
+                });
+            }
+            DisposeOp::Previous => canvas = previous_canvas,
+        }
+    }
+
+    Ok(frames)
+}
+
+/// Draws the RGBA pixels of a (possibly smaller) frame onto the canvas
+fn composite_frame(
+    canvas: &mut [u8],
+    canvas_width: u32,
+    pixels: &[u8],
+    frame_control: &FrameControl,
+) {
+    for_each_frame_pixel(
+        canvas_width,
+        frame_control,
+        |canvas_offset, pixel_offset| {
+            let src = &pixels[pixel_offset..pixel_offset + 4];
+            let dst = &mut canvas[canvas_offset..canvas_offset + 4];
+            match frame_control.blend_op {
+                BlendOp::Source => dst.copy_from_slice(src),
+                BlendOp::Over => {
+                    let src_alpha = src[3] as u32;
+                    for channel in 0..4 {
+                        dst[channel] = ((src[channel] as u32 * src_alpha
+                            + dst[channel] as u32 * (255 - src_alpha))
+                            / 255) as u8;
+                    }
+                    dst[3] = (src_alpha + dst[3] as u32 * (255 - src_alpha) / 255) as u8;
+                }
+            }
+        },
+    );
+}
+
+/// Calls `f` with the byte offsets into the canvas and into the frame of
+/// every pixel that the frame covers
+fn for_each_frame_pixel<F>(canvas_width: u32, frame_control: &FrameControl, mut f: F)
+where
+    F: FnMut(usize, usize),
+{
+    for y in 0..frame_control.height {
+        for x in 0..frame_control.width {
+            let canvas_x = frame_control.x_offset + x;
+            let canvas_y = frame_control.y_offset + y;
+            let canvas_offset = ((canvas_y * canvas_width + canvas_x) * 4) as usize;
+            let pixel_offset = ((y * frame_control.width + x) * 4) as usize;
+            f(canvas_offset, pixel_offset);
+        }
+    }
+}
+
+/// Loads a numbered sequence of PNG's. The run of `#` characters in the pattern is
+/// replaced with the zero-padded frame number. Numbering starts from zero or one.
+fn load_sequence(
+    gamedata: &GameData,
+    pattern: &str,
+    color_space: &ColorSpace,
+) -> Result<Vec<DecodedImage>, NodeError> {
+    let start = pattern
+        .find('#')
+        .ok_or(NodeError::MissingResource(pattern.to_string()))?;
+    let digits = pattern[start..].chars().take_while(|c| *c == '#').count();
+    let frame_path = |frame_number: usize| {
+        format!(
+            "{}{:0width$}{}",
+            &pattern[..start],
+            frame_number,
+            &pattern[start + digits..],
+            width = digits
+        )
+    };
+
+    let mut frame_number = if gamedata.get_texture(&frame_path(0)).is_some() {
+        0
+    } else {
+        1
+    };
+
+    let mut frames = vec![];
+    while gamedata.get_texture(&frame_path(frame_number)).is_some() {
+        frames.push(load_png(gamedata, &frame_path(frame_number), color_space)?);
+        frame_number += 1;
+    }
+    Ok(frames)
+}
+
+impl Node for AnimatedImage {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn update_resolution(&mut self, _gl: &glow::Context, _screen_resolution: &[i32; 2]) {}
+
+    fn bind(&mut self, _gl: &glow::Context, _quad: &Quad, game_state: &GameState) {
+        let frames_elapsed = (game_state.time_since_start * self.fps as f64).max(0.0) as usize;
+        self.current_frame = frame_index(&self.mode, frames_elapsed, self.textures.len());
+    }
+
+    fn get_output_texture(&self, name: &String) -> Result<glow::Texture, NodeError> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            Ok(self.textures[self.current_frame])
+        } else {
+            Err(NodeError::NoSuchOutputTexture(name.clone()))
+        }
+    }
+
+    fn get_output_uniforms(&self, name: &String) -> Vec<(String, UniformValue)> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            vec![(
+                "frame".to_string(),
+                UniformValue::Int(self.current_frame as i32),
+            )]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_input_texture(
+        &mut self,
+        name: &String,
+        _texture: glow::Texture,
+    ) -> Result<(), NodeError> {
+        Err(NodeError::NoSuchInputTexture(name.clone()))
+    }
+}
@@ -0,0 +1,328 @@
+/*!
+The Atlas Node
+--------------
+
+Loads an image containing many sprites along with a JSON sprite sheet
+describing where each sprite is. Two textures are output:
+
+ - `tex` is the image itself
+ - `frames` is a single row `RGBA32F` texture with one texel per sprite
+   containing `(x, y, width, height)` in texture coordinates.
+
+Any renderpass linked to an atlas gets a `#define SPRITE_<NAME> <index>`
+for each sprite in it's preamble, so a sprite's rectangle can be read
+with `texelFetch(frames, ivec2(SPRITE_SHIP, 0), 0)`. Every sprite linked into
+a renderpass needs a different define, so atlases linked into the same
+renderpass can't share sprite names.
+
+!*/
+
+use super::image::{load_png, mip_levels, set_texture_parameters, set_unmipped_texture_parameters};
+use super::{Node, NodeError};
+use crate::config_file::{self, AtlasConfig};
+use crate::gamedata::GameData;
+use crate::quad::Quad;
+use crate::GameState;
+use glow::HasContext;
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub struct Atlas {
+    pub name: String,
+    pub texture: glow::Texture,
+    pub frames_texture: glow::Texture,
+}
+
+/// A sprite as described by the sprite sheet, in pixels
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SpriteRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Deserialize)]
+struct SpriteSheet {
+    frames: SheetFrames,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SheetFrames {
+    /// `"frames": [{"filename": "ship", "frame": {...}}, ...]`
+    Array(Vec<NamedFrame>),
+
+    /// `"frames": {"ship": {"frame": {...}}, ...}`
+    Hash(OrderedFrames),
+}
+
+#[derive(Deserialize)]
+struct NamedFrame {
+    filename: String,
+    #[serde(flatten)]
+    frame: SheetFrame,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SheetFrame {
+    /// Aseprite and TexturePacker nest the rectangle inside other metadata
+    Exported {
+        frame: SpriteRect,
+    },
+    Plain(SpriteRect),
+}
+
+impl SheetFrame {
+    fn into_rect(self) -> SpriteRect {
+        match self {
+            SheetFrame::Exported { frame } => frame,
+            SheetFrame::Plain(rect) => rect,
+        }
+    }
+}
+
+/// The frames of a hash style sprite sheet. These are kept in the order
+/// they appear in the file so that animation frames have consecutive indices.
+struct OrderedFrames(Vec<(String, SheetFrame)>);
+
+impl<'de> Deserialize<'de> for OrderedFrames {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct OrderedFramesVisitor;
+
+        impl<'de> Visitor<'de> for OrderedFramesVisitor {
+            type Value = OrderedFrames;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a map of sprite names to frames")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut frames = vec![];
+                while let Some(entry) = map.next_entry()? {
+                    frames.push(entry);
+                }
+                Ok(OrderedFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_map(OrderedFramesVisitor)
+    }
+}
+
+impl Atlas {
+    pub const OUTPUT_BUFFER_NAME: &'static str = "tex";
+    pub const FRAMES_BUFFER_NAME: &'static str = "frames";
+
+    pub fn create_from_config(
+        gl: &glow::Context,
+        gamedata: &GameData,
+        config: &AtlasConfig,
+    ) -> Result<Self, NodeError> {
+        let image = load_png(gamedata, &config.path, &config.color_space)?;
+        let sprites = load_sprite_sheet(gamedata, &config.sheet_path)?;
+        if sprites.is_empty() {
+            return Err(NodeError::NoImages);
+        }
+
+        let (texture, frames_texture) = unsafe {
+            let texture = gl
+                .create_texture()
+                .map_err(NodeError::CreateTextureFailed)?;
+            let frames_texture = gl
+                .create_texture()
+                .map_err(NodeError::CreateTextureFailed)?;
+            (texture, frames_texture)
+        };
+
+        // Sprite rectangles in texture coordinates
+        let mut frame_data: Vec<f32> = vec![];
+        for (_name, rect) in sprites.iter() {
+            frame_data.extend_from_slice(&[
+                rect.x as f32 / image.width as f32,
+                rect.y as f32 / image.height as f32,
+                rect.w as f32 / image.width as f32,
+                rect.h as f32 / image.height as f32,
+            ]);
+        }
+        let frame_bytes: Vec<u8> = frame_data
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+
+        unsafe {
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+
+            let levels = mip_levels(config.generate_mipmap, image.width);
+            gl.tex_storage_2d(
+                glow::TEXTURE_2D,
+                levels,
+                image.format.to_sized_internal_format(),
+                image.width as i32,
+                image.height as i32,
+            );
+            set_texture_parameters(gl, glow::TEXTURE_2D, glow::REPEAT);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                image.width as i32,
+                image.height as i32,
+                image.format.to_format(),
+                image.format.to_type(),
+                glow::PixelUnpackData::Slice(&image.data),
+            );
+            if levels > 1 {
+                gl.generate_mipmap(glow::TEXTURE_2D);
+            }
+
+            // Float textures can't be filtered, and the frames should be read
+            // with texelFetch anyway.
+            gl.bind_texture(glow::TEXTURE_2D, Some(frames_texture));
+            gl.tex_storage_2d(glow::TEXTURE_2D, 1, glow::RGBA32F, sprites.len() as i32, 1);
+            set_unmipped_texture_parameters(gl, glow::NEAREST);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                sprites.len() as i32,
+                1,
+                glow::RGBA,
+                glow::FLOAT,
+                glow::PixelUnpackData::Slice(&frame_bytes),
+            );
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            texture,
+            frames_texture,
+        })
+    }
+}
+
+/// Reads the sprites out of a sprite sheet in the order they are listed
+pub(crate) fn load_sprite_sheet(
+    gamedata: &GameData,
+    path: &str,
+) -> Result<Vec<(String, SpriteRect)>, NodeError> {
+    let data = gamedata
+        .get_file(path)
+        .ok_or(NodeError::MissingResource(path.to_string()))?;
+    let sheet: SpriteSheet = serde_json::from_slice(data)
+        .map_err(|e| NodeError::InvalidSpriteSheet(path.to_string(), e))?;
+
+    Ok(match sheet.frames {
+        SheetFrames::Array(frames) => frames
+            .into_iter()
+            .map(|named| (named.filename, named.frame.into_rect()))
+            .collect(),
+        SheetFrames::Hash(frames) => frames
+            .0
+            .into_iter()
+            .map(|(name, frame)| (name, frame.into_rect()))
+            .collect(),
+    })
+}
+
+/// Converts a sprite name into the name of the define used to refer to it
+/// in GLSL. `ship 0.aseprite` becomes `SPRITE_SHIP_0`.
+pub(crate) fn sprite_define_name(sprite_name: &str) -> String {
+    let stem = match sprite_name.rfind('.') {
+        Some(dot) if dot > 0 => &sprite_name[..dot],
+        _ => sprite_name,
+    };
+    let name: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("SPRITE_{}", name)
+}
+
+/// The `#define` and index of every sprite in the atlases linked into a
+/// renderpass, in the order they are linked.
+pub(crate) fn linked_sprite_defines(
+    graph: &config_file::GraphConfig,
+    render_pass_name: &str,
+    gamedata: &GameData,
+) -> Result<Vec<(String, usize)>, NodeError> {
+    let mut linked_atlases = vec![];
+    let mut defines = vec![];
+    // Which sprite each define came from, to report clashes
+    let mut sprites: HashMap<String, String> = HashMap::new();
+    for link in graph
+        .links
+        .iter()
+        .filter(|l| l.end_node == render_pass_name)
+    {
+        if linked_atlases.contains(&link.start_node) {
+            continue;
+        }
+        linked_atlases.push(link.start_node.clone());
+        let atlas_config = match graph.nodes.iter().find(|n| n.get_name() == link.start_node) {
+            Some(config_file::Node::Atlas(atlas_config)) => atlas_config,
+            _ => continue,
+        };
+
+        let sheet = load_sprite_sheet(gamedata, &atlas_config.sheet_path)?;
+        for (index, (sprite_name, _rect)) in sheet.into_iter().enumerate() {
+            let define = sprite_define_name(&sprite_name);
+            let sprite = format!("{}: {}", atlas_config.sheet_path, sprite_name);
+            if let Some(existing) = sprites.get(&define) {
+                return Err(NodeError::DuplicateSpriteDefine(
+                    define,
+                    existing.clone(),
+                    sprite,
+                ));
+            }
+            sprites.insert(define.clone(), sprite);
+            defines.push((define, index));
+        }
+    }
+    Ok(defines)
+}
+
+impl Node for Atlas {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn update_resolution(&mut self, _gl: &glow::Context, _screen_resolution: &[i32; 2]) {}
+
+    fn bind(&mut self, _gl: &glow::Context, _quad: &Quad, _game_state: &GameState) {}
+
+    fn get_output_texture(&self, name: &String) -> Result<glow::Texture, NodeError> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            Ok(self.texture)
+        } else if name == Self::FRAMES_BUFFER_NAME {
+            Ok(self.frames_texture)
+        } else {
+            Err(NodeError::NoSuchOutputTexture(name.clone()))
+        }
+    }
+
+    fn set_input_texture(
+        &mut self,
+        name: &String,
+        _texture: glow::Texture,
+    ) -> Result<(), NodeError> {
+        Err(NodeError::NoSuchInputTexture(name.clone()))
+    }
+}
@@ -0,0 +1,178 @@
+/*!
+The Cubemap Node
+----------------
+
+Loads six square images into a cubemap so that shaders can sample
+it with a `samplerCube` (eg for skyboxes and environment lighting).
+
+The faces can either be supplied as six separate images, or as a single
+image laid out as a horizontal cross.
+
+!*/
+
+use super::image::{load_png, mip_levels, set_texture_parameters, DecodedImage};
+use super::{Node, NodeError};
+use crate::config_file::{CubemapConfig, CubemapFaces};
+use crate::gamedata::GameData;
+use crate::quad::Quad;
+use crate::GameState;
+use glow::HasContext;
+
+pub struct Cubemap {
+    pub name: String,
+    pub texture: glow::Texture,
+}
+
+impl Cubemap {
+    pub const OUTPUT_BUFFER_NAME: &'static str = "tex";
+
+    pub fn create_from_config(
+        gl: &glow::Context,
+        gamedata: &GameData,
+        config: &CubemapConfig,
+    ) -> Result<Self, NodeError> {
+        // Faces in the order of the GL enums, starting at TEXTURE_CUBE_MAP_POSITIVE_X
+        let faces = match &config.faces {
+            CubemapFaces::Separate {
+                positive_x,
+                negative_x,
+                positive_y,
+                negative_y,
+                positive_z,
+                negative_z,
+            } => {
+                let mut faces = vec![];
+                for path in [
+                    positive_x, negative_x, positive_y, negative_y, positive_z, negative_z,
+                ]
+                .iter()
+                {
+                    let face = load_png(gamedata, path, &config.color_space)?;
+                    if face.width != face.height {
+                        return Err(NodeError::ImageSizeMismatch(path.to_string()));
+                    }
+                    faces.push(face);
+                }
+                faces
+            }
+            CubemapFaces::Cross(path) => {
+                let cross = load_png(gamedata, path, &config.color_space)?;
+                split_cross(&cross).ok_or(NodeError::ImageSizeMismatch(path.to_string()))?
+            }
+        };
+
+        let size = faces[0].width;
+        let format = faces[0].format.clone();
+        if faces
+            .iter()
+            .any(|face| face.width != size || face.format != format)
+        {
+            return Err(NodeError::ImageSizeMismatch(config.name.clone()));
+        }
+
+        let new_tex = unsafe {
+            gl.create_texture()
+                .map_err(NodeError::CreateTextureFailed)?
+        };
+
+        unsafe {
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_CUBE_MAP, Some(new_tex));
+
+            let levels = mip_levels(config.generate_mipmap, size);
+            gl.tex_storage_2d(
+                glow::TEXTURE_CUBE_MAP,
+                levels,
+                format.to_sized_internal_format(),
+                size as i32,
+                size as i32,
+            );
+
+            set_texture_parameters(gl, glow::TEXTURE_CUBE_MAP, glow::CLAMP_TO_EDGE);
+
+            for (face_id, face) in faces.iter().enumerate() {
+                gl.tex_sub_image_2d(
+                    glow::TEXTURE_CUBE_MAP_POSITIVE_X + face_id as u32,
+                    0,
+                    0,
+                    0,
+                    size as i32,
+                    size as i32,
+                    format.to_format(),
+                    format.to_type(),
+                    glow::PixelUnpackData::Slice(&face.data),
+                );
+            }
+            if levels > 1 {
+                gl.generate_mipmap(glow::TEXTURE_CUBE_MAP);
+            }
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            texture: new_tex,
+        })
+    }
+}
+
+/// Cuts a horizontal cross into it's six faces. The faces are returned in
+/// the order +X, -X, +Y, -Y, +Z, -Z. Returns None if the image is not four
+/// faces wide and three faces high.
+fn split_cross(cross: &DecodedImage) -> Option<Vec<DecodedImage>> {
+    let size = cross.width / 4;
+    if size == 0 || cross.width != size * 4 || cross.height != size * 3 {
+        return None;
+    }
+
+    let bytes_per_pixel = cross.data.len() / (cross.width * cross.height) as usize;
+    let row_length = cross.width as usize * bytes_per_pixel;
+    let face_row_length = size as usize * bytes_per_pixel;
+
+    // (column, row) of each face inside the cross
+    let face_positions = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+
+    let faces = face_positions
+        .iter()
+        .map(|(column, row)| {
+            let mut data = Vec::with_capacity(face_row_length * size as usize);
+            for y in 0..size as usize {
+                let start = (row * size as usize + y) * row_length + column * face_row_length;
+                data.extend_from_slice(&cross.data[start..start + face_row_length]);
+            }
+            DecodedImage {
+                width: size,
+                height: size,
+                format: cross.format.clone(),
+                data,
+            }
+        })
+        .collect();
+
+    Some(faces)
+}
+
+impl Node for Cubemap {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn update_resolution(&mut self, _gl: &glow::Context, _screen_resolution: &[i32; 2]) {}
+
+    fn bind(&mut self, _gl: &glow::Context, _quad: &Quad, _game_state: &GameState) {}
+
+    fn get_output_texture(&self, name: &String) -> Result<glow::Texture, NodeError> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            Ok(self.texture)
+        } else {
+            Err(NodeError::NoSuchOutputTexture(name.clone()))
+        }
+    }
+
+    fn set_input_texture(
+        &mut self,
+        name: &String,
+        _texture: glow::Texture,
+    ) -> Result<(), NodeError> {
+        Err(NodeError::NoSuchInputTexture(name.clone()))
+    }
+}
@@ -0,0 +1,205 @@
+/*!
+The Data Table Node
+-------------------
+
+Loads a table of numbers from a CSV or JSON file into a float texture so
+that shaders can look up values such as weapon stats, palettes or spline
+control points without giant `const float[]` arrays.
+
+Rows shorter than the longest row are padded with zeros. Every cell must be
+a number: a cell that can't be parsed fails with
+`NodeError::NonNumericCell`, which reports where in the file it is.
+
+!*/
+
+use super::image::set_unmipped_texture_parameters;
+use super::{Node, NodeError};
+use crate::config_file::{DataTableConfig, DataTableFormat, DataTableLayout, UniformValue};
+use crate::gamedata::GameData;
+use crate::quad::Quad;
+use crate::GameState;
+use glow::HasContext;
+use serde::Deserialize;
+
+pub struct DataTable {
+    pub name: String,
+    pub texture: glow::Texture,
+    size: [f32; 2],
+}
+
+/// The contents of a JSON data table
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTable {
+    Rows(Vec<Vec<serde_json::Value>>),
+    Row(Vec<serde_json::Value>),
+}
+
+impl DataTable {
+    pub const OUTPUT_BUFFER_NAME: &'static str = "tex";
+
+    pub fn create_from_config(
+        gl: &glow::Context,
+        gamedata: &GameData,
+        config: &DataTableConfig,
+    ) -> Result<Self, NodeError> {
+        let mut rows = Self::read_rows(gamedata, config)?;
+
+        let (channels, internal_format, format) = match config.format {
+            DataTableFormat::R32F => (1, glow::R32F, glow::RED),
+            DataTableFormat::RGBA32F => (4, glow::RGBA32F, glow::RGBA),
+        };
+        let width = rows[0].len().div_ceil(channels);
+        let height = rows.len();
+        let mut bytes: Vec<u8> = Vec::with_capacity(width * height * channels * 4);
+        for row in rows.iter_mut() {
+            row.resize(width * channels, 0.0);
+            bytes.extend(row.iter().flat_map(|value| value.to_ne_bytes()));
+        }
+
+        let texture = unsafe {
+            gl.create_texture()
+                .map_err(NodeError::CreateTextureFailed)?
+        };
+        unsafe {
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_storage_2d(
+                glow::TEXTURE_2D,
+                1,
+                internal_format,
+                width as i32,
+                height as i32,
+            );
+            set_unmipped_texture_parameters(gl, glow::NEAREST);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                format,
+                glow::FLOAT,
+                glow::PixelUnpackData::Slice(&bytes),
+            );
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            texture,
+            size: [width as f32, height as f32],
+        })
+    }
+
+    /// Reads the table a config points at into rows of numbers, as they
+    /// will be laid out in the texture. Short rows are padded with zeros.
+    pub fn read_rows(
+        gamedata: &GameData,
+        config: &DataTableConfig,
+    ) -> Result<Vec<Vec<f32>>, NodeError> {
+        let data = gamedata
+            .get_file(&config.path)
+            .ok_or(NodeError::MissingResource(config.path.clone()))?;
+        let text = std::str::from_utf8(data)
+            .map_err(|e| NodeError::InvalidTable(config.path.clone(), e.to_string()))?;
+
+        let mut rows = if config.path.ends_with(".json") {
+            parse_json(&config.path, text)?
+        } else {
+            parse_csv(&config.path, text, config.has_header)?
+        };
+
+        let num_columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        if num_columns == 0 {
+            return Err(NodeError::InvalidTable(
+                config.path.clone(),
+                "The table is empty".to_string(),
+            ));
+        }
+        for row in rows.iter_mut() {
+            row.resize(num_columns, 0.0);
+        }
+        if config.layout == DataTableLayout::Columns {
+            rows = (0..num_columns)
+                .map(|column| rows.iter().map(|row| row[column]).collect())
+                .collect();
+        }
+        Ok(rows)
+    }
+}
+
+/// Reads a CSV file into rows of numbers. Blank lines are skipped.
+fn parse_csv(path: &str, text: &str, has_header: bool) -> Result<Vec<Vec<f32>>, NodeError> {
+    let mut rows = vec![];
+    let lines = text.lines().enumerate().skip(has_header as usize);
+    for (line_number, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+        let mut row = vec![];
+        for (column, cell) in line.split(',').enumerate() {
+            let value = cell.trim().parse::<f32>().map_err(|_| {
+                NodeError::NonNumericCell(path.to_string(), line_number + 1, column + 1)
+            })?;
+            row.push(value);
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Reads a JSON array, or array of arrays, into rows of numbers
+fn parse_json(path: &str, text: &str) -> Result<Vec<Vec<f32>>, NodeError> {
+    let table: JsonTable = serde_json::from_str(text)
+        .map_err(|e| NodeError::InvalidTable(path.to_string(), e.to_string()))?;
+    let rows = match table {
+        JsonTable::Row(row) => vec![row],
+        JsonTable::Rows(rows) => rows,
+    };
+
+    rows.iter()
+        .enumerate()
+        .map(|(row_number, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    cell.as_f64().map(|value| value as f32).ok_or_else(|| {
+                        NodeError::NonNumericCell(path.to_string(), row_number + 1, column + 1)
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+impl Node for DataTable {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn update_resolution(&mut self, _gl: &glow::Context, _screen_resolution: &[i32; 2]) {}
+
+    fn bind(&mut self, _gl: &glow::Context, _quad: &Quad, _game_state: &GameState) {}
+
+    fn get_output_texture(&self, name: &String) -> Result<glow::Texture, NodeError> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            Ok(self.texture)
+        } else {
+            Err(NodeError::NoSuchOutputTexture(name.clone()))
+        }
+    }
+
+    fn get_output_uniforms(&self, name: &String) -> Vec<(String, UniformValue)> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            vec![("size".to_string(), UniformValue::Vec2(self.size))]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_input_texture(
+        &mut self,
+        name: &String,
+        _texture: glow::Texture,
+    ) -> Result<(), NodeError> {
+        Err(NodeError::NoSuchInputTexture(name.clone()))
+    }
+}
@@ -0,0 +1,218 @@
+/*!
+The Font Node
+-------------
+
+Rasterises a font into a glyph atlas using epaint. Two textures are output:
+
+ - `tex` is the single channel glyph atlas
+ - `metrics` is an `RGBA32F` texture with one column per glyph. Row 0
+   contains the glyph's rectangle in the atlas `(u_min, v_min, u_max, v_max)`,
+   row 1 contains the offset and size of the glyph relative to the pen
+   `(x, y, width, height)` in pixels, and row 2 contains
+   `(advance, line height, 0, 0)` in pixels.
+
+A glyph's index is the position of it's character in the configured
+`characters`. Any renderpass linked to a font gets the helper functions in
+`resources/font_helpers.frag` in it's preamble.
+
+!*/
+
+use super::image::set_unmipped_texture_parameters;
+use super::{Node, NodeError};
+use crate::config_file::{FontConfig, FontSource};
+use crate::gamedata::GameData;
+use crate::quad::Quad;
+use crate::GameState;
+use epaint::text::{FontDefinitions, FontFamily, Fonts, TextStyle};
+use glow::HasContext;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+pub struct Font {
+    pub name: String,
+    pub texture: glow::Texture,
+    pub metrics_texture: glow::Texture,
+}
+
+/// The name the font from the game data is given inside epaint
+const DATA_FONT_NAME: &str = "gametoy_font";
+
+/// Number of rows in the metrics texture
+const METRICS_ROWS: usize = 3;
+
+impl Font {
+    pub const OUTPUT_BUFFER_NAME: &'static str = "tex";
+    pub const METRICS_BUFFER_NAME: &'static str = "metrics";
+
+    pub fn create_from_config(
+        gl: &glow::Context,
+        gamedata: &GameData,
+        config: &FontConfig,
+    ) -> Result<Self, NodeError> {
+        if config.characters.is_empty() {
+            return Err(NodeError::NoGlyphs);
+        }
+
+        let fonts = Fonts::from_definitions(1.0, font_definitions(gamedata, config)?);
+        let font = &fonts[TextStyle::Body];
+        let num_glyphs = config.characters.chars().count();
+
+        // Rasterising happens the first time a glyph is used, so the metrics
+        // have to be collected before the atlas is read.
+        let mut metrics = vec![[0.0f32; 4]; num_glyphs * METRICS_ROWS];
+        for (index, c) in config.characters.chars().enumerate() {
+            metrics[num_glyphs * 2 + index] = [font.glyph_width(c), font.row_height(), 0.0, 0.0];
+        }
+        let atlas = fonts.texture();
+        for (index, c) in config.characters.chars().enumerate() {
+            if let Some(uv_rect) = font.uv_rect(c) {
+                metrics[index] = [
+                    uv_rect.min.0 as f32 / atlas.width as f32,
+                    uv_rect.min.1 as f32 / atlas.height as f32,
+                    uv_rect.max.0 as f32 / atlas.width as f32,
+                    uv_rect.max.1 as f32 / atlas.height as f32,
+                ];
+                metrics[num_glyphs + index] = [
+                    uv_rect.offset.x,
+                    uv_rect.offset.y,
+                    uv_rect.size.x,
+                    uv_rect.size.y,
+                ];
+            }
+        }
+        let metrics_bytes: Vec<u8> = metrics
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+
+        let (texture, metrics_texture) = unsafe {
+            let texture = gl
+                .create_texture()
+                .map_err(NodeError::CreateTextureFailed)?;
+            let metrics_texture = gl
+                .create_texture()
+                .map_err(NodeError::CreateTextureFailed)?;
+            (texture, metrics_texture)
+        };
+
+        unsafe {
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_storage_2d(
+                glow::TEXTURE_2D,
+                1,
+                glow::R8,
+                atlas.width as i32,
+                atlas.height as i32,
+            );
+            set_unmipped_texture_parameters(gl, glow::LINEAR);
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                atlas.width as i32,
+                atlas.height as i32,
+                glow::RED,
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(&atlas.pixels),
+            );
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+
+            gl.bind_texture(glow::TEXTURE_2D, Some(metrics_texture));
+            gl.tex_storage_2d(
+                glow::TEXTURE_2D,
+                1,
+                glow::RGBA32F,
+                num_glyphs as i32,
+                METRICS_ROWS as i32,
+            );
+            set_unmipped_texture_parameters(gl, glow::NEAREST);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                num_glyphs as i32,
+                METRICS_ROWS as i32,
+                glow::RGBA,
+                glow::FLOAT,
+                glow::PixelUnpackData::Slice(&metrics_bytes),
+            );
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            texture,
+            metrics_texture,
+        })
+    }
+}
+
+/// Sets up epaint so that `TextStyle::Body` is the requested font. The
+/// built-in fonts are kept as fallbacks so that epaint always has a
+/// replacement glyph available.
+fn font_definitions(
+    gamedata: &GameData,
+    config: &FontConfig,
+) -> Result<FontDefinitions, NodeError> {
+    let mut definitions = FontDefinitions::default();
+    let family = match &config.source {
+        FontSource::Monospace => FontFamily::Monospace,
+        FontSource::Proportional => FontFamily::Proportional,
+        FontSource::File(path) => {
+            let data = gamedata
+                .get_file(path)
+                .ok_or(NodeError::MissingResource(path.to_string()))?;
+            // epaint panics on invalid fonts, so check it first
+            ab_glyph::FontRef::try_from_slice(data)
+                .map_err(|_| NodeError::InvalidFont(path.to_string()))?;
+
+            definitions
+                .font_data
+                .insert(DATA_FONT_NAME.to_string(), Cow::Owned(data.clone()));
+            definitions
+                .fonts_for_family
+                .entry(FontFamily::Proportional)
+                .or_default()
+                .insert(0, DATA_FONT_NAME.to_string());
+            FontFamily::Proportional
+        }
+    };
+
+    // Only rasterise the one style that is used
+    let mut family_and_size = BTreeMap::new();
+    family_and_size.insert(TextStyle::Body, (family, config.size));
+    definitions.family_and_size = family_and_size;
+    Ok(definitions)
+}
+
+impl Node for Font {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn update_resolution(&mut self, _gl: &glow::Context, _screen_resolution: &[i32; 2]) {}
+
+    fn bind(&mut self, _gl: &glow::Context, _quad: &Quad, _game_state: &GameState) {}
+
+    fn get_output_texture(&self, name: &String) -> Result<glow::Texture, NodeError> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            Ok(self.texture)
+        } else if name == Self::METRICS_BUFFER_NAME {
+            Ok(self.metrics_texture)
+        } else {
+            Err(NodeError::NoSuchOutputTexture(name.clone()))
+        }
+    }
+
+    fn set_input_texture(
+        &mut self,
+        name: &String,
+        _texture: glow::Texture,
+    ) -> Result<(), NodeError> {
+        Err(NodeError::NoSuchInputTexture(name.clone()))
+    }
+}
@@ -0,0 +1,166 @@
+/*!
+The Host Data Node
+------------------
+
+A texture whose contents come from the application embedding GameToy rather
+than from the game data. The host calls `GameToy::upload_node_data` (or
+`GameToy::upload_node_data_rect` for part of the texture) with tightly packed
+texels in the configured format, which lets it feed simulation results, video
+frames or network state into the shaders.
+
+Uploads are queued and copied into the texture the next time the node is
+bound, so a renderpass listed after this node sees the new data in the same
+frame. Row zero of the data is at `y = 0` in texture coordinates.
+
+!*/
+
+use super::image::set_unmipped_texture_parameters;
+use super::{Node, NodeError};
+use crate::config_file::{HostDataConfig, OutputBufferFormat, UniformValue};
+use crate::quad::Quad;
+use crate::GameState;
+use glow::HasContext;
+
+pub struct HostData {
+    pub name: String,
+    pub texture: glow::Texture,
+    size: [u32; 2],
+    format: OutputBufferFormat,
+
+    /// Uploads that haven't been copied to the texture yet, as
+    /// `[x, y, width, height]` and the texels
+    pending_uploads: Vec<([u32; 4], Vec<u8>)>,
+}
+
+impl HostData {
+    pub const OUTPUT_BUFFER_NAME: &'static str = "tex";
+
+    pub fn create_from_config(
+        gl: &glow::Context,
+        config: &HostDataConfig,
+    ) -> Result<Self, NodeError> {
+        let [width, height] = config.size;
+        if width == 0 || height == 0 {
+            return Err(NodeError::HostDataOutOfBounds([0, 0], config.size));
+        }
+
+        let texture = unsafe {
+            gl.create_texture()
+                .map_err(NodeError::CreateTextureFailed)?
+        };
+        let zeroes = vec![0; width as usize * height as usize * config.format.to_bytes_per_pixel()];
+        unsafe {
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_storage_2d(
+                glow::TEXTURE_2D,
+                1,
+                config.format.to_sized_internal_format(),
+                width as i32,
+                height as i32,
+            );
+            set_unmipped_texture_parameters(gl, glow::NEAREST);
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                config.format.to_format(),
+                config.format.to_type(),
+                glow::PixelUnpackData::Slice(&zeroes),
+            );
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            texture,
+            size: config.size,
+            format: config.format.clone(),
+            pending_uploads: vec![],
+        })
+    }
+}
+
+impl Node for HostData {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn update_resolution(&mut self, _gl: &glow::Context, _screen_resolution: &[i32; 2]) {}
+
+    fn bind(&mut self, gl: &glow::Context, _quad: &Quad, _game_state: &GameState) {
+        if self.pending_uploads.is_empty() {
+            return;
+        }
+        unsafe {
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            for ([x, y, width, height], data) in self.pending_uploads.drain(..) {
+                gl.tex_sub_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    x as i32,
+                    y as i32,
+                    width as i32,
+                    height as i32,
+                    self.format.to_format(),
+                    self.format.to_type(),
+                    glow::PixelUnpackData::Slice(&data),
+                );
+            }
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+        }
+    }
+
+    fn get_output_texture(&self, name: &String) -> Result<glow::Texture, NodeError> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            Ok(self.texture)
+        } else {
+            Err(NodeError::NoSuchOutputTexture(name.clone()))
+        }
+    }
+
+    fn get_output_uniforms(&self, name: &String) -> Vec<(String, UniformValue)> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            let size = [self.size[0] as f32, self.size[1] as f32];
+            vec![("size".to_string(), UniformValue::Vec2(size))]
+        } else {
+            vec![]
+        }
+    }
+
+    fn set_input_texture(
+        &mut self,
+        name: &String,
+        _texture: glow::Texture,
+    ) -> Result<(), NodeError> {
+        Err(NodeError::NoSuchInputTexture(name.clone()))
+    }
+
+    fn queue_host_data(&mut self, region: Option<[u32; 4]>, data: &[u8]) -> Result<(), NodeError> {
+        let region = region.unwrap_or([0, 0, self.size[0], self.size[1]]);
+        let [x, y, width, height] = region;
+        let fits_x = x.checked_add(width).is_some_and(|end| end <= self.size[0]);
+        let fits_y = y.checked_add(height).is_some_and(|end| end <= self.size[1]);
+        if !fits_x || !fits_y || width == 0 || height == 0 {
+            return Err(NodeError::HostDataOutOfBounds([x, y], [width, height]));
+        }
+
+        let expected_len = width as usize * height as usize * self.format.to_bytes_per_pixel();
+        if data.len() != expected_len {
+            return Err(NodeError::HostDataSizeMismatch(expected_len, data.len()));
+        }
+
+        // Anything still queued would be overwritten anyway
+        if region == [0, 0, self.size[0], self.size[1]] {
+            self.pending_uploads.clear();
+        }
+        self.pending_uploads.push((region, data.to_vec()));
+        Ok(())
+    }
+}
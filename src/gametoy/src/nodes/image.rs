@@ -4,7 +4,7 @@ use crate::GameState;
 use glow::HasContext;
 use png::{BitDepth, ColorType};
 
-use crate::config_file::OutputBufferFormat;
+use crate::config_file::{ColorSpace, OutputBufferFormat};
 
 use png;
 
@@ -13,6 +13,15 @@ pub struct Image {
     pub texture: glow::Texture,
 }
 
+/// A PNG that has been decoded into a buffer that is ready to be
+/// uploaded to the GPU.
+pub(crate) struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: OutputBufferFormat,
+    pub data: Vec<u8>,
+}
+
 impl Image {
     pub const OUTPUT_BUFFER_NAME: &'static str = "tex";
 
@@ -26,72 +35,34 @@ impl Image {
                 .map_err(NodeError::CreateTextureFailed)?
         };
 
-        let data = gamedata
-            .textures
-            .get(&config.path)
-            .ok_or(NodeError::MissingResource(config.path.to_string()))?;
-
-        let decoder = png::Decoder::new(data.as_slice());
-        let (info, mut reader) = decoder.read_info().unwrap();
-        // Allocate the output buffer.
-        let mut buf = vec![0; info.buffer_size()];
-        // Read the next frame. An APNG might contain multiple frames.
-        reader.next_frame(&mut buf).unwrap();
-
-        let tex_format = match reader.output_color_type() {
-            (ColorType::RGB, BitDepth::Eight) => OutputBufferFormat::RGB8,
-            (ColorType::RGB, BitDepth::Sixteen) => OutputBufferFormat::RGBA16UI,
-            (ColorType::RGBA, BitDepth::Eight) => OutputBufferFormat::RGBA8,
-            (ColorType::RGBA, BitDepth::Sixteen) => OutputBufferFormat::RGBA16UI,
-            (ColorType::Grayscale, BitDepth::Eight) => OutputBufferFormat::R8,
-            (ColorType::Grayscale, BitDepth::Sixteen) => OutputBufferFormat::R16UI,
-            (_, _) => unimplemented!("Unsupported PNG Pixel Type"),
-        };
-        
+        let image = load_png(gamedata, &config.path, &config.color_space)?;
 
         unsafe {
             gl.active_texture(glow::TEXTURE1);
             gl.bind_texture(glow::TEXTURE_2D, Some(new_tex));
 
-            let levels = {
-                if config.generate_mipmap {
-                    (info.width as f32).log2().ceil() as i32
-                } else {
-                    1
-                }
-            };
+            let levels = mip_levels(config.generate_mipmap, image.width);
 
             gl.tex_storage_2d(
                 glow::TEXTURE_2D,
                 levels,
-                tex_format.to_sized_internal_format(),
-                info.width as i32,
-                info.height as i32,
+                image.format.to_sized_internal_format(),
+                image.width as i32,
+                image.height as i32,
             );
 
-            gl.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MAG_FILTER,
-                glow::LINEAR as i32,
-            );
-            gl.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MIN_FILTER,
-                glow::LINEAR_MIPMAP_LINEAR as i32,
-            );
-            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::REPEAT as i32);
-            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::REPEAT as i32);
+            set_texture_parameters(gl, glow::TEXTURE_2D, glow::REPEAT);
 
             gl.tex_sub_image_2d(
                 glow::TEXTURE_2D,
                 0,
                 0,
                 0,
-                info.width as i32,
-                info.height as i32,
-                tex_format.to_format(),
-                tex_format.to_type(),
-                glow::PixelUnpackData::Slice(&buf),
+                image.width as i32,
+                image.height as i32,
+                image.format.to_format(),
+                image.format.to_type(),
+                glow::PixelUnpackData::Slice(&image.data),
             );
             if levels > 1 {
                 gl.generate_mipmap(glow::TEXTURE_2D);
@@ -105,6 +76,120 @@ impl Image {
     }
 }
 
+/// Fetches a PNG from the gamedata and decodes it
+pub(crate) fn load_png(
+    gamedata: &crate::gamedata::GameData,
+    path: &str,
+    color_space: &ColorSpace,
+) -> Result<DecodedImage, NodeError> {
+    let data = gamedata
+        .get_texture(path)
+        .ok_or(NodeError::MissingResource(path.to_string()))?;
+
+    let decoder = png::Decoder::new(data.as_slice());
+    let (info, mut reader) = decoder
+        .read_info()
+        .map_err(|e| NodeError::InvalidImage(path.to_string(), e))?;
+    // Allocate the output buffer.
+    let mut buf = vec![0; info.buffer_size()];
+    // Read the next frame. An APNG might contain multiple frames.
+    reader
+        .next_frame(&mut buf)
+        .map_err(|e| NodeError::InvalidImage(path.to_string(), e))?;
+
+    let (format, data) = match color_space {
+        ColorSpace::Linear => {
+            let tex_format = match reader.output_color_type() {
+                (ColorType::RGB, BitDepth::Eight) => OutputBufferFormat::RGB8,
+                (ColorType::RGB, BitDepth::Sixteen) => OutputBufferFormat::RGBA16UI,
+                (ColorType::RGBA, BitDepth::Eight) => OutputBufferFormat::RGBA8,
+                (ColorType::RGBA, BitDepth::Sixteen) => OutputBufferFormat::RGBA16UI,
+                (ColorType::Grayscale, BitDepth::Eight) => OutputBufferFormat::R8,
+                (ColorType::Grayscale, BitDepth::Sixteen) => OutputBufferFormat::R16UI,
+                (_, _) => return Err(NodeError::UnsupportedImageFormat(path.to_string())),
+            };
+            (tex_format, buf)
+        }
+        ColorSpace::Srgb => {
+            // SRGB8 is not color-renderable, so mipmaps can't be generated for it. Expanding
+            // RGB data to RGBA lets everything use SRGB8_ALPHA8.
+            let buf = match reader.output_color_type() {
+                (ColorType::RGB, BitDepth::Eight) => to_rgba8(&buf, ColorType::RGB),
+                (ColorType::RGBA, BitDepth::Eight) => buf,
+                (_, _) => return Err(NodeError::UnsupportedColorSpace(path.to_string())),
+            };
+            (OutputBufferFormat::SRGB8_ALPHA8, buf)
+        }
+    };
+
+    Ok(DecodedImage {
+        width: info.width,
+        height: info.height,
+        format,
+        data,
+    })
+}
+
+/// How many mip levels a texture of the supplied width needs
+pub(crate) fn mip_levels(generate_mipmap: bool, width: u32) -> i32 {
+    if generate_mipmap {
+        (width as f32).log2().ceil() as i32
+    } else {
+        1
+    }
+}
+
+/// Sets up the filtering and wrapping for a static texture that is
+/// currently bound to `target`
+pub(crate) unsafe fn set_texture_parameters(gl: &glow::Context, target: u32, wrap_mode: u32) {
+    gl.tex_parameter_i32(target, glow::TEXTURE_MAG_FILTER, glow::LINEAR as i32);
+    gl.tex_parameter_i32(
+        target,
+        glow::TEXTURE_MIN_FILTER,
+        glow::LINEAR_MIPMAP_LINEAR as i32,
+    );
+    gl.tex_parameter_i32(target, glow::TEXTURE_WRAP_S, wrap_mode as i32);
+    gl.tex_parameter_i32(target, glow::TEXTURE_WRAP_T, wrap_mode as i32);
+}
+
+/// Sets up the filtering and wrapping for a texture without mipmaps that is
+/// currently bound to TEXTURE_2D. Data textures should use NEAREST filtering.
+pub(crate) unsafe fn set_unmipped_texture_parameters(gl: &glow::Context, filter: u32) {
+    gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, filter as i32);
+    gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, filter as i32);
+    gl.tex_parameter_i32(
+        glow::TEXTURE_2D,
+        glow::TEXTURE_WRAP_S,
+        glow::CLAMP_TO_EDGE as i32,
+    );
+    gl.tex_parameter_i32(
+        glow::TEXTURE_2D,
+        glow::TEXTURE_WRAP_T,
+        glow::CLAMP_TO_EDGE as i32,
+    );
+}
+
+/// Converts tightly packed 8 bit pixel data into RGBA. Grayscale is copied
+/// into all three colour channels and missing alpha is set to opaque.
+pub(crate) fn to_rgba8(data: &[u8], color_type: ColorType) -> Vec<u8> {
+    let channels = color_type.samples();
+    let mut rgba = Vec::with_capacity(data.len() / channels * 4);
+    for pixel in data.chunks_exact(channels) {
+        match color_type {
+            // Palettes are expanded by the decoder, so Indexed data never reaches here.
+            ColorType::Grayscale | ColorType::Indexed => {
+                rgba.extend_from_slice(&[pixel[0], pixel[0], pixel[0], 255])
+            }
+            ColorType::GrayscaleAlpha => {
+                rgba.extend_from_slice(&[pixel[0], pixel[0], pixel[0], pixel[1]])
+            }
+            ColorType::RGB => rgba.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]),
+            ColorType::RGBA => rgba.extend_from_slice(pixel),
+        }
+    }
+    rgba
+}
+
 impl Node for Image {
     fn get_name(&self) -> &String {
         return &self.name;
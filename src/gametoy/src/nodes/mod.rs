@@ -1,11 +1,30 @@
+mod animated_image;
+mod atlas;
+mod cubemap;
+mod data_table;
+mod font;
+mod host_data;
 mod image;
 mod keyboard;
 mod node;
 mod output;
+mod readback;
 mod renderpass;
+mod texture_array;
+mod tiled_map;
 
+pub use animated_image::AnimatedImage;
+pub(crate) use atlas::linked_sprite_defines;
+pub use atlas::Atlas;
+pub use cubemap::Cubemap;
+pub use data_table::DataTable;
+pub use font::Font;
+pub use host_data::HostData;
 pub use image::Image;
 pub use keyboard::Keyboard;
 pub use node::{Node, NodeError};
 pub use output::Output;
-pub use renderpass::RenderPass;
+pub use readback::Readback;
+pub use renderpass::{generate_shader_text, RenderPass};
+pub use texture_array::TextureArray;
+pub use tiled_map::TiledMap;
@@ -1,5 +1,8 @@
+use crate::config_file::UniformValue;
+use crate::diagnostics::Diagnostic;
 use glow::Texture;
 use std::any::Any;
+use std::fmt;
 
 #[derive(Debug)]
 pub enum NodeError {
@@ -17,16 +20,114 @@ pub enum NodeError {
     /// The GPU failed to allocate a texture
     CreateTextureFailed(String),
 
+    /// The GPU failed to allocate a buffer
+    CreateBufferFailed(String),
+
     /// The text files that should contain the shader source code
     /// do not exist in the supplied GameData
     MissingResource(String),
 
+    /// A shader includes itself, either directly or through other files,
+    /// without `#pragma once` or an include guard.
+    /// The strings are the paths of the files making up the cycle
+    IncludeCycle(Vec<String>),
+
+    /// An `#include` directive isn't followed by a quoted path.
+    /// String is the path of the shader, followed by the line (counting from one)
+    MalformedInclude(String, usize),
+
     /// This renderpass has two input slots with the same name
     DuplicateInputSlotName(String),
 
     /// This renderpass has two output slots with the same name
     DuplicateOutputSlotName(String),
 
+    /// This renderpass has more input slots than the GPU has texture units.
+    /// The number is how many the GPU supports
+    TooManyInputSlots(usize),
+
+    /// This renderpass has more output slots than the GPU can draw to at once.
+    /// The number is how many the GPU supports
+    TooManyOutputSlots(usize),
+
+    /// An image file could not be decoded.
+    /// String is the path of the image
+    InvalidImage(String, png::DecodingError),
+
+    /// The image's pixel format isn't supported. Only greyscale, RGB and RGBA
+    /// images are.
+    /// String is the path of the image
+    UnsupportedImageFormat(String),
+
+    /// The image can't be stored in the requested colour space. Only 8 bit
+    /// RGB and RGBA images can be sRGB encoded.
+    /// String is the path of the image
+    UnsupportedColorSpace(String),
+
+    /// The images making up this node are not all the same size and format,
+    /// or a cubemap face is not square.
+    /// String is the path of the offending image
+    ImageSizeMismatch(String),
+
+    /// The sprite sheet describing an atlas could not be parsed.
+    /// String is the path of the sprite sheet
+    InvalidSpriteSheet(String, serde_json::Error),
+
+    /// Two sprites linked into a renderpass would get the same `#define`, either
+    /// because their names only differ in punctuation or because two atlases both
+    /// have a sprite with the name. The strings are the define, then each sprite
+    /// as `sheet path: sprite name`
+    DuplicateSpriteDefine(String, String, String),
+
+    /// The font file could not be parsed.
+    /// String is the path of the font
+    InvalidFont(String),
+
+    /// A font node has no characters to rasterise
+    NoGlyphs,
+
+    /// The map file could not be parsed.
+    /// The first string is the path of the map, the second describes the problem
+    InvalidMap(String, String),
+
+    /// The map does not contain a layer that the config asks for.
+    /// String is the name of the layer
+    MissingLayer(String),
+
+    /// The data table file could not be parsed.
+    /// The first string is the path of the table, the second describes the problem
+    InvalidTable(String, String),
+
+    /// A cell in a data table is not a number.
+    /// String is the path of the table, followed by the row (line for CSV files)
+    /// and column of the cell, counting from one.
+    NonNumericCell(String, usize, usize),
+
+    /// This node does not accept data from the host application
+    HostDataNotSupported,
+
+    /// The region being uploaded does not fit inside the texture.
+    /// The arrays are the offset and size of the region
+    HostDataOutOfBounds([u32; 2], [u32; 2]),
+
+    /// The uploaded data is the wrong length for the region being uploaded.
+    /// The first number is the expected length in bytes, the second is the supplied length
+    HostDataSizeMismatch(usize, usize),
+
+    /// This node does not read anything back to the CPU
+    ReadbackNotSupported,
+
+    /// A readback node has been asked to read a region with no texels in it
+    EmptyReadbackRegion,
+
+    /// A uniform was given a value of a different type to the one it was declared with.
+    /// String is the name of the uniform
+    UniformTypeMismatch(String),
+
+    /// There were no images to load into this node (eg an empty
+    /// texture array or image sequence)
+    NoImages,
+
     /// There is no shader defined for this renderpass!
     NoShader,
 
@@ -34,6 +135,115 @@ pub enum NodeError {
     ShaderError(crate::shader::ShaderError),
 }
 
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchInputTexture(slot) => write!(f, "there is no input slot \"{}\"", slot),
+            Self::NoSuchOutputTexture(slot) => write!(f, "there is no output slot \"{}\"", slot),
+            Self::CreateFramebufferFailed(message) => {
+                write!(f, "failed to create a framebuffer: {}", message)
+            }
+            Self::CreateTextureFailed(message) => {
+                write!(f, "failed to create a texture: {}", message)
+            }
+            Self::CreateBufferFailed(message) => {
+                write!(f, "failed to create a buffer: {}", message)
+            }
+            Self::MissingResource(path) => write!(f, "{}: file not found", path),
+            Self::IncludeCycle(cycle) => {
+                write!(f, "files include each other: {}", cycle.join(" -> "))
+            }
+            Self::MalformedInclude(path, line) => write!(
+                f,
+                "{}:{}: #include must be followed by a quoted path",
+                path, line
+            ),
+            Self::DuplicateInputSlotName(slot) => {
+                write!(f, "more than one input slot is called \"{}\"", slot)
+            }
+            Self::DuplicateOutputSlotName(slot) => {
+                write!(f, "more than one output slot is called \"{}\"", slot)
+            }
+            Self::TooManyInputSlots(max) => {
+                write!(
+                    f,
+                    "there are more input slots than the GPU supports ({})",
+                    max
+                )
+            }
+            Self::TooManyOutputSlots(max) => {
+                write!(
+                    f,
+                    "there are more output slots than the GPU supports ({})",
+                    max
+                )
+            }
+            Self::InvalidImage(path, _) => write!(f, "{}: the image could not be decoded", path),
+            Self::UnsupportedImageFormat(path) => write!(
+                f,
+                "{}: only greyscale, RGB and RGBA images are supported",
+                path
+            ),
+            Self::UnsupportedColorSpace(path) => {
+                write!(f, "{}: only 8 bit RGB and RGBA images can be sRGB", path)
+            }
+            Self::ImageSizeMismatch(path) => write!(
+                f,
+                "{}: the image is a different size or format to the others in the node",
+                path
+            ),
+            Self::InvalidSpriteSheet(path, _) => {
+                write!(f, "{}: the sprite sheet could not be parsed", path)
+            }
+            Self::DuplicateSpriteDefine(define, first, second) => write!(
+                f,
+                "\"{}\" and \"{}\" would both be defined as {}, so one of them needs renaming",
+                first, second, define
+            ),
+            Self::InvalidFont(path) => write!(f, "{}: the font could not be parsed", path),
+            Self::NoGlyphs => write!(f, "the font has no characters to rasterise"),
+            Self::InvalidMap(path, message) => write!(f, "{}: {}", path, message),
+            Self::MissingLayer(layer) => write!(f, "the map has no layer \"{}\"", layer),
+            Self::InvalidTable(path, message) => write!(f, "{}: {}", path, message),
+            Self::NonNumericCell(path, row, column) => write!(
+                f,
+                "{}: the cell at row {}, column {} is not a number",
+                path, row, column
+            ),
+            Self::HostDataNotSupported => write!(f, "the node doesn't accept data from the host"),
+            Self::HostDataOutOfBounds(offset, size) => write!(
+                f,
+                "a region of size {:?} at {:?} doesn't fit in the texture",
+                size, offset
+            ),
+            Self::HostDataSizeMismatch(expected, actual) => {
+                write!(f, "expected {} bytes of data, but got {}", expected, actual)
+            }
+            Self::ReadbackNotSupported => write!(f, "the node doesn't read anything back"),
+            Self::EmptyReadbackRegion => write!(f, "the region to read back is empty"),
+            Self::UniformTypeMismatch(name) => write!(
+                f,
+                "the value for uniform \"{}\" is a different type to the one in the config",
+                name
+            ),
+            Self::NoImages => write!(f, "there are no images to load"),
+            Self::NoShader => write!(f, "the renderpass has no shader"),
+            Self::ShaderError(_) => write!(f, "the shader could not be built"),
+        }
+    }
+}
+
+impl std::error::Error for NodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidImage(_, err) => Some(err),
+            Self::InvalidSpriteSheet(_, err) => Some(err),
+            Self::ShaderError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
 pub trait Node: Any {
     /// Returns the name of this node
     fn get_name(&self) -> &String;
@@ -42,6 +252,12 @@ pub trait Node: Any {
     /// node
     fn bind(&mut self, gl: &glow::Context, quad: &crate::quad::Quad, game_state: &crate::GameState);
 
+    /// Whether the quad should be drawn after this node is bound. Only nodes that render
+    /// with a shader draw anything; data nodes do all their work in `bind`.
+    fn draws(&self) -> bool {
+        false
+    }
+
     /// Runs after a node has finished drawing, but before the next one is setup up.
     fn post_draw(
         &mut self,
@@ -61,10 +277,50 @@ pub trait Node: Any {
     /// Sets the input texture. If there is no texture with the name, it returns the NodeError::NoSuchInputTexture error
     fn set_input_texture(&mut self, name: &String, texture: Texture) -> Result<(), NodeError>;
 
+    /// Returns the uniforms that accompany the output texture with the provided name. These
+    /// should match the ones declared by `config_file::Node::get_link_uniforms`.
+    fn get_output_uniforms(&self, _name: &String) -> Vec<(String, UniformValue)> {
+        vec![]
+    }
+
+    /// Receives the uniforms that accompany the input texture with the provided name.
+    fn set_input_uniforms(&mut self, _name: &String, _uniforms: Vec<(String, UniformValue)>) {}
+
+    /// Queues data from the host application to be copied into this node's texture
+    /// the next time it is bound. `region` is `[x, y, width, height]` in texels, or
+    /// `None` for the whole texture. Nodes that don't accept data return the
+    /// NodeError::HostDataNotSupported error.
+    fn queue_host_data(
+        &mut self,
+        _region: Option<[u32; 4]>,
+        _data: &[u8],
+    ) -> Result<(), NodeError> {
+        Err(NodeError::HostDataNotSupported)
+    }
+
+    /// Returns the most recent data this node has read back from the GPU, or None if
+    /// no read has completed yet. Nodes that don't read anything back return the
+    /// NodeError::ReadbackNotSupported error.
+    fn get_readback(&self) -> Result<Option<Vec<u8>>, NodeError> {
+        Err(NodeError::ReadbackNotSupported)
+    }
+
+    /// Changes the value of a user-defined uniform. Returns whether this node has a
+    /// uniform with the name, so that nodes which don't can ignore it.
+    fn set_uniform(&mut self, _name: &str, _value: &UniformValue) -> Result<bool, NodeError> {
+        Ok(false)
+    }
+
     /// Retrieves the current input texture. This is useful for external programs trying to get insight into
     /// gametoy (such as the editor)
     fn get_input_texture(&self, name: &String) -> Result<Option<Texture>, NodeError> {
-        unimplemented!()
+        Err(NodeError::NoSuchInputTexture(name.clone()))
+    }
+
+    /// Problems found while creating the node that don't stop it from running, such as an
+    /// input slot the shader never reads.
+    fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        vec![]
     }
 
     /// If a node has it's own output connected to it's own input (aka self-referential), then some
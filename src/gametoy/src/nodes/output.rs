@@ -7,9 +7,14 @@ texture is connected to this is draw directly onto the screen.
 
 This buffer is always at the resolution of the users display.
 
+If the node is configured with a linear colour space, the texture
+is converted to sRGB as it is drawn so that passes can do their
+lighting maths in linear space.
+
 !*/
 
 use super::node;
+use super::NodeError;
 use crate::config_file;
 use crate::quad::Quad;
 use crate::shader;
@@ -26,30 +31,43 @@ pub struct Output {
 
     pub output_tex_uniform: glow::UniformLocation,
 
+    pub encode_srgb_uniform: Option<glow::UniformLocation>,
+
     pub output_texture: Option<glow::Texture>,
+
+    pub color_space: config_file::ColorSpace,
 }
 
 impl Output {
     pub const INPUT_BUFFER_NAME: &'static str = "col";
 
-    pub fn create_from_config(gl: &glow::Context, config: &config_file::OutputConfig) -> Self {
+    pub fn create_from_config(
+        gl: &glow::Context,
+        config: &config_file::OutputConfig,
+    ) -> Result<Self, NodeError> {
         let shader_program = shader::SimpleShader::new(
             &gl,
             include_str!("../resources/shader.vert"),
             include_str!("../resources/output_node.frag"),
         )
-        .expect("Failed to create output shader");
+        .map_err(NodeError::ShaderError)?;
 
         let output_tex_uniform = unsafe { gl.get_uniform_location(shader_program.program, "col") }
-            .expect("Output shader has no 'col' uniform");
+            .ok_or_else(|| {
+                NodeError::ShaderError(shader::ShaderError::MissingUniform("col".to_string()))
+            })?;
+        let encode_srgb_uniform =
+            unsafe { gl.get_uniform_location(shader_program.program, "encode_srgb") };
 
-        Self {
+        Ok(Self {
             name: config.name.clone(),
             resolution: [1920, 1080],
             output_tex_uniform,
+            encode_srgb_uniform,
             shader_program,
             output_texture: None,
-        }
+            color_space: config.color_space.clone(),
+        })
     }
 }
 
@@ -76,9 +94,16 @@ impl node::Node for Output {
             gl.bind_texture(glow::TEXTURE_2D, self.output_texture);
             // Tell WebGL which uniform refers to this texture unit
             gl.uniform_1_i32(Some(&self.output_tex_uniform), texture_unit_id as i32);
+
+            let encode_srgb = self.color_space == config_file::ColorSpace::Linear;
+            gl.uniform_1_i32(self.encode_srgb_uniform.as_ref(), encode_srgb as i32);
         }
     }
 
+    fn draws(&self) -> bool {
+        true
+    }
+
     fn get_output_texture(&self, name: &String) -> Result<glow::Texture, node::NodeError> {
         Err(node::NodeError::NoSuchOutputTexture(name.clone()))
     }
@@ -0,0 +1,255 @@
+/*!
+The Readback Node
+-----------------
+
+Copies a small region of the texture connected to it's "tex" input back to
+the CPU every frame, so the host application can react to what happens in
+the shaders (eg showing the score in the window title or writing a save
+file). The most recent result is returned by `GameToy::get_readback`.
+
+To avoid stalling the pipeline the copy goes into one of a ring of pixel pack
+buffers and a fence is used to find out when it has finished, so results
+arrive a frame or two after they were rendered. If fences aren't available
+(as on some WebGL implementations) the node falls back to a synchronous
+`read_pixels`.
+
+The data is tightly packed texels in the configured format. The first row is
+the bottom of the region, matching texture coordinates.
+
+!*/
+
+use super::{Node, NodeError};
+use crate::config_file::{ReadbackConfig, ReadbackFormat};
+use crate::quad::Quad;
+use crate::GameState;
+use glow::HasContext;
+
+/// How many reads can be in flight at once
+const NUM_PACK_BUFFERS: usize = 3;
+
+/// A pixel pack buffer and the fence for the read into it, if one is in flight
+struct PackBuffer {
+    buffer: glow::Buffer,
+    fence: Option<glow::Fence>,
+}
+
+pub struct Readback {
+    pub name: String,
+    pub input_texture: Option<glow::Texture>,
+
+    framebuffer: glow::Framebuffer,
+    offset: [u32; 2],
+    size: [u32; 2],
+    format: ReadbackFormat,
+
+    pack_buffers: Vec<PackBuffer>,
+
+    /// The buffer the next read goes into. This is also the buffer holding
+    /// the oldest read.
+    next_buffer: usize,
+
+    /// Set to false once creating a fence fails, after which reads are synchronous
+    use_fences: bool,
+
+    latest: Option<Vec<u8>>,
+}
+
+impl Readback {
+    pub const INPUT_BUFFER_NAME: &'static str = "tex";
+
+    pub fn create_from_config(
+        gl: &glow::Context,
+        config: &ReadbackConfig,
+    ) -> Result<Self, NodeError> {
+        if config.size[0] == 0 || config.size[1] == 0 {
+            return Err(NodeError::EmptyReadbackRegion);
+        }
+
+        let framebuffer = unsafe {
+            gl.create_framebuffer()
+                .map_err(NodeError::CreateFramebufferFailed)?
+        };
+
+        let buffer_size = Self::data_len(config.size, &config.format);
+        let mut pack_buffers = Vec::with_capacity(NUM_PACK_BUFFERS);
+        for _ in 0..NUM_PACK_BUFFERS {
+            unsafe {
+                let buffer = gl.create_buffer().map_err(NodeError::CreateBufferFailed)?;
+                gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(buffer));
+                gl.buffer_data_size(
+                    glow::PIXEL_PACK_BUFFER,
+                    buffer_size as i32,
+                    glow::STREAM_READ,
+                );
+                pack_buffers.push(PackBuffer {
+                    buffer,
+                    fence: None,
+                });
+            }
+        }
+        unsafe {
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            input_texture: None,
+            framebuffer,
+            offset: config.offset,
+            size: config.size,
+            format: config.format.clone(),
+            pack_buffers,
+            next_buffer: 0,
+            use_fences: true,
+            latest: None,
+        })
+    }
+
+    fn data_len(size: [u32; 2], format: &ReadbackFormat) -> usize {
+        size[0] as usize * size[1] as usize * format.to_bytes_per_pixel()
+    }
+
+    /// Copies out any reads that have finished, oldest first so that
+    /// `latest` ends up as the newest one.
+    unsafe fn collect_finished_reads(&mut self, gl: &glow::Context) {
+        for i in 0..NUM_PACK_BUFFERS {
+            let pack_buffer = &mut self.pack_buffers[(self.next_buffer + i) % NUM_PACK_BUFFERS];
+            let fence = match pack_buffer.fence {
+                Some(fence) => fence,
+                None => continue,
+            };
+            if gl.get_sync_status(fence) != glow::SIGNALED {
+                // Later reads can't have finished either
+                break;
+            }
+            gl.delete_sync(fence);
+            pack_buffer.fence = None;
+
+            let mut data = vec![0; Self::data_len(self.size, &self.format)];
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(pack_buffer.buffer));
+            gl.get_buffer_sub_data(glow::PIXEL_PACK_BUFFER, 0, &mut data);
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            self.latest = Some(data);
+        }
+    }
+
+    /// Starts reading the bound framebuffer into the next pack buffer. If
+    /// every buffer is still busy the read is skipped rather than waiting.
+    unsafe fn start_async_read(&mut self, gl: &glow::Context) {
+        let (format, data_type) = self.format.to_format_and_type();
+        let pack_buffer = &mut self.pack_buffers[self.next_buffer];
+        if pack_buffer.fence.is_some() {
+            return;
+        }
+
+        gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(pack_buffer.buffer));
+        gl.read_pixels(
+            self.offset[0] as i32,
+            self.offset[1] as i32,
+            self.size[0] as i32,
+            self.size[1] as i32,
+            format,
+            data_type,
+            glow::PixelPackData::BufferOffset(0),
+        );
+        gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+
+        match gl.fence_sync(glow::SYNC_GPU_COMMANDS_COMPLETE, 0) {
+            Ok(fence) => {
+                pack_buffer.fence = Some(fence);
+                self.next_buffer = (self.next_buffer + 1) % NUM_PACK_BUFFERS;
+            }
+            Err(_) => {
+                self.use_fences = false;
+                self.read_sync(gl);
+            }
+        }
+    }
+
+    /// Reads the bound framebuffer straight into `latest`
+    unsafe fn read_sync(&mut self, gl: &glow::Context) {
+        let (format, data_type) = self.format.to_format_and_type();
+        let mut data = vec![0; Self::data_len(self.size, &self.format)];
+        gl.read_pixels(
+            self.offset[0] as i32,
+            self.offset[1] as i32,
+            self.size[0] as i32,
+            self.size[1] as i32,
+            format,
+            data_type,
+            glow::PixelPackData::Slice(&mut data),
+        );
+        self.latest = Some(data);
+    }
+}
+
+impl Node for Readback {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn update_resolution(&mut self, _gl: &glow::Context, _screen_resolution: &[i32; 2]) {}
+
+    fn bind(&mut self, gl: &glow::Context, _quad: &Quad, _game_state: &GameState) {
+        unsafe {
+            if self.use_fences {
+                self.collect_finished_reads(gl);
+            }
+
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.framebuffer));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                self.input_texture,
+                0,
+            );
+            if self.input_texture.is_some() {
+                if self.use_fences {
+                    self.start_async_read(gl);
+                } else {
+                    self.read_sync(gl);
+                }
+            }
+
+            // Leave the input texture free to be sampled by the nodes after this one
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                None,
+                0,
+            );
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+    }
+
+    fn get_output_texture(&self, name: &String) -> Result<glow::Texture, NodeError> {
+        Err(NodeError::NoSuchOutputTexture(name.clone()))
+    }
+
+    fn set_input_texture(
+        &mut self,
+        name: &String,
+        texture: glow::Texture,
+    ) -> Result<(), NodeError> {
+        if name == Self::INPUT_BUFFER_NAME {
+            self.input_texture = Some(texture);
+            Ok(())
+        } else {
+            Err(NodeError::NoSuchInputTexture(name.clone()))
+        }
+    }
+
+    fn get_input_texture(&self, name: &String) -> Result<Option<glow::Texture>, NodeError> {
+        if name == Self::INPUT_BUFFER_NAME {
+            Ok(self.input_texture)
+        } else {
+            Err(NodeError::NoSuchInputTexture(name.clone()))
+        }
+    }
+
+    fn get_readback(&self) -> Result<Option<Vec<u8>>, NodeError> {
+        Ok(self.latest.clone())
+    }
+}
@@ -15,12 +15,17 @@ TODOs:
 
 !*/
 
+use super::atlas;
 use super::node;
 use super::NodeError;
 use crate::config_file;
+use crate::config_file::UniformValue;
+use crate::diagnostics::{Diagnostic, Severity};
 use crate::gamedata::GameData;
+use crate::preprocessor::Preprocessor;
 use crate::quad::Quad;
 use crate::shader::SimpleShader;
+use crate::source_map::ShaderSource;
 use crate::GameState;
 use glow::HasContext;
 
@@ -47,7 +52,7 @@ pub struct RenderPass {
     config: config_file::RenderPassConfig,
 
     /// The textures that this renderpass reads from when rendering.
-    input_textures: HashMap<String, Option<glow::Texture>>,
+    input_textures: HashMap<String, InputTexture>,
 
     /// The framebuffer that the shader renders into
     framebuffer: glow::Framebuffer,
@@ -62,6 +67,20 @@ pub struct RenderPass {
     back_output_textures: Option<HashMap<String, OutputTexture>>,
 
     uniform_map: HashMap<String, glow::UniformLocation>,
+
+    /// Uniforms that arrived along links with the input textures, keyed
+    /// by the full uniform name.
+    link_uniforms: HashMap<String, UniformValue>,
+
+    /// The current values of the uniforms declared in the config
+    user_uniforms: HashMap<String, UniformValue>,
+}
+
+/// An input slot and the texture currently connected to it
+struct InputTexture {
+    tex: Option<glow::Texture>,
+    /// What target the texture needs to be bound to (eg TEXTURE_2D)
+    target: u32,
 }
 
 /// Container for a texture and it's configuration.
@@ -107,8 +126,6 @@ impl OutputTexture {
                 glow::TEXTURE_WRAP_T,
                 glow::REPEAT as i32,
             );
-
-            assert_eq!(gl.get_error(), glow::NO_ERROR);
         }
 
         Ok(Self {
@@ -153,6 +170,22 @@ impl RenderPass {
         gamedata: &GameData,
         config: &config_file::RenderPassConfig,
     ) -> Result<Self, NodeError> {
+        // Each input slot needs a texture unit and each output slot a draw
+        // buffer, and the GPU only has so many.
+        let (max_inputs, max_outputs) = unsafe {
+            (
+                gl.get_parameter_i32(glow::MAX_TEXTURE_IMAGE_UNITS),
+                gl.get_parameter_i32(glow::MAX_COLOR_ATTACHMENTS)
+                    .min(gl.get_parameter_i32(glow::MAX_DRAW_BUFFERS)),
+            )
+        };
+        if config.input_texture_slots.len() > max_inputs as usize {
+            return Err(NodeError::TooManyInputSlots(max_inputs as usize));
+        }
+        if config.output_texture_slots.len() > max_outputs as usize {
+            return Err(NodeError::TooManyOutputSlots(max_outputs as usize));
+        }
+
         // First we create the framebuffer and output textures that this shader
         // will render into.
         let resolution = match config.resolution_scaling_mode {
@@ -164,12 +197,13 @@ impl RenderPass {
         let (framebuffer, output_textures) =
             create_framebuffer_and_textures(gl, config, resolution)?;
 
+        let shader_source = generate_shader_text(config, gamedata)?;
         let mut shader_program = SimpleShader::new(
             gl,
             include_str!("../resources/shader.vert"),
-            &generate_shader_text(config, gamedata)?,
+            &shader_source.text,
         )
-        .map_err(NodeError::ShaderError)?;
+        .map_err(|err| NodeError::ShaderError(err.map_source(&shader_source.source_map)))?;
         shader_program.bind(gl);
 
         // If we know what uniforms exist in advance we can replace lots of GL calls with
@@ -181,7 +215,6 @@ impl RenderPass {
         insert_uniform_if_exists(gl, &mut uniform_map, prog, "iTime".to_string());
         insert_uniform_if_exists(gl, &mut uniform_map, prog, "iTimeDelta".to_string());
         insert_uniform_if_exists(gl, &mut uniform_map, prog, "iFrame".to_string());
-        insert_uniform_if_exists(gl, &mut uniform_map, prog, "iMouse".to_string());
         insert_uniform_if_exists(gl, &mut uniform_map, prog, "iDate".to_string());
 
         // Make sure that our input textures are known
@@ -189,8 +222,12 @@ impl RenderPass {
         for input_texture_slot in config.input_texture_slots.iter() {
             insert_uniform_if_exists(gl, &mut uniform_map, prog, input_texture_slot.name.clone());
 
+            let input_texture = InputTexture {
+                tex: None,
+                target: input_texture_slot.sampler_type.to_texture_target(),
+            };
             if input_textures
-                .insert(input_texture_slot.name.clone(), None)
+                .insert(input_texture_slot.name.clone(), input_texture)
                 .is_some()
             {
                 return Err(NodeError::DuplicateInputSlotName(
@@ -198,6 +235,14 @@ impl RenderPass {
                 ));
             }
         }
+        for (uniform_name, _) in get_linked_uniforms(config, gamedata) {
+            insert_uniform_if_exists(gl, &mut uniform_map, prog, uniform_name);
+        }
+        let mut user_uniforms = HashMap::new();
+        for uniform in config.uniforms.iter() {
+            insert_uniform_if_exists(gl, &mut uniform_map, prog, uniform.name.clone());
+            user_uniforms.insert(uniform.name.clone(), uniform.clamp(&uniform.value));
+        }
 
         Ok(Self {
             name: config.name.clone(),
@@ -211,6 +256,8 @@ impl RenderPass {
             back_output_textures: None,
             frame: 0,
             uniform_map,
+            link_uniforms: HashMap::new(),
+            user_uniforms,
         })
     }
 }
@@ -259,16 +306,21 @@ impl node::Node for RenderPass {
                 );
             }
 
+            let uniforms = self.link_uniforms.iter().chain(self.user_uniforms.iter());
+            for (uniform_name, value) in uniforms {
+                if let Some(loc) = self.uniform_map.get(uniform_name) {
+                    upload_uniform(gl, loc, value);
+                }
+            }
+
             // Textures
             for (texture_id, (texture_name, texture)) in self.input_textures.iter().enumerate() {
                 gl.active_texture(texture_unit_id_to_gl(texture_id as u32));
-                gl.bind_texture(glow::TEXTURE_2D, *texture);
-                // Tell WebGL which uniform refers to this texture unit
+                gl.bind_texture(texture.target, texture.tex);
+                // Tell WebGL which uniform refers to this texture unit. Slots
+                // without a uniform are reported by `get_diagnostics`
                 if let Some(loc) = self.uniform_map.get(texture_name) {
                     gl.uniform_1_i32(Some(loc), texture_id as i32);
-                } else {
-                    // TODO: Raise a warning somehow?
-                    // panic!("No Uniform for input texture");
                 }
             }
 
@@ -277,15 +329,17 @@ impl node::Node for RenderPass {
         self.frame = self.frame.overflowing_add(1).0;
     }
 
+    fn draws(&self) -> bool {
+        true
+    }
+
     fn post_draw(&mut self, gl: &glow::Context, game_state: &GameState) -> Result<(), NodeError> {
-        if self.back_output_textures.is_some() && self.frame % 2 == 1  {
-            for outtex in self.back_output_textures.as_ref().unwrap().values() {
-                outtex.generate_mip(gl);
-            }
-        } else {
-            for outtex in self.output_textures.values() {
-                outtex.generate_mip(gl);
-            }
+        let output_textures = match &self.back_output_textures {
+            Some(back_output_textures) if self.frame % 2 == 1 => back_output_textures,
+            _ => &self.output_textures,
+        };
+        for outtex in output_textures.values() {
+            outtex.generate_mip(gl);
         }
         Ok(())
 
@@ -316,20 +370,15 @@ impl node::Node for RenderPass {
     }
 
     fn get_output_texture(&self, name: &String) -> Result<glow::Texture, node::NodeError> {
-        if self.back_output_textures.is_some() && self.frame % 2 == 1 {
+        let output_textures = match &self.back_output_textures {
             // If we are rendering to the front textures this frame, return the back textures
-            self.back_output_textures
-                .as_ref()
-                .unwrap()
-                .get(name)
-                .map(|x| x.tex)
-                .ok_or(node::NodeError::NoSuchOutputTexture(name.clone()))
-        } else {
-            self.output_textures
-                .get(name)
-                .map(|x| x.tex)
-                .ok_or(node::NodeError::NoSuchOutputTexture(name.clone()))
-        }
+            Some(back_output_textures) if self.frame % 2 == 1 => back_output_textures,
+            _ => &self.output_textures,
+        };
+        output_textures
+            .get(name)
+            .map(|x| x.tex)
+            .ok_or(node::NodeError::NoSuchOutputTexture(name.clone()))
     }
 
     fn set_input_texture(
@@ -339,13 +388,33 @@ impl node::Node for RenderPass {
     ) -> Result<(), node::NodeError> {
         match self.input_textures.get_mut(name) {
             Some(slot) => {
-                slot.replace(texture);
+                slot.tex.replace(texture);
                 Ok(())
             }
             None => Err(node::NodeError::NoSuchInputTexture(name.clone())),
         }
     }
 
+    fn set_input_uniforms(&mut self, name: &String, uniforms: Vec<(String, UniformValue)>) {
+        for (uniform_name, value) in uniforms {
+            self.link_uniforms
+                .insert(format!("{}_{}", name, uniform_name), value);
+        }
+    }
+
+    fn set_uniform(&mut self, name: &str, value: &UniformValue) -> Result<bool, NodeError> {
+        let uniform = match self.config.uniforms.iter().find(|u| u.name == name) {
+            Some(uniform) => uniform,
+            None => return Ok(false),
+        };
+        if !uniform.value.is_same_type(value) {
+            return Err(NodeError::UniformTypeMismatch(name.to_string()));
+        }
+        self.user_uniforms
+            .insert(name.to_string(), uniform.clamp(value));
+        Ok(true)
+    }
+
     fn set_up_self_reference(
         &mut self,
         gl: &glow::Context,
@@ -356,14 +425,30 @@ impl node::Node for RenderPass {
         // so we need to guard against creating lots of back-buffers
         if self.back_framebuffer.is_none() {
             let (framebuffer, output_textures) =
-                create_framebuffer_and_textures(gl, &self.config, self.resolution)
-                    .expect("Failed to create backbuffer");
+                create_framebuffer_and_textures(gl, &self.config, self.resolution)?;
 
             self.back_framebuffer = Some(framebuffer);
             self.back_output_textures = Some(output_textures);
         }
         Ok(())
     }
+
+    fn get_diagnostics(&self) -> Vec<Diagnostic> {
+        self.config
+            .input_texture_slots
+            .iter()
+            .filter(|slot| !self.uniform_map.contains_key(&slot.name))
+            .map(|slot| {
+                Diagnostic::new(
+                    Severity::Warning,
+                    format!(
+                        "input slot \"{}\" is misspelt or unused by the shader",
+                        slot.name
+                    ),
+                )
+            })
+            .collect()
+    }
 }
 
 /// Attempts to fetch a uniform's location from a shader program and insert it into a hashmap
@@ -380,6 +465,69 @@ fn insert_uniform_if_exists(
     }
 }
 
+/// Uploads a value to a uniform in the currently bound shader program
+unsafe fn upload_uniform(gl: &glow::Context, loc: &glow::UniformLocation, value: &UniformValue) {
+    match value {
+        UniformValue::Float(x) => gl.uniform_1_f32(Some(loc), *x),
+        UniformValue::Int(x) => gl.uniform_1_i32(Some(loc), *x),
+        UniformValue::Vec2(v) => gl.uniform_2_f32(Some(loc), v[0], v[1]),
+        UniformValue::Vec3(v) => gl.uniform_3_f32(Some(loc), v[0], v[1], v[2]),
+        UniformValue::Vec4(v) | UniformValue::Color(v) => {
+            gl.uniform_4_f32(Some(loc), v[0], v[1], v[2], v[3])
+        }
+        UniformValue::Bool(x) => gl.uniform_1_i32(Some(loc), *x as i32),
+    }
+}
+
+/// Finds the uniforms that arrive along links into this renderpass's input slots.
+/// Returns the full uniform name (`<input slot>_<uniform>`) and a placeholder value
+/// that defines the type.
+fn get_linked_uniforms(
+    config: &config_file::RenderPassConfig,
+    gamedata: &GameData,
+) -> Vec<(String, UniformValue)> {
+    let graph = &gamedata.config_file.graph;
+    let mut uniforms = vec![];
+    for link in graph.links.iter().filter(|l| l.end_node == config.name) {
+        if let Some(start_node) = graph.nodes.iter().find(|n| n.get_name() == link.start_node) {
+            for (uniform_name, value) in start_node.get_link_uniforms() {
+                let full_name = format!("{}_{}", link.end_input_slot, uniform_name);
+                if !uniforms.iter().any(|(name, _)| name == &full_name) {
+                    uniforms.push((full_name, value));
+                }
+            }
+        }
+    }
+    uniforms
+}
+
+/// Generates the extra shader source needed by nodes linked into this renderpass:
+/// `#define`s for the sprites in an atlas and the helper functions for fonts.
+fn get_linked_preamble(
+    config: &config_file::RenderPassConfig,
+    gamedata: &GameData,
+) -> Result<String, NodeError> {
+    let graph = &gamedata.config_file.graph;
+    let mut preamble = String::new();
+    for (define, index) in atlas::linked_sprite_defines(graph, &config.name, gamedata)? {
+        preamble += &format!("#define {} {}\n", define, index);
+    }
+    let has_font = graph
+        .links
+        .iter()
+        .filter(|l| l.end_node == config.name)
+        .any(|link| {
+            matches!(
+                graph.nodes.iter().find(|n| n.get_name() == link.start_node),
+                Some(config_file::Node::Font(_))
+            )
+        });
+    if has_font {
+        preamble += include_str!("../resources/font_helpers.frag");
+    }
+    Ok(preamble)
+}
+
 fn create_framebuffer_and_textures(
     gl: &glow::Context,
     config: &config_file::RenderPassConfig,
@@ -469,20 +617,21 @@ fn create_framebuffer_and_textures(
     Ok((framebuffer, output_textures))
 }
 
-/// Returns the code that is inserted before the users GLSL. This includes
-/// texture uniforms, the shader version etc.
-fn generate_shader_text(
+/// Assembles the complete fragment shader: a generated preamble containing
+/// texture uniforms, the shader version etc. followed by the users GLSL. The
+/// source map records which file each line came from.
+pub fn generate_shader_text(
     config: &config_file::RenderPassConfig,
     gamedata: &GameData,
-) -> Result<String, NodeError> {
-    let mut shader_text = String::new();
+) -> Result<ShaderSource, NodeError> {
+    let mut preamble = String::new();
 
     // Static things such as the shader version and "global" uniforms
-    shader_text += include_str!("../resources/renderpass_static.frag");
+    preamble += include_str!("../resources/renderpass_static.frag");
 
     // Generate some shader source to represent the output textures
     for (slot_id, output_texture_slot) in config.output_texture_slots.iter().enumerate() {
-        shader_text += &format!(
+        preamble += &format!(
             "layout(location={}) out vec{} {};\n",
             slot_id,
             output_texture_slot.format.to_channel_count(),
@@ -492,33 +641,116 @@ fn generate_shader_text(
 
     // Generate some shader source to represent the input textures
     for input_texture_slot in config.input_texture_slots.iter() {
-        shader_text += &format!("uniform sampler2D {};\n", input_texture_slot.name);
+        preamble += &format!(
+            "uniform {} {};\n",
+            input_texture_slot.sampler_type.to_glsl_type(),
+            input_texture_slot.name
+        );
     }
+    for (uniform_name, value) in get_linked_uniforms(config, gamedata) {
+        preamble += &format!("uniform {} {};\n", value.to_glsl_type(), uniform_name);
+    }
+    for uniform in config.uniforms.iter() {
+        preamble += &format!(
+            "uniform {} {};\n",
+            uniform.value.to_glsl_type(),
+            uniform.name
+        );
+    }
+    preamble += &get_linked_preamble(config, gamedata)?;
+    if config.shadertoy_compat {
+        preamble += &generate_shadertoy_preamble(config);
+    }
+
+    let mut shader_source = ShaderSource::new();
+    shader_source.push_generated(&preamble);
 
-    let preamble_length = shader_text.len();
+    let preamble_length = shader_source.text.len();
     // Now we can assemble all the shader source into a single file and compile it
+    let mut preprocessor = Preprocessor::new(&gamedata.shader_sources);
     for shader_path in config.fragment_shader_paths.iter() {
-        let source = gamedata
-            .shader_sources
-            .get(shader_path)
-            .ok_or(NodeError::MissingResource(shader_path.to_string()))?;
-        shader_text += source;
+        preprocessor.expand_into(shader_path, &mut shader_source)?;
     }
     // Nothing was added to the shader when reading from disk, so there is no
     // actual rendering going to be performed here.
-    if shader_text.len() == preamble_length {
+    if shader_source.text.len() == preamble_length {
         Err(NodeError::NoShader)?;
     }
+    if config.shadertoy_compat {
+        shader_source.push_generated(&generate_shadertoy_main(config));
+    }
+
+    Ok(shader_source)
+}
 
-    Ok(shader_text)
+/// Number of input slots that are given Shadertoy's `iChannel` names
+const SHADERTOY_CHANNELS: usize = 4;
+
+/// Declares the names Shadertoy shaders expect that GameToy doesn't provide
+/// under the same name.
+fn generate_shadertoy_preamble(config: &config_file::RenderPassConfig) -> String {
+    let mut preamble = String::new();
+    // Shadertoy's iFrame is an int. Macros don't expand recursively, so the
+    // inner iFrame is still the uniform.
+    preamble += "#define iFrame int(iFrame)\n";
+    preamble += "#define iFrameRate (1.0 / iTimeDelta)\n";
+    preamble += "const float iSampleRate = 44100.0;\n";
+    // GameToy doesn't track the mouse, so it is never moved or clicked
+    preamble += "const vec4 iMouse = vec4(0.0);\n";
+    preamble += &format!("vec3 iChannelResolution[{}];\n", SHADERTOY_CHANNELS);
+    preamble += &format!("float iChannelTime[{}];\n", SHADERTOY_CHANNELS);
+
+    for (channel, slot) in config
+        .input_texture_slots
+        .iter()
+        .take(SHADERTOY_CHANNELS)
+        .enumerate()
+    {
+        let channel_name = format!("iChannel{}", channel);
+        if slot.name != channel_name {
+            preamble += &format!("#define {} {}\n", channel_name, slot.name);
+        }
+    }
+    preamble
+}
+
+/// Generates a `main` that fills in the per-channel values and calls the
+/// Shadertoy `mainImage`, writing it's colour to the first output slot.
+fn generate_shadertoy_main(config: &config_file::RenderPassConfig) -> String {
+    let mut main = String::from("\nvoid main() {\n");
+    for channel in 0..SHADERTOY_CHANNELS {
+        // Globals aren't zero initialised in GLSL ES, so unused channels are set too
+        let resolution = match config.input_texture_slots.get(channel) {
+            Some(slot) => format!("vec3(textureSize({}, 0).xy, 1.0)", slot.name),
+            None => "vec3(0.0)".to_string(),
+        };
+        main += &format!("    iChannelResolution[{}] = {};\n", channel, resolution);
+        main += &format!("    iChannelTime[{}] = iTime;\n", channel);
+    }
+    main += "    vec4 shadertoy_frag_color = vec4(0.0);\n";
+    main += "    mainImage(shadertoy_frag_color, gl_FragCoord.xy);\n";
+    if let Some(output_slot) = config.output_texture_slots.first() {
+        let swizzle = match output_slot.format.to_channel_count() {
+            1 => ".x",
+            2 => ".xy",
+            3 => ".xyz",
+            _ => "",
+        };
+        main += &format!(
+            "    {} = shadertoy_frag_color{};\n",
+            output_slot.name, swizzle
+        );
+    }
+    main += "}\n";
+    main
 }
 
+// The number of slots is checked against the GPU's limits when the
+// renderpass is created, so these are always valid.
 fn color_attachment_int_to_gl(int: u32) -> u32 {
-    assert!(int <= 10);
     glow::COLOR_ATTACHMENT0 + int
 }
 
 fn texture_unit_id_to_gl(int: u32) -> u32 {
-    assert!(int <= 32);
     glow::TEXTURE0 + int
 }
@@ -0,0 +1,122 @@
+/*!
+The Texture Array Node
+----------------------
+
+Loads a list of images into the layers of a texture array so that
+shaders can sample them with a `sampler2DArray`. This is useful for
+tile atlases where each tile is a layer, as there is no bleeding
+between neighbouring tiles.
+
+All of the images must be the same size and pixel format.
+
+!*/
+
+use super::image::{load_png, mip_levels, set_texture_parameters};
+use super::{Node, NodeError};
+use crate::config_file::TextureArrayConfig;
+use crate::gamedata::GameData;
+use crate::quad::Quad;
+use crate::GameState;
+use glow::HasContext;
+
+pub struct TextureArray {
+    pub name: String,
+    pub texture: glow::Texture,
+}
+
+impl TextureArray {
+    pub const OUTPUT_BUFFER_NAME: &'static str = "tex";
+
+    pub fn create_from_config(
+        gl: &glow::Context,
+        gamedata: &GameData,
+        config: &TextureArrayConfig,
+    ) -> Result<Self, NodeError> {
+        let mut layers = vec![];
+        for path in config.paths.iter() {
+            layers.push(load_png(gamedata, path, &config.color_space)?);
+        }
+
+        let first_layer = layers.first().ok_or(NodeError::NoImages)?;
+        let width = first_layer.width;
+        let height = first_layer.height;
+        let format = first_layer.format.clone();
+        for (layer, path) in layers.iter().zip(config.paths.iter()) {
+            if layer.width != width || layer.height != height || layer.format != format {
+                return Err(NodeError::ImageSizeMismatch(path.clone()));
+            }
+        }
+
+        let new_tex = unsafe {
+            gl.create_texture()
+                .map_err(NodeError::CreateTextureFailed)?
+        };
+
+        unsafe {
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_2D_ARRAY, Some(new_tex));
+
+            let levels = mip_levels(config.generate_mipmap, width);
+            gl.tex_storage_3d(
+                glow::TEXTURE_2D_ARRAY,
+                levels,
+                format.to_sized_internal_format(),
+                width as i32,
+                height as i32,
+                layers.len() as i32,
+            );
+
+            set_texture_parameters(gl, glow::TEXTURE_2D_ARRAY, glow::REPEAT);
+
+            for (layer_id, layer) in layers.iter().enumerate() {
+                gl.tex_sub_image_3d(
+                    glow::TEXTURE_2D_ARRAY,
+                    0,
+                    0,
+                    0,
+                    layer_id as i32,
+                    width as i32,
+                    height as i32,
+                    1,
+                    format.to_format(),
+                    format.to_type(),
+                    glow::PixelUnpackData::Slice(&layer.data),
+                );
+            }
+            if levels > 1 {
+                gl.generate_mipmap(glow::TEXTURE_2D_ARRAY);
+            }
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            texture: new_tex,
+        })
+    }
+}
+
+impl Node for TextureArray {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn update_resolution(&mut self, _gl: &glow::Context, _screen_resolution: &[i32; 2]) {}
+
+    fn bind(&mut self, _gl: &glow::Context, _quad: &Quad, _game_state: &GameState) {}
+
+    fn get_output_texture(&self, name: &String) -> Result<glow::Texture, NodeError> {
+        if name == Self::OUTPUT_BUFFER_NAME {
+            Ok(self.texture)
+        } else {
+            Err(NodeError::NoSuchOutputTexture(name.clone()))
+        }
+    }
+
+    fn set_input_texture(
+        &mut self,
+        name: &String,
+        _texture: glow::Texture,
+    ) -> Result<(), NodeError> {
+        Err(NodeError::NoSuchInputTexture(name.clone()))
+    }
+}
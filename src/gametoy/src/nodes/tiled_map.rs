@@ -0,0 +1,446 @@
+/*!
+The Tiled Map Node
+------------------
+
+Loads a map made in the [Tiled](https://www.mapeditor.org/) editor from
+either the XML (`.tmx`) or JSON (`.tmj`) format. Each layer listed in the
+config becomes an output texture:
+
+ - Tile layers are `R16UI` textures of global tile ids with the flip flags
+   removed. Zero is an empty cell and row zero is the top of the map. Sample
+   these with a `usampler2D` and `texelFetch`.
+ - Object layers are single row `RGBA32F` textures with one texel per object
+   containing `(x, y, width, height)` in pixels.
+
+Each output also passes `size` (cells or objects) and `tile_size` (pixels)
+uniforms along it's links. Layers inside groups are found by name as if
+they were at the top level. Infinite maps are not supported.
+
+!*/
+
+use super::image::set_unmipped_texture_parameters;
+use super::{Node, NodeError};
+use crate::config_file::{TiledMapConfig, UniformValue};
+use crate::gamedata::GameData;
+use crate::quad::Quad;
+use crate::GameState;
+use glow::HasContext;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Tiled stores whether a tile is flipped in the top bits of the id
+const TILE_FLAG_MASK: u32 = 0xF000_0000;
+
+pub struct TiledMap {
+    pub name: String,
+    layers: HashMap<String, LayerTexture>,
+    tile_size: [f32; 2],
+}
+
+struct LayerTexture {
+    texture: glow::Texture,
+    size: [f32; 2],
+}
+
+/// The parts of a map that are uploaded to the GPU
+struct MapData {
+    tile_size: [u32; 2],
+    layers: Vec<MapLayer>,
+}
+
+struct MapLayer {
+    name: String,
+    contents: LayerContents,
+}
+
+enum LayerContents {
+    Tiles {
+        width: u32,
+        height: u32,
+        ids: Vec<u16>,
+    },
+    Objects(Vec<[f32; 4]>),
+}
+
+impl TiledMap {
+    pub fn create_from_config(
+        gl: &glow::Context,
+        gamedata: &GameData,
+        config: &TiledMapConfig,
+    ) -> Result<Self, NodeError> {
+        let data = gamedata
+            .get_file(&config.path)
+            .ok_or(NodeError::MissingResource(config.path.clone()))?;
+        let text = std::str::from_utf8(data)
+            .map_err(|e| NodeError::InvalidMap(config.path.clone(), e.to_string()))?;
+        let map = if config.path.ends_with(".tmj") {
+            parse_tmj(text)
+        } else {
+            parse_tmx(text)
+        }
+        .map_err(|reason| NodeError::InvalidMap(config.path.clone(), reason))?;
+
+        let mut layers = HashMap::new();
+        for layer_name in config.layers.iter() {
+            let layer = map
+                .layers
+                .iter()
+                .find(|layer| &layer.name == layer_name)
+                .ok_or(NodeError::MissingLayer(layer_name.clone()))?;
+            layers.insert(layer_name.clone(), upload_layer(gl, layer)?);
+        }
+
+        Ok(Self {
+            name: config.name.clone(),
+            layers,
+            tile_size: [map.tile_size[0] as f32, map.tile_size[1] as f32],
+        })
+    }
+}
+
+fn upload_layer(gl: &glow::Context, layer: &MapLayer) -> Result<LayerTexture, NodeError> {
+    let texture = unsafe {
+        gl.create_texture()
+            .map_err(NodeError::CreateTextureFailed)?
+    };
+
+    unsafe {
+        gl.active_texture(glow::TEXTURE1);
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        set_unmipped_texture_parameters(gl, glow::NEAREST);
+    }
+
+    let size = match &layer.contents {
+        LayerContents::Tiles { width, height, ids } => {
+            let bytes: Vec<u8> = ids.iter().flat_map(|id| id.to_ne_bytes()).collect();
+            unsafe {
+                gl.tex_storage_2d(
+                    glow::TEXTURE_2D,
+                    1,
+                    glow::R16UI,
+                    *width as i32,
+                    *height as i32,
+                );
+                // Rows of 16 bit texels are not always a multiple of 4 bytes
+                gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+                gl.tex_sub_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    *width as i32,
+                    *height as i32,
+                    glow::RED_INTEGER,
+                    glow::UNSIGNED_SHORT,
+                    glow::PixelUnpackData::Slice(&bytes),
+                );
+                gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+            }
+            [*width as f32, *height as f32]
+        }
+        LayerContents::Objects(objects) => {
+            // A texture can't be empty, so layers without objects get a
+            // single unused texel.
+            let width = usize::max(objects.len(), 1);
+            let mut bytes: Vec<u8> = objects
+                .iter()
+                .flatten()
+                .flat_map(|value| value.to_ne_bytes())
+                .collect();
+            bytes.resize(width * 16, 0);
+            unsafe {
+                gl.tex_storage_2d(glow::TEXTURE_2D, 1, glow::RGBA32F, width as i32, 1);
+                gl.tex_sub_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    width as i32,
+                    1,
+                    glow::RGBA,
+                    glow::FLOAT,
+                    glow::PixelUnpackData::Slice(&bytes),
+                );
+            }
+            [objects.len() as f32, 1.0]
+        }
+    };
+
+    Ok(LayerTexture { texture, size })
+}
+
+/// Converts the global tile ids of a layer into what is stored in the texture
+fn tile_layer(name: String, width: u32, height: u32, gids: Vec<u32>) -> Result<MapLayer, String> {
+    // A texture can't be empty, and the size comes straight from the file
+    if width == 0 || height == 0 {
+        return Err(format!("Layer {} is {}x{} tiles", name, width, height));
+    }
+    let num_tiles = (width as usize)
+        .checked_mul(height as usize)
+        .ok_or_else(|| format!("Layer {} is too large at {}x{} tiles", name, width, height))?;
+    if gids.len() != num_tiles {
+        return Err(format!(
+            "Layer {} has {} tiles but should have {}",
+            name,
+            gids.len(),
+            num_tiles
+        ));
+    }
+    let mut ids = Vec::with_capacity(gids.len());
+    for gid in gids {
+        let id = gid & !TILE_FLAG_MASK;
+        if id > u16::MAX as u32 {
+            return Err(format!(
+                "Tile id {} in layer {} does not fit in 16 bits",
+                id, name
+            ));
+        }
+        ids.push(id as u16);
+    }
+    Ok(MapLayer {
+        name,
+        contents: LayerContents::Tiles { width, height, ids },
+    })
+}
+
+/// Decodes base64 tile data, which is a list of little endian u32's that may be compressed
+fn decode_base64(text: &str, compression: Option<&str>) -> Result<Vec<u32>, String> {
+    let compressed = base64::decode(text.trim()).map_err(|e| e.to_string())?;
+    let bytes = match compression {
+        None | Some("") => compressed,
+        Some("zlib") => {
+            let mut bytes = vec![];
+            flate2::read::ZlibDecoder::new(compressed.as_slice())
+                .read_to_end(&mut bytes)
+                .map_err(|e| e.to_string())?;
+            bytes
+        }
+        Some("gzip") => {
+            let mut bytes = vec![];
+            flate2::read::GzDecoder::new(compressed.as_slice())
+                .read_to_end(&mut bytes)
+                .map_err(|e| e.to_string())?;
+            bytes
+        }
+        Some(other) => return Err(format!("Unsupported compression {}", other)),
+    };
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+fn parse_csv(text: &str) -> Result<Vec<u32>, String> {
+    text.split(',')
+        .map(|cell| cell.trim())
+        .filter(|cell| !cell.is_empty())
+        .map(|cell| cell.parse::<u32>().map_err(|e| e.to_string()))
+        .collect()
+}
+
+fn parse_tmx(text: &str) -> Result<MapData, String> {
+    let document = roxmltree::Document::parse(text).map_err(|e| e.to_string())?;
+    let map = document.root_element();
+    if map.attribute("infinite") == Some("1") {
+        return Err("Infinite maps are not supported".to_string());
+    }
+
+    let mut layers = vec![];
+    collect_tmx_layers(map, &mut layers)?;
+    Ok(MapData {
+        tile_size: [
+            tmx_attribute(map, "tilewidth")?,
+            tmx_attribute(map, "tileheight")?,
+        ],
+        layers,
+    })
+}
+
+fn tmx_attribute<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> Result<T, String> {
+    node.attribute(name)
+        .ok_or(format!("<{}> has no {}", node.tag_name().name(), name))?
+        .parse()
+        .map_err(|_| format!("<{}> has an invalid {}", node.tag_name().name(), name))
+}
+
+/// Finds all the layers in a `<map>` or `<group>` element
+fn collect_tmx_layers(parent: roxmltree::Node, layers: &mut Vec<MapLayer>) -> Result<(), String> {
+    for child in parent.children().filter(|node| node.is_element()) {
+        let name = child.attribute("name").unwrap_or("").to_string();
+        match child.tag_name().name() {
+            "layer" => {
+                let data = child
+                    .children()
+                    .find(|node| node.has_tag_name("data"))
+                    .ok_or(format!("Layer {} has no data", name))?;
+                let text = data.text().unwrap_or("");
+                let gids = match data.attribute("encoding") {
+                    None => data
+                        .children()
+                        .filter(|node| node.has_tag_name("tile"))
+                        .map(|tile| tmx_attribute(tile, "gid").unwrap_or(0))
+                        .collect(),
+                    Some("csv") => parse_csv(text)?,
+                    Some("base64") => decode_base64(text, data.attribute("compression"))?,
+                    Some(other) => return Err(format!("Unsupported encoding {}", other)),
+                };
+                layers.push(tile_layer(
+                    name,
+                    tmx_attribute(child, "width")?,
+                    tmx_attribute(child, "height")?,
+                    gids,
+                )?);
+            }
+            "objectgroup" => {
+                let objects = child
+                    .children()
+                    .filter(|node| node.has_tag_name("object"))
+                    .map(|object| {
+                        [
+                            tmx_attribute(object, "x").unwrap_or(0.0),
+                            tmx_attribute(object, "y").unwrap_or(0.0),
+                            tmx_attribute(object, "width").unwrap_or(0.0),
+                            tmx_attribute(object, "height").unwrap_or(0.0),
+                        ]
+                    })
+                    .collect();
+                layers.push(MapLayer {
+                    name,
+                    contents: LayerContents::Objects(objects),
+                });
+            }
+            "group" => collect_tmx_layers(child, layers)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct TmjMap {
+    #[serde(default)]
+    infinite: bool,
+    tilewidth: u32,
+    tileheight: u32,
+    layers: Vec<TmjLayer>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum TmjLayer {
+    TileLayer {
+        name: String,
+        width: u32,
+        height: u32,
+        data: TmjTileData,
+        #[serde(default)]
+        compression: Option<String>,
+    },
+    ObjectGroup {
+        name: String,
+        objects: Vec<TmjObject>,
+    },
+    Group {
+        layers: Vec<TmjLayer>,
+    },
+    ImageLayer {},
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TmjTileData {
+    Ids(Vec<u32>),
+    Base64(String),
+}
+
+#[derive(Deserialize)]
+struct TmjObject {
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+}
+
+fn parse_tmj(text: &str) -> Result<MapData, String> {
+    let map: TmjMap = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if map.infinite {
+        return Err("Infinite maps are not supported".to_string());
+    }
+
+    let mut layers = vec![];
+    collect_tmj_layers(map.layers, &mut layers)?;
+    Ok(MapData {
+        tile_size: [map.tilewidth, map.tileheight],
+        layers,
+    })
+}
+
+fn collect_tmj_layers(tmj_layers: Vec<TmjLayer>, layers: &mut Vec<MapLayer>) -> Result<(), String> {
+    for layer in tmj_layers {
+        match layer {
+            TmjLayer::TileLayer {
+                name,
+                width,
+                height,
+                data,
+                compression,
+            } => {
+                let gids = match data {
+                    TmjTileData::Ids(ids) => ids,
+                    TmjTileData::Base64(text) => decode_base64(&text, compression.as_deref())?,
+                };
+                layers.push(tile_layer(name, width, height, gids)?);
+            }
+            TmjLayer::ObjectGroup { name, objects } => layers.push(MapLayer {
+                name,
+                contents: LayerContents::Objects(
+                    objects
+                        .iter()
+                        .map(|object| [object.x, object.y, object.width, object.height])
+                        .collect(),
+                ),
+            }),
+            TmjLayer::Group { layers: children } => collect_tmj_layers(children, layers)?,
+            TmjLayer::ImageLayer {} => {}
+        }
+    }
+    Ok(())
+}
+
+impl Node for TiledMap {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn update_resolution(&mut self, _gl: &glow::Context, _screen_resolution: &[i32; 2]) {}
+
+    fn bind(&mut self, _gl: &glow::Context, _quad: &Quad, _game_state: &GameState) {}
+
+    fn get_output_texture(&self, name: &String) -> Result<glow::Texture, NodeError> {
+        self.layers
+            .get(name)
+            .map(|layer| layer.texture)
+            .ok_or(NodeError::NoSuchOutputTexture(name.clone()))
+    }
+
+    fn get_output_uniforms(&self, name: &String) -> Vec<(String, UniformValue)> {
+        match self.layers.get(name) {
+            Some(layer) => vec![
+                ("size".to_string(), UniformValue::Vec2(layer.size)),
+                ("tile_size".to_string(), UniformValue::Vec2(self.tile_size)),
+            ],
+            None => vec![],
+        }
+    }
+
+    fn set_input_texture(
+        &mut self,
+        name: &String,
+        _texture: glow::Texture,
+    ) -> Result<(), NodeError> {
+        Err(NodeError::NoSuchInputTexture(name.clone()))
+    }
+}
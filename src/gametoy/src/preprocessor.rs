@@ -0,0 +1,196 @@
+/*!
+Shader Preprocessor
+-------------------
+
+Expands `#include "file.glsl"` directives in shader sources before they are
+handed to the GPU, as GLSL has no way to include files itself. Paths are
+looked up in the `GameData`, first relative to the file doing the including
+and then from the root of the datapack.
+
+Files containing `#pragma once`, or wrapped in an include guard
+(`#ifndef NAME` / `#define NAME` ... `#endif`), are only included the first
+time they are seen by a pass. This also applies to the files listed in a
+renderpass's `fragment_shader_paths`, so a shared file can be listed there
+and included by other files without being duplicated.
+
+Including a file that is already being expanded (without `#pragma once` or
+a guard) is an error, as it would never finish.
+
+The expanded source carries a `SourceMap` so that compiler errors can be
+traced back to the file and line they came from.
+
+!*/
+
+use crate::data_source;
+use crate::nodes::NodeError;
+use crate::source_map::ShaderSource;
+use std::collections::{HashMap, HashSet};
+
+/// Expands includes for a single shader. Keeps track of the files that have
+/// already been included so that `#pragma once` works across multiple calls
+/// to `expand`.
+pub struct Preprocessor<'a> {
+    shader_sources: &'a HashMap<String, String>,
+
+    /// Files that should only be included once and already have been
+    included_once: HashSet<String>,
+
+    /// The files currently being expanded, outermost first
+    stack: Vec<String>,
+}
+
+impl<'a> Preprocessor<'a> {
+    pub fn new(shader_sources: &'a HashMap<String, String>) -> Self {
+        Self {
+            shader_sources,
+            included_once: HashSet::new(),
+            stack: vec![],
+        }
+    }
+
+    /// Returns the source of the file at `path` with all it's includes
+    /// expanded. If the file should only be included once and already has
+    /// been, this returns an empty string.
+    pub fn expand(&mut self, path: &str) -> Result<String, NodeError> {
+        let mut output = ShaderSource::new();
+        self.expand_into(path, &mut output)?;
+        Ok(output.text)
+    }
+
+    /// Appends the source of the file at `path`, with all it's includes
+    /// expanded, to `output`. The path is normalised first, so `./a.glsl`
+    /// and `a.glsl` are the same file.
+    pub fn expand_into(&mut self, path: &str, output: &mut ShaderSource) -> Result<(), NodeError> {
+        let normalised = data_source::normalise_path(path)
+            .map_err(|_| NodeError::MissingResource(path.to_string()))?;
+        let path = normalised.as_str();
+        let source = self
+            .shader_sources
+            .get(path)
+            .ok_or_else(|| NodeError::MissingResource(path.to_string()))?;
+
+        if self.included_once.contains(path) {
+            return Ok(());
+        }
+        if let Some(start) = self.stack.iter().position(|p| p == path) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(path.to_string());
+            return Err(NodeError::IncludeCycle(cycle));
+        }
+        if is_include_once(source) {
+            // Marked before expanding so that a file including itself
+            // (through another file) behaves as the guard would.
+            self.included_once.insert(path.to_string());
+        }
+
+        self.stack.push(path.to_string());
+        let result = self.expand_source(path, source, output);
+        self.stack.pop();
+        result
+    }
+
+    fn expand_source(
+        &mut self,
+        path: &str,
+        source: &str,
+        output: &mut ShaderSource,
+    ) -> Result<(), NodeError> {
+        // Lines are collected into chunks between includes so the source map
+        // gets one range per chunk
+        let mut chunk = String::new();
+        let mut chunk_first_line = 1;
+        for (line_id, line) in source.lines().enumerate() {
+            match parse_directive(line, "include") {
+                Some(argument) => {
+                    let include_path = parse_include_path(argument).ok_or_else(|| {
+                        NodeError::MalformedInclude(path.to_string(), line_id + 1)
+                    })?;
+                    let resolved = self.resolve(path, include_path);
+                    output.push_file_lines(path, chunk_first_line, &chunk);
+                    chunk.clear();
+                    chunk_first_line = line_id + 2;
+                    self.expand_into(&resolved, output)?;
+                }
+                None if is_pragma_once(line) => {
+                    // Left as a blank line so line numbers within the file
+                    // are unchanged
+                    chunk.push('\n');
+                }
+                None => {
+                    chunk += line;
+                    chunk.push('\n');
+                }
+            }
+        }
+        output.push_file_lines(path, chunk_first_line, &chunk);
+        Ok(())
+    }
+
+    /// Finds the file an include refers to. Paths relative to the including
+    /// file take priority over paths from the root of the datapack.
+    fn resolve(&self, from: &str, include_path: &str) -> String {
+        let relative = match from.rfind('/') {
+            Some(end) => format!("{}/{}", &from[..end], include_path),
+            None => include_path.to_string(),
+        };
+        for candidate in [relative.as_str(), include_path] {
+            if let Ok(normalised) = data_source::normalise_path(candidate) {
+                if self.shader_sources.contains_key(&normalised) {
+                    return normalised;
+                }
+            }
+        }
+        relative
+    }
+}
+
+/// If the line is the preprocessor directive `name`, returns the rest of the
+/// line. Whitespace is allowed either side of the `#`.
+fn parse_directive<'b>(line: &'b str, name: &str) -> Option<&'b str> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    let argument = directive.strip_prefix(name)?;
+    if argument.is_empty() || argument.starts_with(char::is_whitespace) {
+        Some(argument.trim())
+    } else {
+        // Some other directive that starts the same, eg #includes
+        None
+    }
+}
+
+/// Reads the path out of `"file.glsl"` or `<file.glsl>`, ignoring any
+/// trailing comment.
+fn parse_include_path(argument: &str) -> Option<&str> {
+    let close = match argument.chars().next()? {
+        '"' => '"',
+        '<' => '>',
+        _ => return None,
+    };
+    let rest = &argument[1..];
+    let end = rest.find(close)?;
+    let trailing = rest[end + 1..].trim();
+    if !(trailing.is_empty() || trailing.starts_with("//")) || end == 0 {
+        return None;
+    }
+    Some(&rest[..end])
+}
+
+fn is_pragma_once(line: &str) -> bool {
+    parse_directive(line, "pragma") == Some("once")
+}
+
+/// Checks for `#pragma once` or an include guard: a file whose first
+/// directive is `#ifndef NAME` followed by `#define NAME`.
+fn is_include_once(source: &str) -> bool {
+    if source.lines().any(is_pragma_once) {
+        return true;
+    }
+    let mut directives = source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"));
+    let guard = match directives.next().and_then(|l| parse_directive(l, "ifndef")) {
+        Some(guard) if !guard.is_empty() => guard,
+        _ => return false,
+    };
+    directives.next().and_then(|l| parse_directive(l, "define")) == Some(guard)
+}
@@ -1,6 +1,7 @@
 /// LOOK AT:
 /// https://rust-tutorials.github.io/learn-opengl/basics/001-drawing-a-triangle.html
 use glow::{Buffer, Context, HasContext, ARRAY_BUFFER, FLOAT, STATIC_DRAW};
+use std::fmt;
 
 /// An error with this whole object.
 #[derive(Debug)]
@@ -9,6 +10,18 @@ pub enum QuadError {
     BufferCreationFailed(String),
 }
 
+impl fmt::Display for QuadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BufferCreationFailed(message) => {
+                write!(f, "failed to create the quad's vertex buffer: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QuadError {}
+
 pub struct Quad {
     position_buffer: Buffer,
 }
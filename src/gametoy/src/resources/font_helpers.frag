@@ -0,0 +1,39 @@
+// Helpers for drawing text with the outputs of a Font node. `font` is the
+// glyph atlas (the `tex` output) and `metrics` is the `metrics` output.
+// Positions are in pixels with y increasing upwards, the same as fragCoord.
+// A pen position is the top left corner of the line of text.
+
+// Returns how much of the pixel is covered by a single glyph
+float font_draw_glyph(sampler2D font, sampler2D metrics, int glyph, vec2 pen, vec2 pixel) {
+    vec4 uv_rect = texelFetch(metrics, ivec2(glyph, 0), 0);
+    vec4 quad = texelFetch(metrics, ivec2(glyph, 1), 0);
+    vec2 local = vec2(pixel.x - pen.x, pen.y - pixel.y) - quad.xy;
+    if (any(lessThan(local, vec2(0.0))) || any(greaterThanEqual(local, quad.zw))) {
+        return 0.0;
+    }
+    return texture(font, mix(uv_rect.xy, uv_rect.zw, local / quad.zw)).r;
+}
+
+// Returns the horizontal distance to move the pen after drawing a glyph
+float font_glyph_advance(sampler2D metrics, int glyph) {
+    return texelFetch(metrics, ivec2(glyph, 2), 0).x;
+}
+
+// Draws `length` glyphs whose indices are stored in the red channel of row
+// `row` of `text` (eg a state buffer written by another pass). Returns how
+// much of the pixel is covered by the text.
+float font_draw_text(sampler2D font, sampler2D metrics, sampler2D text, int row, int length, vec2 origin, vec2 pixel) {
+    float line_height = texelFetch(metrics, ivec2(0, 2), 0).y;
+    if (pixel.y > origin.y || pixel.y < origin.y - line_height) {
+        return 0.0;
+    }
+
+    float coverage = 0.0;
+    vec2 pen = origin;
+    for (int i = 0; i < length; i++) {
+        int glyph = int(texelFetch(text, ivec2(i, row), 0).r);
+        coverage = max(coverage, font_draw_glyph(font, metrics, glyph, pen, pixel));
+        pen.x += font_glyph_advance(metrics, glyph);
+    }
+    return coverage;
+}
@@ -7,8 +7,20 @@ out vec4 fragColor;
 uniform mediump vec3 iResolution;
 uniform sampler2D col;
 
+// Set when the input texture contains linear values that need encoding for display
+uniform bool encode_srgb;
+
+
+vec3 linear_to_srgb(vec3 linear) {
+        vec3 low = linear * 12.92;
+        vec3 high = 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055;
+        return mix(high, low, vec3(lessThanEqual(linear, vec3(0.0031308))));
+}
 
 
 void main() {
         fragColor = texture(col, fragCoordUV);
+        if (encode_srgb) {
+                fragColor.rgb = linear_to_srgb(max(fragColor.rgb, vec3(0.0)));
+        }
 }
@@ -1,5 +1,7 @@
 #version 300 es
 precision highp float;
+precision highp sampler2DArray; // Only sampler2D and samplerCube have a default precision
+precision highp usampler2D;
 uniform mediump vec3 iResolution; // viewport resolution (in pixels)
 uniform float iTime;      // shader playback time (in seconds)
 uniform float iTimeDelta; // render time (in seconds)
@@ -1,4 +1,7 @@
+use crate::diagnostics::{parse_info_log, Diagnostic};
+use crate::source_map::SourceMap;
 use glow::{Context, HasContext, Program, FRAGMENT_SHADER, VERTEX_SHADER};
+use std::fmt;
 
 #[derive(Debug)]
 pub enum ShaderError {
@@ -8,8 +11,71 @@ pub enum ShaderError {
         shader_type: u32,
         compiler_output: String,
         shader_text: String,
+
+        /// The problems in `compiler_output`. Until `map_source` is called
+        /// their lines refer to `shader_text`.
+        diagnostics: Vec<Diagnostic>,
     },
     ShaderLinkError(String),
+
+    /// The shader doesn't declare an attribute GameToy needs to draw with it.
+    /// String is the name of the attribute
+    MissingAttribute(String),
+
+    /// The shader doesn't declare a uniform GameToy needs to draw with it.
+    /// String is the name of the uniform
+    MissingUniform(String),
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ShaderAllocError(message) => {
+                write!(f, "failed to allocate a shader: {}", message)
+            }
+            Self::ShaderProgramAllocError(message) => {
+                write!(f, "failed to allocate a shader program: {}", message)
+            }
+            Self::ShaderCompileError {
+                shader_type,
+                diagnostics,
+                ..
+            } => {
+                let shader_type = match *shader_type {
+                    VERTEX_SHADER => "vertex",
+                    FRAGMENT_SHADER => "fragment",
+                    _ => "unknown",
+                };
+                write!(f, "the {} shader failed to compile", shader_type)?;
+                for diagnostic in diagnostics.iter() {
+                    write!(f, "\n{}", diagnostic)?;
+                }
+                Ok(())
+            }
+            Self::ShaderLinkError(message) => {
+                write!(f, "the shader failed to link: {}", message.trim())
+            }
+            Self::MissingAttribute(name) => {
+                write!(f, "the shader has no \"{}\" attribute", name)
+            }
+            Self::MissingUniform(name) => write!(f, "the shader has no \"{}\" uniform", name),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+impl ShaderError {
+    /// Points the diagnostics of a compile error at the files the shader
+    /// was assembled from.
+    pub fn map_source(mut self, source_map: &SourceMap) -> Self {
+        if let Self::ShaderCompileError { diagnostics, .. } = &mut self {
+            for diagnostic in diagnostics.iter_mut() {
+                source_map.map_diagnostic(diagnostic);
+            }
+        }
+        self
+    }
 }
 
 pub struct SimpleShader {
@@ -22,7 +88,7 @@ impl SimpleShader {
         let program = unsafe { init_shader_program(gl, vert, frag)? };
         let attrib_vertex_positions = unsafe {
             gl.get_attrib_location(program, "aVertexPosition")
-                .expect("No vertx positions?")
+                .ok_or_else(|| ShaderError::MissingAttribute("aVertexPosition".to_string()))?
         };
 
         Ok(Self {
@@ -54,6 +120,7 @@ unsafe fn load_shader(
         gl.delete_shader(shader);
         return Err(ShaderError::ShaderCompileError {
             shader_type,
+            diagnostics: parse_info_log(&compiler_output),
             compiler_output: compiler_output,
             shader_text: shader_text.to_string(),
         });
@@ -0,0 +1,455 @@
+use crate::config_file::{
+    ConfigFile, ExecutionMode, Node, OutputBufferFormat, RenderPassConfig, ResolutionScalingMode,
+    UniformValue,
+};
+use crate::gamedata::GameData;
+use crate::nodes::NodeError;
+use crate::preprocessor::Preprocessor;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The names Shadertoy gives it's buffer tabs, in the order they run
+const BUFFER_TABS: [&str; 4] = ["Buffer A", "Buffer B", "Buffer C", "Buffer D"];
+const IMAGE_TAB: &str = "Image";
+
+/// Number of input channels a Shadertoy pass has
+const NUM_CHANNELS: usize = 4;
+
+/// Uniforms that only exist inside passes, so code using them can't go in the Common tab
+const PASS_UNIFORMS: &[&str] = &["iResolution", "iTime", "iTimeDelta", "iFrame", "iDate"];
+
+#[derive(Debug)]
+pub enum ShadertoyExportError {
+    /// No renderpass is connected to an Output node, so there is nothing to
+    /// use as the Image pass
+    NoImagePass,
+
+    /// Shadertoy only has four buffers. usize is the number of buffers the
+    /// project would need
+    TooManyPasses(usize),
+
+    /// A shader the config refers to couldn't be loaded, either because it
+    /// isn't in the game data or because it's includes couldn't be expanded
+    ShaderSourceError(NodeError),
+}
+
+impl fmt::Display for ShadertoyExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoImagePass => write!(f, "no renderpass is connected to the Output node"),
+            Self::TooManyPasses(num_passes) => write!(
+                f,
+                "the project needs {} buffers, but Shadertoy only has {}",
+                num_passes,
+                BUFFER_TABS.len()
+            ),
+            Self::ShaderSourceError(_) => write!(f, "a shader couldn't be loaded"),
+        }
+    }
+}
+
+impl std::error::Error for ShadertoyExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ShaderSourceError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A pass ready to be pasted into a Shadertoy tab
+#[derive(Debug)]
+pub struct ShadertoyPassSource {
+    /// The tab to paste the code into, eg "Buffer A" or "Image"
+    pub tab: String,
+
+    /// The name of the renderpass this was generated from
+    pub node_name: String,
+
+    pub code: String,
+
+    /// What to select for iChannel0 to iChannel3
+    pub channels: Vec<Option<String>>,
+}
+
+/// A GameToy project converted into code for Shadertoy's tabs
+#[derive(Debug)]
+pub struct ShadertoySource {
+    /// Code for the Common tab: shader files that are shared between passes
+    pub common: String,
+
+    /// The buffers in the order they run, followed by the image
+    pub passes: Vec<ShadertoyPassSource>,
+
+    /// Human readable descriptions of everything that doesn't translate
+    pub report: Vec<String>,
+}
+
+impl ShadertoySource {
+    /// Describes where each pass goes, what to select for each channel and
+    /// what didn't translate.
+    pub fn report_text(&self) -> String {
+        let mut text = String::new();
+        for pass in self.passes.iter() {
+            text += &format!("{} (from \"{}\")\n", pass.tab, pass.node_name);
+            for (channel, source) in pass.channels.iter().enumerate() {
+                if let Some(source) = source {
+                    text += &format!("    iChannel{}: {}\n", channel, source);
+                }
+            }
+        }
+        if !self.report.is_empty() {
+            text += "\nThings that don't translate to Shadertoy:\n";
+            for problem in self.report.iter() {
+                text += &format!(" - {}\n", problem);
+            }
+        }
+        text
+    }
+}
+
+/// Converts the renderpasses of a project into code for Shadertoy. The
+/// renderpass connected to the Output node becomes the Image and the rest
+/// become buffers, so there can be at most four other renderpasses.
+pub fn export_shadertoy(
+    config: &ConfigFile,
+    gamedata: &GameData,
+) -> Result<ShadertoySource, ShadertoyExportError> {
+    let graph = &config.graph;
+    let image_pass = graph
+        .links
+        .iter()
+        .filter(|link| {
+            graph
+                .nodes
+                .iter()
+                .any(|n| n.get_name() == link.end_node && matches!(n, Node::Output(_)))
+        })
+        .find_map(|link| {
+            graph.nodes.iter().find_map(|n| match n {
+                Node::RenderPass(pass) if pass.name == link.start_node => Some(pass),
+                _ => None,
+            })
+        })
+        .ok_or(ShadertoyExportError::NoImagePass)?;
+
+    let buffer_passes: Vec<&RenderPassConfig> = graph
+        .nodes
+        .iter()
+        .filter_map(|n| match n {
+            Node::RenderPass(pass) if pass.name != image_pass.name => Some(pass),
+            _ => None,
+        })
+        .collect();
+    if buffer_passes.len() > BUFFER_TABS.len() {
+        return Err(ShadertoyExportError::TooManyPasses(buffer_passes.len()));
+    }
+    let tabs: Vec<(&str, &RenderPassConfig)> = BUFFER_TABS
+        .iter()
+        .copied()
+        .zip(buffer_passes)
+        .chain(std::iter::once((IMAGE_TAB, image_pass)))
+        .collect();
+
+    let mut exporter = Exporter {
+        config,
+        tabs: &tabs,
+        report: vec![],
+    };
+    let common_paths = exporter.common_paths(gamedata)?;
+    let mut common = String::new();
+    let mut preprocessor = Preprocessor::new(&gamedata.shader_sources);
+    for path in common_paths.iter() {
+        common += &preprocessor
+            .expand(path)
+            .map_err(ShadertoyExportError::ShaderSourceError)?;
+    }
+
+    let mut passes = vec![];
+    for (tab, pass) in tabs.iter() {
+        passes.push(exporter.export_pass(gamedata, tab, pass, &common_paths)?);
+    }
+
+    Ok(ShadertoySource {
+        common,
+        passes,
+        report: exporter.report,
+    })
+}
+
+/// Splits GLSL source into the identifiers it uses
+fn identifiers(source: &str) -> HashSet<&str> {
+    source
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+struct Exporter<'a> {
+    config: &'a ConfigFile,
+
+    /// The Shadertoy tab each renderpass is exported to
+    tabs: &'a [(&'a str, &'a RenderPassConfig)],
+
+    report: Vec<String>,
+}
+
+impl<'a> Exporter<'a> {
+    /// Adds a problem to the report, unless it's already there because
+    /// another pass ran into it.
+    fn report(&mut self, problem: String) {
+        if !self.report.contains(&problem) {
+            self.report.push(problem);
+        }
+    }
+
+    /// Shader files used by more than one pass go in the Common tab, unless
+    /// they use something that only exists inside a pass (such as a uniform
+    /// or input texture). Those are left in each pass.
+    fn common_paths(&self, gamedata: &GameData) -> Result<Vec<String>, ShadertoyExportError> {
+        let mut pass_names: HashSet<&str> = PASS_UNIFORMS.iter().copied().collect();
+        for (_, pass) in self.tabs.iter() {
+            pass_names.extend(pass.input_texture_slots.iter().map(|s| s.name.as_str()));
+            pass_names.extend(pass.output_texture_slots.iter().map(|s| s.name.as_str()));
+            pass_names.extend(pass.uniforms.iter().map(|u| u.name.as_str()));
+        }
+
+        let mut common_paths = vec![];
+        for (_, pass) in self.tabs.iter() {
+            for path in pass.fragment_shader_paths.iter() {
+                let num_users = self
+                    .tabs
+                    .iter()
+                    .filter(|(_, other)| other.fragment_shader_paths.contains(path))
+                    .count();
+                if num_users < 2 || common_paths.contains(path) {
+                    continue;
+                }
+                let source = Preprocessor::new(&gamedata.shader_sources)
+                    .expand(path)
+                    .map_err(ShadertoyExportError::ShaderSourceError)?;
+                if identifiers(&source).is_disjoint(&pass_names) {
+                    common_paths.push(path.clone());
+                }
+            }
+        }
+        Ok(common_paths)
+    }
+
+    fn export_pass(
+        &mut self,
+        gamedata: &GameData,
+        tab: &str,
+        pass: &RenderPassConfig,
+        common_paths: &[String],
+    ) -> Result<ShadertoyPassSource, ShadertoyExportError> {
+        self.check_pass(tab, pass);
+
+        let mut code = format!("// \"{}\", exported from GameToy\n", pass.name);
+        let mut channels = vec![None; NUM_CHANNELS];
+        for (channel, slot) in pass.input_texture_slots.iter().enumerate() {
+            if channel >= NUM_CHANNELS {
+                self.report(format!(
+                    "\"{}\" has {} inputs, but Shadertoy passes only have {}. \"{}\" was left out",
+                    pass.name,
+                    pass.input_texture_slots.len(),
+                    NUM_CHANNELS,
+                    slot.name
+                ));
+                continue;
+            }
+            channels[channel] = self.channel_source(pass, &slot.name);
+            let channel_name = format!("iChannel{}", channel);
+            if slot.name != channel_name {
+                code += &format!("#define {} {}\n", slot.name, channel_name);
+            }
+        }
+
+        // Shadertoy has no way to change these, so they become constants
+        for uniform in pass.uniforms.iter() {
+            code += &format!(
+                "const {} {} = {};\n",
+                uniform.value.to_glsl_type(),
+                uniform.name,
+                glsl_literal(&uniform.value)
+            );
+        }
+
+        if !pass.shadertoy_compat {
+            // GameToy's iFrame is unsigned
+            code += "#define iFrame uint(iFrame)\n";
+            code += "vec2 fragCoord;\n";
+            code += "vec2 fragCoordUV;\n";
+            for slot in pass.output_texture_slots.iter() {
+                code += &format!("vec{} {};\n", slot.format.to_channel_count(), slot.name);
+            }
+            code += "#define main gametoy_main\n";
+        }
+        // The common files are expanded first (and thrown away) so that
+        // anything they include with `#pragma once` isn't repeated here.
+        let mut preprocessor = Preprocessor::new(&gamedata.shader_sources);
+        for path in common_paths.iter().chain(pass.fragment_shader_paths.iter()) {
+            let source = preprocessor
+                .expand(path)
+                .map_err(ShadertoyExportError::ShaderSourceError)?;
+            if !common_paths.contains(path) {
+                code += &source;
+            }
+        }
+        if pass.shadertoy_compat && identifiers(&code).contains("iMouse") {
+            self.report(format!(
+                "\"{}\" reads iMouse, which is always zero in GameToy but follows the mouse on Shadertoy",
+                pass.name
+            ));
+        }
+        if !pass.shadertoy_compat {
+            code += &generate_main_image(pass);
+        }
+
+        Ok(ShadertoyPassSource {
+            tab: tab.to_string(),
+            node_name: pass.name.clone(),
+            code,
+            channels,
+        })
+    }
+
+    /// Reports the parts of a renderpass's configuration Shadertoy can't match
+    fn check_pass(&mut self, tab: &str, pass: &RenderPassConfig) {
+        match pass.resolution_scaling_mode {
+            ResolutionScalingMode::Fixed(x, y) => self.report(format!(
+                "\"{}\" renders at a fixed resolution of {}x{}, Shadertoy passes are the size of the screen",
+                pass.name, x, y
+            )),
+            ResolutionScalingMode::ViewportScale(x, y) if x != 1.0 || y != 1.0 => {
+                self.report(format!(
+                    "\"{}\" renders at {}x{} times the screen size, Shadertoy passes are the size of the screen",
+                    pass.name, x, y
+                ))
+            }
+            ResolutionScalingMode::ViewportScale(_, _) => {}
+        }
+
+        if pass.execution_mode != ExecutionMode::Always {
+            self.report(format!(
+                "\"{}\" doesn't run every frame, Shadertoy passes always do",
+                pass.name
+            ));
+        }
+
+        if pass.output_texture_slots.len() > 1 {
+            self.report(format!(
+                "\"{}\" has {} outputs, Shadertoy passes only have one. Only \"{}\" is kept",
+                pass.name,
+                pass.output_texture_slots.len(),
+                pass.output_texture_slots[0].name
+            ));
+        }
+
+        if let Some(output) = pass.output_texture_slots.first() {
+            if tab != IMAGE_TAB && output.format != OutputBufferFormat::RGBA32F {
+                self.report(format!(
+                    "\"{}\" outputs {:?}, Shadertoy buffers are always RGBA32F",
+                    pass.name, output.format
+                ));
+            }
+        }
+    }
+
+    /// Describes what should be selected for the channel an input slot is
+    /// given, reporting anything that has no equivalent.
+    fn channel_source(&mut self, pass: &RenderPassConfig, slot_name: &str) -> Option<String> {
+        let graph = &self.config.graph;
+        let link = graph
+            .links
+            .iter()
+            .find(|l| l.end_node == pass.name && l.end_input_slot == slot_name)?;
+        let start_node = graph
+            .nodes
+            .iter()
+            .find(|n| n.get_name() == link.start_node)?;
+
+        match start_node {
+            Node::RenderPass(start_pass) => {
+                let tab = self
+                    .tabs
+                    .iter()
+                    .find(|(_, p)| p.name == start_pass.name)
+                    .map(|(tab, _)| *tab)?;
+                if tab == IMAGE_TAB {
+                    self.report(format!(
+                        "\"{}\" reads from the Image pass, which Shadertoy doesn't allow",
+                        pass.name
+                    ));
+                    return None;
+                }
+                let first_output = start_pass.output_texture_slots.first().map(|s| &s.name);
+                if first_output != Some(&link.start_output_slot) {
+                    self.report(format!(
+                        "\"{}\" reads output \"{}\" of \"{}\", but only the first output is exported",
+                        pass.name, link.start_output_slot, start_pass.name
+                    ));
+                }
+                Some(tab.to_string())
+            }
+            Node::Keyboard(_) => Some("Keyboard".to_string()),
+            Node::Image(image) => {
+                self.report(format!(
+                    "Shadertoy only has it's own textures, so \"{}\" needs replacing with one of them",
+                    image.path
+                ));
+                Some(format!("Texture ({})", image.path))
+            }
+            Node::Cubemap(cubemap) => {
+                self.report(format!(
+                    "Shadertoy only has it's own cubemaps, so \"{}\" needs replacing with one of them",
+                    cubemap.name
+                ));
+                Some(format!("Cubemap ({})", cubemap.name))
+            }
+            other => {
+                self.report(format!(
+                    "\"{}\" reads from \"{}\", which has no Shadertoy equivalent",
+                    pass.name,
+                    other.get_name()
+                ));
+                None
+            }
+        }
+    }
+}
+
+/// Generates a `mainImage` that sets up the globals a GameToy shader expects,
+/// runs it's `main` and returns the first output.
+fn generate_main_image(pass: &RenderPassConfig) -> String {
+    let mut main = String::from("#undef main\n\n");
+    main += "void mainImage(out vec4 shadertoy_frag_color, in vec2 shadertoy_frag_coord) {\n";
+    main += "    fragCoord = shadertoy_frag_coord;\n";
+    main += "    fragCoordUV = shadertoy_frag_coord / iResolution.xy;\n";
+    main += "    gametoy_main();\n";
+    if let Some(output) = pass.output_texture_slots.first() {
+        let color = match output.format.to_channel_count() {
+            1 => format!("vec4({}, 0.0, 0.0, 1.0)", output.name),
+            2 => format!("vec4({}, 0.0, 1.0)", output.name),
+            3 => format!("vec4({}, 1.0)", output.name),
+            _ => output.name.clone(),
+        };
+        main += &format!("    shadertoy_frag_color = {};\n", color);
+    }
+    main += "}\n";
+    main
+}
+
+/// Writes a value the way it would appear in GLSL source
+fn glsl_literal(value: &UniformValue) -> String {
+    let float = |x: &f32| format!("{:?}", x);
+    let vector = |v: &[f32]| v.iter().map(float).collect::<Vec<_>>().join(", ");
+    match value {
+        UniformValue::Float(x) => float(x),
+        UniformValue::Int(x) => x.to_string(),
+        UniformValue::Vec2(v) => format!("vec2({})", vector(v)),
+        UniformValue::Vec3(v) => format!("vec3({})", vector(v)),
+        UniformValue::Vec4(v) | UniformValue::Color(v) => format!("vec4({})", vector(v)),
+        UniformValue::Bool(x) => x.to_string(),
+    }
+}
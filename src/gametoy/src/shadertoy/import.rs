@@ -0,0 +1,446 @@
+use crate::config_file::{
+    ColorSpace, ConfigFile, CubemapConfig, CubemapFaces, ExecutionMode, GraphConfig, ImageConfig,
+    InputBufferConfig, KeyboardConfig, Link, MetaData, Node, OutputBufferConfig,
+    OutputBufferFormat, OutputConfig, RenderPassConfig, ResolutionScalingMode, SamplerType,
+};
+use crate::migration;
+use crate::nodes;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The name of the output slot every imported pass writes to
+const PASS_OUTPUT_NAME: &str = "fragColor";
+
+const COMMON_SHADER_PATH: &str = "common.frag";
+const KEYBOARD_NODE_NAME: &str = "Keyboard";
+const OUTPUT_NODE_NAME: &str = "Output";
+
+#[derive(Debug)]
+pub enum ShadertoyImportError {
+    /// The file isn't JSON in the shape of a Shadertoy export
+    ParseError(serde_json::Error),
+
+    /// The export doesn't contain a shader with an Image pass
+    NoImagePass,
+}
+
+impl fmt::Display for ShadertoyImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParseError(_) => write!(f, "the file isn't a Shadertoy export"),
+            Self::NoImagePass => write!(f, "the export doesn't have an Image pass"),
+        }
+    }
+}
+
+impl std::error::Error for ShadertoyImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ParseError(err) => Some(err),
+            Self::NoImagePass => None,
+        }
+    }
+}
+
+/// The result of importing a shader from Shadertoy
+#[derive(Debug)]
+pub struct ShadertoyProject {
+    pub config_file: ConfigFile,
+
+    /// The source of every shader the config refers to, keyed by path
+    pub shader_sources: HashMap<String, String>,
+
+    /// Files the config refers to that aren't included in the export, such
+    /// as textures. Each entry is the path in the project followed by where
+    /// it came from on Shadertoy.
+    pub missing_files: Vec<(String, String)>,
+
+    /// Human readable descriptions of anything that couldn't be converted
+    pub warnings: Vec<String>,
+}
+
+/// The export contains either a single shader, a shader wrapped the way the
+/// API returns it, or a list of shaders.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ShadertoyExport {
+    Wrapped {
+        #[serde(rename = "Shader")]
+        shader: ShadertoyShader,
+    },
+    Single(ShadertoyShader),
+    List(Vec<ShadertoyShader>),
+}
+
+#[derive(Deserialize)]
+struct ShadertoyShader {
+    #[serde(default)]
+    info: ShadertoyInfo,
+    renderpass: Vec<ShadertoyPass>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ShadertoyInfo {
+    id: String,
+    name: String,
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct ShadertoyPass {
+    #[serde(default)]
+    inputs: Vec<ShadertoyInput>,
+    #[serde(default)]
+    outputs: Vec<ShadertoyOutput>,
+    code: String,
+    name: String,
+    #[serde(rename = "type")]
+    pass_type: String,
+}
+
+#[derive(Deserialize)]
+struct ShadertoyInput {
+    /// Buffers are matched to the passes that write them using this. It is a
+    /// number in older exports and a string in newer ones.
+    id: serde_json::Value,
+    #[serde(alias = "filepath", default)]
+    src: String,
+    #[serde(alias = "type")]
+    ctype: String,
+    channel: usize,
+    #[serde(default)]
+    sampler: ShadertoySampler,
+}
+
+#[derive(Deserialize)]
+struct ShadertoyOutput {
+    id: serde_json::Value,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ShadertoySampler {
+    filter: String,
+    srgb: String,
+    vflip: String,
+}
+
+/// Converts a Shadertoy JSON export into a GameToy project. Textures aren't
+/// part of the export, so they are listed in `missing_files` for the user to
+/// download.
+pub fn import_shadertoy(json: &str) -> Result<ShadertoyProject, ShadertoyImportError> {
+    let export: ShadertoyExport =
+        serde_json::from_str(json).map_err(ShadertoyImportError::ParseError)?;
+    let mut warnings = vec![];
+    let shader = match export {
+        ShadertoyExport::Wrapped { shader } => shader,
+        ShadertoyExport::Single(shader) => shader,
+        ShadertoyExport::List(mut shaders) => {
+            if shaders.len() > 1 {
+                warnings.push(format!(
+                    "The export contains {} shaders, only the first was imported",
+                    shaders.len()
+                ));
+            }
+            if shaders.is_empty() {
+                return Err(ShadertoyImportError::NoImagePass);
+            }
+            shaders.remove(0)
+        }
+    };
+
+    let mut importer = Importer {
+        shader_sources: HashMap::new(),
+        missing_files: vec![],
+        warnings,
+        input_nodes: vec![],
+        links: vec![],
+    };
+    let config_file = importer.convert(&shader)?;
+
+    Ok(ShadertoyProject {
+        config_file,
+        shader_sources: importer.shader_sources,
+        missing_files: importer.missing_files,
+        warnings: importer.warnings,
+    })
+}
+
+struct Importer {
+    shader_sources: HashMap<String, String>,
+    missing_files: Vec<(String, String)>,
+    warnings: Vec<String>,
+
+    /// Nodes that feed the passes, such as images and the keyboard
+    input_nodes: Vec<Node>,
+    links: Vec<Link>,
+}
+
+impl Importer {
+    fn convert(&mut self, shader: &ShadertoyShader) -> Result<ConfigFile, ShadertoyImportError> {
+        let mut shader_paths = vec![];
+        for pass in shader.renderpass.iter() {
+            match pass.pass_type.as_str() {
+                "common" => {
+                    shader_paths.insert(0, COMMON_SHADER_PATH.to_string());
+                    self.shader_sources
+                        .insert(COMMON_SHADER_PATH.to_string(), pass.code.clone());
+                    self.check_mouse(pass);
+                }
+                "buffer" | "image" => {}
+                other => self.warnings.push(format!(
+                    "\"{}\" is a {} pass, which GameToy doesn't support",
+                    pass.name, other
+                )),
+            }
+        }
+
+        // Shadertoy runs the buffers in order and then the image
+        let buffers = shader.renderpass.iter().filter(|p| p.pass_type == "buffer");
+        let image = shader.renderpass.iter().find(|p| p.pass_type == "image");
+        let image = image.ok_or(ShadertoyImportError::NoImagePass)?;
+
+        let mut passes = vec![];
+        for pass in buffers.chain(std::iter::once(image)) {
+            passes.push(self.convert_pass(shader, pass, &shader_paths));
+        }
+
+        self.links.push(Link {
+            start_node: image.name.clone(),
+            start_output_slot: PASS_OUTPUT_NAME.to_string(),
+            end_node: OUTPUT_NODE_NAME.to_string(),
+            end_input_slot: nodes::Output::INPUT_BUFFER_NAME.to_string(),
+        });
+
+        let mut graph_nodes: Vec<Node> = self.input_nodes.drain(..).collect();
+        graph_nodes.extend(passes.into_iter().map(Node::RenderPass));
+        graph_nodes.push(Node::Output(OutputConfig {
+            name: OUTPUT_NODE_NAME.to_string(),
+            color_space: ColorSpace::Srgb,
+        }));
+
+        let website = if shader.info.id.is_empty() {
+            String::new()
+        } else {
+            format!("https://www.shadertoy.com/view/{}", shader.info.id)
+        };
+        Ok(ConfigFile {
+            format_version: migration::CURRENT_FORMAT_VERSION,
+            metadata: MetaData {
+                game_name: shader.info.name.clone(),
+                game_version: String::new(),
+                release_date: String::new(),
+                website,
+                author_name: shader.info.username.clone(),
+                // Shaders on Shadertoy are under this license unless they say otherwise
+                license: "CC BY-NC-SA 3.0".to_string(),
+            },
+            graph: GraphConfig {
+                nodes: graph_nodes,
+                links: self.links.drain(..).collect(),
+            },
+        })
+    }
+
+    fn convert_pass(
+        &mut self,
+        shader: &ShadertoyShader,
+        pass: &ShadertoyPass,
+        shader_paths: &[String],
+    ) -> RenderPassConfig {
+        let shader_path = format!("{}.frag", pass.name.to_lowercase().replace(' ', "_"));
+        self.shader_sources
+            .insert(shader_path.clone(), pass.code.clone());
+        self.check_mouse(pass);
+        let mut fragment_shader_paths = shader_paths.to_vec();
+        fragment_shader_paths.push(shader_path);
+
+        // Shadertoy compatibility finds the channels by position, so every
+        // channel up to the last one used needs a slot.
+        let num_channels = pass.inputs.iter().map(|i| i.channel + 1).max().unwrap_or(0);
+        let mut input_texture_slots: Vec<InputBufferConfig> = (0..num_channels)
+            .map(|channel| InputBufferConfig {
+                name: format!("iChannel{}", channel),
+                sampler_type: SamplerType::Sampler2D,
+            })
+            .collect();
+
+        for input in pass.inputs.iter() {
+            let end_input_slot = format!("iChannel{}", input.channel);
+            let source = match input.ctype.as_str() {
+                "buffer" => self.find_buffer(shader, pass, input),
+                "keyboard" => Some(self.keyboard_node()),
+                "texture" => Some(self.image_node(input)),
+                "cubemap" => {
+                    input_texture_slots[input.channel].sampler_type = SamplerType::SamplerCube;
+                    Some(self.cubemap_node(input))
+                }
+                other => {
+                    self.warnings.push(format!(
+                        "{} of \"{}\" is a {} input, which GameToy doesn't support",
+                        end_input_slot, pass.name, other
+                    ));
+                    None
+                }
+            };
+            if let Some((start_node, start_output_slot)) = source {
+                self.links.push(Link {
+                    start_node,
+                    start_output_slot,
+                    end_node: pass.name.clone(),
+                    end_input_slot,
+                });
+            }
+        }
+
+        let format = if pass.pass_type == "image" {
+            OutputBufferFormat::RGBA8
+        } else {
+            OutputBufferFormat::RGBA32F
+        };
+        RenderPassConfig {
+            name: pass.name.clone(),
+            output_texture_slots: vec![OutputBufferConfig {
+                name: PASS_OUTPUT_NAME.to_string(),
+                format,
+                generate_mipmap: false,
+            }],
+            input_texture_slots,
+            resolution_scaling_mode: ResolutionScalingMode::ViewportScale(1.0, 1.0),
+            fragment_shader_paths,
+            execution_mode: ExecutionMode::Always,
+            uniforms: vec![],
+            shadertoy_compat: true,
+        }
+    }
+
+    /// Warns about passes that read `iMouse`, which is always zero in GameToy
+    fn check_mouse(&mut self, pass: &ShadertoyPass) {
+        let reads_mouse = pass
+            .code
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .any(|word| word == "iMouse");
+        if reads_mouse {
+            self.warnings.push(format!(
+                "\"{}\" reads iMouse, which is always zero as GameToy doesn't track the mouse",
+                pass.name
+            ));
+        }
+    }
+
+    /// Finds the pass that writes the buffer an input reads from
+    fn find_buffer(
+        &mut self,
+        shader: &ShadertoyShader,
+        pass: &ShadertoyPass,
+        input: &ShadertoyInput,
+    ) -> Option<(String, String)> {
+        let source = shader
+            .renderpass
+            .iter()
+            .filter(|p| p.pass_type == "buffer")
+            .find(|p| p.outputs.iter().any(|o| o.id == input.id));
+        match source {
+            Some(source) => Some((source.name.clone(), PASS_OUTPUT_NAME.to_string())),
+            None => {
+                self.warnings.push(format!(
+                    "iChannel{} of \"{}\" reads from a buffer that isn't in the export",
+                    input.channel, pass.name
+                ));
+                None
+            }
+        }
+    }
+
+    fn keyboard_node(&mut self) -> (String, String) {
+        if !self
+            .input_nodes
+            .iter()
+            .any(|n| n.get_name() == KEYBOARD_NODE_NAME)
+        {
+            self.input_nodes.push(Node::Keyboard(KeyboardConfig {
+                name: KEYBOARD_NODE_NAME.to_string(),
+            }));
+        }
+        (
+            KEYBOARD_NODE_NAME.to_string(),
+            nodes::Keyboard::OUTPUT_BUFFER_NAME.to_string(),
+        )
+    }
+
+    fn image_node(&mut self, input: &ShadertoyInput) -> (String, String) {
+        let path = self.local_path(&input.src, "");
+        if !self.input_nodes.iter().any(|n| n.get_name() == path) {
+            if input.sampler.vflip == "true" {
+                self.warnings.push(format!(
+                    "Shadertoy flips {} vertically, GameToy doesn't, so flip it when downloading it",
+                    path
+                ));
+            }
+            self.input_nodes.push(Node::Image(ImageConfig {
+                name: path.clone(),
+                path: path.clone(),
+                generate_mipmap: input.sampler.filter == "mipmap",
+                color_space: sampler_color_space(&input.sampler),
+            }));
+        }
+        (path, nodes::Image::OUTPUT_BUFFER_NAME.to_string())
+    }
+
+    /// Shadertoy stores the faces of a cubemap as `name.ext`, `name_1.ext` ...
+    /// `name_5.ext` in the order +X, -X, +Y, -Y, +Z, -Z
+    fn cubemap_node(&mut self, input: &ShadertoyInput) -> (String, String) {
+        let name = self.local_path(&input.src, "");
+        if !self.input_nodes.iter().any(|n| n.get_name() == name) {
+            let mut faces = vec![name.clone()];
+            for face in 1..6 {
+                faces.push(self.local_path(&input.src, &format!("_{}", face)));
+            }
+            self.input_nodes.push(Node::Cubemap(CubemapConfig {
+                name: name.clone(),
+                faces: CubemapFaces::Separate {
+                    positive_x: faces[0].clone(),
+                    negative_x: faces[1].clone(),
+                    positive_y: faces[2].clone(),
+                    negative_y: faces[3].clone(),
+                    positive_z: faces[4].clone(),
+                    negative_z: faces[5].clone(),
+                },
+                generate_mipmap: input.sampler.filter == "mipmap",
+                color_space: sampler_color_space(&input.sampler),
+            }));
+        }
+        (name, nodes::Cubemap::OUTPUT_BUFFER_NAME.to_string())
+    }
+
+    /// Works out where a file from Shadertoy goes in the project, and records
+    /// it as missing. GameToy only loads PNG's, so other images need
+    /// converting when they are downloaded.
+    fn local_path(&mut self, src: &str, suffix: &str) -> String {
+        let file_name = src.rsplit('/').next().unwrap_or(src);
+        let (stem, extension) = match file_name.rfind('.') {
+            Some(dot) => (&file_name[..dot], &file_name[dot..]),
+            None => (file_name, ""),
+        };
+        let directory = &src[..src.len() - file_name.len()];
+        let path = format!("{}{}.png", stem, suffix);
+        let url = format!(
+            "https://www.shadertoy.com{}{}{}{}",
+            directory, stem, suffix, extension
+        );
+        if !self.missing_files.iter().any(|(p, _)| p == &path) {
+            self.missing_files.push((path.clone(), url));
+        }
+        path
+    }
+}
+
+fn sampler_color_space(sampler: &ShadertoySampler) -> ColorSpace {
+    if sampler.srgb == "true" {
+        ColorSpace::Srgb
+    } else {
+        ColorSpace::Linear
+    }
+}
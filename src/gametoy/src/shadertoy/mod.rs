@@ -0,0 +1,23 @@
+/*!
+Shadertoy Conversion
+--------------------
+
+Converts between Shadertoy shaders and GameToy projects. Shadertoy's JSON
+export (or the JSON returned by it's API) can be turned into a `ConfigFile`
+and a set of `.frag` files with `import_shadertoy`.
+
+Passes that are imported are run with `shadertoy_compat` turned on, so their
+code can be used without modification.
+
+Going the other way, `export_shadertoy` generates code for the Buffer A-D,
+Image and Common tabs from a project with at most five renderpasses. Anything
+that has no Shadertoy equivalent (fixed resolutions, extra outputs, custom
+textures etc.) is listed in the report so it can be fixed up by hand.
+
+!*/
+
+mod export;
+mod import;
+
+pub use export::{export_shadertoy, ShadertoyExportError, ShadertoyPassSource, ShadertoySource};
+pub use import::{import_shadertoy, ShadertoyImportError, ShadertoyProject};
@@ -0,0 +1,121 @@
+/*!
+Source Maps
+-----------
+
+The shader compiled for a renderpass is assembled from a generated preamble
+(the version, uniforms and slot declarations) followed by the user's files
+with their includes expanded. A `SourceMap` records which file and line each
+line of the assembled shader came from, so that errors reported by the
+compiler can be shown against the files the user actually wrote.
+
+!*/
+
+use crate::diagnostics::Diagnostic;
+
+/// A run of consecutive lines in a generated shader that came from the same place
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceRange {
+    /// The first line of the range in the generated shader, counting from one
+    pub first_line: usize,
+    pub num_lines: usize,
+
+    /// The file the lines were copied from, or None if they were generated
+    /// by GameToy
+    pub file: Option<String>,
+
+    /// The line in `file` that `first_line` came from. For generated code
+    /// this is the same as `first_line`.
+    pub file_first_line: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    pub ranges: Vec<SourceRange>,
+}
+
+impl SourceMap {
+    /// Finds where a line of the generated shader came from. Returns the
+    /// file (None if the line was generated) and the line within it.
+    pub fn lookup(&self, line: usize) -> Option<(Option<&str>, usize)> {
+        let range = self
+            .ranges
+            .iter()
+            .find(|r| line >= r.first_line && line < r.first_line + r.num_lines)?;
+        Some((
+            range.file.as_deref(),
+            range.file_first_line + line - range.first_line,
+        ))
+    }
+
+    /// Moves a diagnostic from the generated shader to the file it came from.
+    /// Diagnostics in generated code are left pointing at the generated shader.
+    pub fn map_diagnostic(&self, diagnostic: &mut Diagnostic) {
+        if diagnostic.file.is_some() {
+            return;
+        }
+        let line = match diagnostic.line {
+            Some(line) => line,
+            None => return,
+        };
+        if let Some((Some(file), file_line)) = self.lookup(line) {
+            diagnostic.file = Some(file.to_string());
+            diagnostic.line = Some(file_line);
+        }
+    }
+}
+
+/// Shader source code along with where each line of it came from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShaderSource {
+    pub text: String,
+    pub source_map: SourceMap,
+}
+
+impl ShaderSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends code generated by GameToy
+    pub fn push_generated(&mut self, text: &str) {
+        let first_line = self.num_lines() + 1;
+        self.push(None, first_line, text);
+    }
+
+    /// Appends lines copied from a file, starting at `file_first_line`
+    pub fn push_file_lines(&mut self, file: &str, file_first_line: usize, text: &str) {
+        self.push(Some(file), file_first_line, text);
+    }
+
+    /// The number of complete lines in the text
+    pub fn num_lines(&self) -> usize {
+        self.text.matches('\n').count()
+    }
+
+    fn push(&mut self, file: Option<&str>, file_first_line: usize, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let num_lines = text.lines().count().max(1);
+        let first_line = self.num_lines() + 1;
+        self.text += text;
+        // Every push starts on a new line so that ranges never share a line
+        if !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+
+        if let Some(last) = self.source_map.ranges.last_mut() {
+            let continues_file = last.file_first_line + last.num_lines == file_first_line;
+            if last.file.as_deref() == file && continues_file {
+                last.num_lines += num_lines;
+                return;
+            }
+        }
+        self.source_map.ranges.push(SourceRange {
+            first_line,
+            num_lines,
+            file: file.map(str::to_string),
+            file_first_line,
+        });
+    }
+}
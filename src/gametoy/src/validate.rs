@@ -0,0 +1,454 @@
+/*!
+Offline Shader Validation
+-------------------------
+
+Checks the shaders of a project without a GL context, so that a datapack can
+be checked on a machine with no GPU (eg in CI) before it is shipped. Each
+renderpass's shader is assembled exactly as it would be at runtime and then
+parsed and type checked with naga.
+
+naga only understands Vulkan flavoured GLSL (version 440 and up) and has no
+combined texture/sampler types, so the GLSL ES shader is rewritten before it
+is parsed:
+
+ - the `#version` is replaced and `precision` statements are removed
+ - identifiers that are keywords in GLSL 450 (such as `buffer`) are renamed
+ - sampler types (`sampler2D` etc.) become texture types (`texture2D` etc.)
+   everywhere, and overloads of the texture functions that accept texture
+   types are declared, all using a single sampler
+ - `uniform float x;` style declarations become plain globals
+ - `in` variables are given locations
+
+This means the validation is only as strict as naga: a shader that passes
+could still be rejected by a driver that is more pedantic about GLSL ES,
+but syntax errors, undeclared variables and calls to functions with the
+wrong arguments are all caught and reported as errors. Problems found when
+naga validates the parsed shader are reported as warnings, as some are
+caused by naga handling valid GLSL differently (eg `vec4(true)`).
+Diagnostics point at the user's files, the same as errors from runtime
+compilation.
+
+Only available with the `validate` feature.
+
+!*/
+
+use crate::config_file::{ConfigFile, Node, RenderPassConfig};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::gamedata::GameData;
+use crate::nodes::generate_shader_text;
+use crate::source_map::ShaderSource;
+use naga::front::glsl;
+
+/// A GLSL ES sampler type, the texture type it becomes and the types used by
+/// the texture functions that accept it
+struct SamplerConversion {
+    sampler: &'static str,
+    texture: &'static str,
+
+    /// The type returned by sampling
+    texel: &'static str,
+    coord: &'static str,
+    derivative: &'static str,
+    size: &'static str,
+
+    /// Integer coordinates for `texelFetch`, if it's allowed
+    fetch_coord: Option<&'static str>,
+
+    /// The type of the offset for `textureOffset` etc., if it's allowed
+    offset: Option<&'static str>,
+
+    /// Integer textures can only be fetched from, not filtered
+    filterable: bool,
+}
+
+const SAMPLER_CONVERSIONS: &[SamplerConversion] = &[
+    SamplerConversion {
+        sampler: "sampler2D",
+        texture: "texture2D",
+        texel: "vec4",
+        coord: "vec2",
+        derivative: "vec2",
+        size: "ivec2",
+        fetch_coord: Some("ivec2"),
+        offset: Some("ivec2"),
+        filterable: true,
+    },
+    SamplerConversion {
+        sampler: "samplerCube",
+        texture: "textureCube",
+        texel: "vec4",
+        coord: "vec3",
+        derivative: "vec3",
+        size: "ivec2",
+        fetch_coord: None,
+        offset: None,
+        filterable: true,
+    },
+    SamplerConversion {
+        sampler: "sampler2DArray",
+        texture: "texture2DArray",
+        texel: "vec4",
+        coord: "vec3",
+        derivative: "vec2",
+        size: "ivec3",
+        fetch_coord: Some("ivec3"),
+        offset: Some("ivec2"),
+        filterable: true,
+    },
+    SamplerConversion {
+        sampler: "sampler3D",
+        texture: "texture3D",
+        texel: "vec4",
+        coord: "vec3",
+        derivative: "vec3",
+        size: "ivec3",
+        fetch_coord: Some("ivec3"),
+        offset: Some("ivec3"),
+        filterable: true,
+    },
+    SamplerConversion {
+        sampler: "usampler2D",
+        texture: "utexture2D",
+        texel: "uvec4",
+        coord: "vec2",
+        derivative: "vec2",
+        size: "ivec2",
+        fetch_coord: Some("ivec2"),
+        offset: Some("ivec2"),
+        filterable: false,
+    },
+    SamplerConversion {
+        sampler: "isampler2D",
+        texture: "itexture2D",
+        texel: "ivec4",
+        coord: "vec2",
+        derivative: "vec2",
+        size: "ivec2",
+        fetch_coord: Some("ivec2"),
+        offset: Some("ivec2"),
+        filterable: false,
+    },
+];
+
+/// Words that are keywords in GLSL 450 but can be used as names in GLSL ES 300
+const RESERVED_WORDS: &[&str] = &[
+    "buffer",
+    "shared",
+    "coherent",
+    "volatile",
+    "restrict",
+    "readonly",
+    "writeonly",
+    "sample",
+    "patch",
+    "subroutine",
+];
+
+const PRECISION_QUALIFIERS: &[&str] = &["lowp", "mediump", "highp"];
+
+/// The sampler used by all the texture function overloads
+const SAMPLER_NAME: &str = "gametoy_sampler";
+
+/// Validates the shader of every renderpass in the project
+pub fn validate_project(config: &ConfigFile, gamedata: &GameData) -> Vec<Diagnostic> {
+    config
+        .graph
+        .nodes
+        .iter()
+        .filter_map(|node| match node {
+            Node::RenderPass(pass) => Some(validate_renderpass(pass, gamedata)),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+/// Assembles the shader for a renderpass and validates it. Problems
+/// assembling the shader (such as a missing file) are also reported as
+/// diagnostics.
+pub fn validate_renderpass(config: &RenderPassConfig, gamedata: &GameData) -> Vec<Diagnostic> {
+    let diagnostics = match generate_shader_text(config, gamedata) {
+        Ok(shader_source) => validate_shader_source(&shader_source),
+        Err(err) => vec![Diagnostic::new(Severity::Error, err.to_string())],
+    };
+    diagnostics
+        .into_iter()
+        .map(|diagnostic| diagnostic.in_node(&config.name))
+        .collect()
+}
+
+/// Validates an assembled shader, pointing any problems at the files it was
+/// assembled from.
+pub fn validate_shader_source(shader_source: &ShaderSource) -> Vec<Diagnostic> {
+    let mut diagnostics = validate_shader(&shader_source.text);
+    for diagnostic in diagnostics.iter_mut() {
+        shader_source.source_map.map_diagnostic(diagnostic);
+    }
+    diagnostics
+}
+
+/// Validates a GLSL ES fragment shader. Lines in the diagnostics refer to
+/// `shader_text`.
+pub fn validate_shader(shader_text: &str) -> Vec<Diagnostic> {
+    let converted = convert_to_vulkan_glsl(shader_text);
+    let mut frontend = glsl::Frontend::default();
+    let options = glsl::Options::from(naga::ShaderStage::Fragment);
+
+    let module = match frontend.parse(&options, &converted.text) {
+        Ok(module) => module,
+        Err(errors) => {
+            return errors
+                .errors
+                .iter()
+                .map(|error| {
+                    converted
+                        .diagnostic(error.kind.to_string(), error.meta.location(&converted.text))
+                })
+                .collect()
+        }
+    };
+
+    let mut validator = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    );
+    match validator.validate(&module) {
+        Ok(_) => vec![],
+        Err(error) => {
+            let mut message = error.as_inner().to_string();
+            let mut source = std::error::Error::source(error.as_inner());
+            while let Some(cause) = source {
+                message += &format!(": {}", cause);
+                source = cause.source();
+            }
+            // The spans go from the function down to the expression, so
+            // the last is the most precise
+            let location = error
+                .spans()
+                .last()
+                .map(|(span, _)| span.location(&converted.text));
+            let mut diagnostic = match location {
+                Some(location) => converted.diagnostic(message, location),
+                None => Diagnostic::new(Severity::Error, message),
+            };
+            diagnostic.severity = Severity::Warning;
+            vec![diagnostic]
+        }
+    }
+}
+
+/// A shader rewritten for naga, along with the line of the original shader
+/// each of it's lines came from.
+struct ConvertedShader {
+    text: String,
+    original_lines: Vec<usize>,
+}
+
+impl ConvertedShader {
+    fn diagnostic(&self, message: String, location: naga::SourceLocation) -> Diagnostic {
+        let mut diagnostic = Diagnostic::new(Severity::Error, message);
+        if location.line_number > 0 {
+            let line_id = location.line_number as usize - 1;
+            diagnostic.line = self.original_lines.get(line_id).copied();
+            diagnostic.column = Some(location.line_position as usize);
+        }
+        diagnostic
+    }
+
+    fn push_line(&mut self, original_line: usize, text: &str) {
+        self.text += text;
+        self.text.push('\n');
+        self.original_lines.push(original_line);
+    }
+}
+
+fn convert_to_vulkan_glsl(shader_text: &str) -> ConvertedShader {
+    let mut converted = ConvertedShader {
+        text: String::with_capacity(shader_text.len()),
+        original_lines: vec![],
+    };
+    // Binding 0 is the sampler
+    let mut next_binding = 1;
+    let mut next_location = 0;
+
+    for (line_id, line) in shader_text.lines().enumerate() {
+        let original_line = line_id + 1;
+        let line = replace_sampler_types(line);
+        let code = line.split("//").next().unwrap_or_default().trim();
+
+        if code.starts_with("#version") {
+            converted.push_line(original_line, "#version 450");
+            for line in generate_prelude().lines() {
+                converted.push_line(original_line, line);
+            }
+        } else if code.starts_with("precision ") {
+            converted.push_line(original_line, "");
+        } else if let Some(declaration) = code.strip_prefix("uniform ") {
+            let words: Vec<&str> = declaration
+                .split_whitespace()
+                .filter(|word| !PRECISION_QUALIFIERS.contains(word))
+                .collect();
+            let is_texture = words.first().is_some_and(|ty| {
+                SAMPLER_CONVERSIONS
+                    .iter()
+                    .any(|conversion| conversion.texture == *ty)
+            });
+            if is_texture {
+                converted.push_line(
+                    original_line,
+                    &format!(
+                        "layout(set=0, binding={}) uniform {}",
+                        next_binding,
+                        words.join(" ")
+                    ),
+                );
+                next_binding += 1;
+            } else {
+                // Only the types are being checked, so other uniforms can
+                // be ordinary globals
+                converted.push_line(original_line, &words.join(" "));
+            }
+        } else if code.starts_with("in ") {
+            converted.push_line(
+                original_line,
+                &format!("layout(location={}) {}", next_location, code),
+            );
+            next_location += 1;
+        } else {
+            converted.push_line(original_line, &line);
+        }
+    }
+    converted
+}
+
+/// Replaces sampler types with their texture types, leaving other words
+/// that contain them (eg `mySampler2D`) alone
+fn replace_sampler_types(line: &str) -> String {
+    let mut replaced = String::with_capacity(line.len());
+    let mut word_start = None;
+    for (index, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        let is_word_char = c.is_ascii_alphanumeric() || c == '_';
+        match (word_start, is_word_char) {
+            (None, true) => word_start = Some(index),
+            (Some(start), false) => {
+                let word = &line[start..index];
+                let conversion = SAMPLER_CONVERSIONS.iter().find(|conv| conv.sampler == word);
+                replaced += conversion.map_or(word, |conv| conv.texture);
+                word_start = None;
+            }
+            _ => {}
+        }
+        if !is_word_char && index < line.len() {
+            replaced.push(c);
+        }
+    }
+    replaced
+}
+
+/// Declarations placed straight after the `#version`: renames for reserved
+/// words, the sampler and the texture function overloads.
+fn generate_prelude() -> String {
+    let mut prelude = String::new();
+    for word in RESERVED_WORDS {
+        prelude += &format!("#define {} gametoy_{}\n", word, word);
+    }
+    prelude += &format!(
+        "layout(set=0, binding=0) uniform sampler {};\n",
+        SAMPLER_NAME
+    );
+
+    for conv in SAMPLER_CONVERSIONS {
+        let sampler = format!("{}(t, {})", conv.sampler, SAMPLER_NAME);
+        let mut overload =
+            |return_type: &str, name: &str, parameters: &[(&str, &str)], call: &str| {
+                let declarations: Vec<String> = parameters
+                    .iter()
+                    .map(|(ty, name)| format!("{} {}", ty, name))
+                    .collect();
+                prelude += &format!(
+                    "{} {}({} t, {}) {{ return {}; }}\n",
+                    return_type,
+                    name,
+                    conv.texture,
+                    declarations.join(", "),
+                    call
+                );
+            };
+
+        // Only the types matter here, so where naga is stricter than GLSL ES
+        // the overloads call something with the same result instead: integer
+        // textures can't be filtered and offsets must be constants.
+        let sample = |function: &str, arguments: &str| match conv.fetch_coord {
+            Some(fetch_coord) if !conv.filterable => {
+                format!("texelFetch({}, {}(p), 0)", sampler, fetch_coord)
+            }
+            _ => format!("{}({}, {})", function, sampler, arguments),
+        };
+
+        let (texel, coord) = (conv.texel, conv.coord);
+        overload(texel, "texture", &[(coord, "p")], &sample("texture", "p"));
+        overload(
+            texel,
+            "texture",
+            &[(coord, "p"), ("float", "bias")],
+            &sample("texture", "p, bias"),
+        );
+        overload(
+            texel,
+            "textureLod",
+            &[(coord, "p"), ("float", "lod")],
+            &sample("textureLod", "p, lod"),
+        );
+        overload(
+            texel,
+            "textureGrad",
+            &[
+                (coord, "p"),
+                (conv.derivative, "dx"),
+                (conv.derivative, "dy"),
+            ],
+            &sample("textureGrad", "p, dx, dy"),
+        );
+        overload(
+            conv.size,
+            "textureSize",
+            &[("int", "lod")],
+            &format!("textureSize({}, lod)", sampler),
+        );
+        if let Some(fetch_coord) = conv.fetch_coord {
+            let fetch = format!("texelFetch({}, p, lod)", sampler);
+            overload(
+                texel,
+                "texelFetch",
+                &[(fetch_coord, "p"), ("int", "lod")],
+                &fetch,
+            );
+            if let Some(offset) = conv.offset {
+                overload(
+                    texel,
+                    "texelFetchOffset",
+                    &[(fetch_coord, "p"), ("int", "lod"), (offset, "offset")],
+                    &fetch,
+                );
+            }
+        }
+        if let Some(offset) = conv.offset {
+            overload(
+                texel,
+                "textureOffset",
+                &[(coord, "p"), (offset, "offset")],
+                &sample("texture", "p"),
+            );
+            overload(
+                texel,
+                "textureLodOffset",
+                &[(coord, "p"), ("float", "lod"), (offset, "offset")],
+                &sample("textureLod", "p, lod"),
+            );
+        }
+    }
+    prelude
+}
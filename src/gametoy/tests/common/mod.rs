@@ -0,0 +1,66 @@
+//! Fixtures shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use gametoy::gamedata::GameData;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The folder of one of the demos
+pub fn demo_folder(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../demos")
+        .join(name)
+}
+
+/// The data.json of every demo
+pub fn demo_config_files() -> Vec<PathBuf> {
+    let demos_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../demos");
+    let mut paths: Vec<PathBuf> = fs::read_dir(&demos_folder)
+        .expect("Unable to read the demos folder")
+        .map(|entry| entry.unwrap().path().join("data.json"))
+        .filter(|path| path.exists())
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "No demos in {:?}", demos_folder);
+    paths
+}
+
+/// A datapack containing the given files, as `(path, contents)`
+pub fn make_tar<'a, I>(files: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = (&'a str, &'a [u8])>,
+{
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, contents).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+/// Loads a project with the given nodes and links in it's config file, along
+/// with the other files, as `(path, contents)`
+pub fn load_project(nodes: Value, links: Value, files: &[(&str, &[u8])]) -> GameData {
+    let config_file = json!({
+        "format_version": gametoy::migration::CURRENT_FORMAT_VERSION,
+        "metadata": {
+            "game_name": "Test",
+            "game_version": "0.0.0",
+            "release_date": "2021-01-01",
+            "website": "",
+            "author_name": "",
+            "license": "",
+        },
+        "graph": {"nodes": nodes, "links": links},
+    })
+    .to_string();
+    let mut all_files = vec![("data.json", config_file.as_bytes())];
+    all_files.extend_from_slice(files);
+    let tar = make_tar(all_files);
+    GameData::from_source(tar::Archive::new(&tar[..]))
+        .unwrap_or_else(|e| panic!("{}", gametoy::describe_error(&e)))
+}
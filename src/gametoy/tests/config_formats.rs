@@ -0,0 +1,115 @@
+//! Checks that config files survive being saved and loaded in each format
+mod common;
+
+use common::{demo_config_files, make_tar};
+use gametoy::config_file::{ConfigFile, ConfigFileError, ConfigFileFormat};
+use gametoy::gamedata::{GameData, GameDataError};
+use gametoy::migration::{MigrationError, CURRENT_FORMAT_VERSION, FIRST_RON_VERSION};
+use std::fs;
+use std::path::Path;
+
+fn load_demo(path: &Path) -> ConfigFile {
+    ConfigFile::from_reader(fs::File::open(path).unwrap(), ConfigFileFormat::Json)
+        .unwrap_or_else(|e| panic!("{:?}: {}", path, gametoy::describe_error(&e)))
+}
+
+#[test]
+fn demos_round_trip_in_every_format() {
+    for path in demo_config_files() {
+        let config_file = load_demo(&path);
+        for format in ConfigFileFormat::ALL.iter().copied() {
+            let text = config_file.to_string_pretty(format).unwrap_or_else(|e| {
+                panic!(
+                    "{:?} as {:?}: {}",
+                    path,
+                    format,
+                    gametoy::describe_error(&e)
+                )
+            });
+            let reloaded = ConfigFile::from_reader(text.as_bytes(), format).unwrap_or_else(|e| {
+                panic!(
+                    "{:?} as {:?}: {}",
+                    path,
+                    format,
+                    gametoy::describe_error(&e)
+                )
+            });
+            assert_eq!(reloaded, config_file, "{:?} as {:?}", path, format);
+
+            // Saving again gives the same text, so saves only differ where
+            // the project changed
+            assert_eq!(reloaded.to_string_pretty(format).unwrap(), text);
+        }
+    }
+}
+
+#[test]
+fn datapacks_accept_any_format() {
+    let config_file = load_demo(&demo_config_files()[0]);
+    for format in ConfigFileFormat::ALL.iter().copied() {
+        let text = config_file.to_string_pretty(format).unwrap();
+        let tar = make_tar(vec![(format.file_name(), text.as_bytes())]);
+        let gamedata = GameData::from_source(tar::Archive::new(&tar[..]))
+            .unwrap_or_else(|e| panic!("{:?}: {}", format, gametoy::describe_error(&e)));
+        assert_eq!(gamedata.config_file, config_file);
+    }
+}
+
+#[test]
+fn datapacks_with_two_config_files_are_rejected() {
+    let config_file = load_demo(&demo_config_files()[0]);
+    let json = config_file
+        .to_string_pretty(ConfigFileFormat::Json)
+        .unwrap();
+    let toml = config_file
+        .to_string_pretty(ConfigFileFormat::Toml)
+        .unwrap();
+    let tar = make_tar(vec![
+        ("data.json", json.as_bytes()),
+        ("data.toml", toml.as_bytes()),
+    ]);
+    match GameData::from_source(tar::Archive::new(&tar[..])) {
+        Err(GameDataError::MultipleConfigFiles(first, second)) => {
+            assert_eq!(
+                (first.as_str(), second.as_str()),
+                ("data.json", "data.toml")
+            )
+        }
+        Err(err) => panic!("Expected MultipleConfigFiles, got {}", err),
+        Ok(_) => panic!("Expected MultipleConfigFiles"),
+    }
+}
+
+/// A demo saved as RON, claiming to be in `version`
+fn ron_in_version(version: u32) -> String {
+    let mut config_file = load_demo(&demo_config_files()[0]);
+    config_file.format_version = version;
+    config_file.to_string_pretty(ConfigFileFormat::Ron).unwrap()
+}
+
+#[test]
+fn ron_files_are_read_in_every_version_since_the_first() {
+    let config_file = load_demo(&demo_config_files()[0]);
+    for version in FIRST_RON_VERSION..=CURRENT_FORMAT_VERSION {
+        let loaded =
+            ConfigFile::from_reader(ron_in_version(version).as_bytes(), ConfigFileFormat::Ron)
+                .unwrap_or_else(|e| panic!("version {}: {}", version, gametoy::describe_error(&e)));
+        assert_eq!(loaded, config_file, "version {}", version);
+    }
+}
+
+#[test]
+fn ron_files_outside_the_known_versions_are_rejected() {
+    match ConfigFile::from_reader(ron_in_version(0).as_bytes(), ConfigFileFormat::Ron) {
+        Err(ConfigFileError::RonVersionMismatch(0)) => {}
+        other => panic!("Expected RonVersionMismatch, got {:?}", other),
+    }
+
+    let newer = CURRENT_FORMAT_VERSION + 1;
+    match ConfigFile::from_reader(ron_in_version(newer).as_bytes(), ConfigFileFormat::Ron) {
+        Err(ConfigFileError::MigrationError(MigrationError::NewerVersion(version))) => {
+            assert_eq!(version, newer as u64)
+        }
+        other => panic!("Expected NewerVersion, got {:?}", other),
+    }
+}
@@ -0,0 +1,393 @@
+//! Checks that `ConfigFile::validate` reports problems with projects
+mod common;
+
+use common::load_project;
+use gametoy::data_source::Directory;
+use gametoy::diagnostics::{Diagnostic, Severity};
+use gametoy::gamedata::GameData;
+use serde_json::{json, Value};
+
+fn validate(gamedata: &GameData) -> Vec<Diagnostic> {
+    gamedata.config_file.validate(gamedata)
+}
+
+fn renderpass(name: &str, inputs: &[&str]) -> Value {
+    let inputs: Vec<Value> = inputs.iter().map(|name| json!({ "name": name })).collect();
+    json!({"RenderPass": {
+        "name": name,
+        "output_texture_slots": [{"name": "col", "format": "RGBA8", "generate_mipmap": false}],
+        "input_texture_slots": inputs,
+        "resolution_scaling_mode": {"ViewportScale": [1.0, 1.0]},
+        "fragment_shader_paths": ["pass.frag"],
+        "execution_mode": "Always",
+    }})
+}
+
+fn output(name: &str) -> Value {
+    json!({"Output": {"name": name}})
+}
+
+fn atlas(name: &str, sheet_path: &str) -> Value {
+    json!({"Atlas": {"name": name, "path": "sprites.png", "sheet_path": sheet_path}})
+}
+
+fn link(start_node: &str, start_output_slot: &str, end_node: &str, end_input_slot: &str) -> Value {
+    json!({
+        "start_node": start_node,
+        "start_output_slot": start_output_slot,
+        "end_node": end_node,
+        "end_input_slot": end_input_slot,
+    })
+}
+
+fn sprite_sheet(sprites: &[&str]) -> Vec<u8> {
+    let frames: Vec<Value> = sprites
+        .iter()
+        .map(|name| json!({"filename": name, "frame": {"x": 0, "y": 0, "w": 1, "h": 1}}))
+        .collect();
+    json!({ "frames": frames }).to_string().into_bytes()
+}
+
+/// Validates a project whose renderpasses all use an empty `pass.frag`
+fn validate_graph(nodes: Value, links: Value) -> Vec<Diagnostic> {
+    validate(&load_project(
+        nodes,
+        links,
+        &[("pass.frag", b"void main() {}")],
+    ))
+}
+
+/// The diagnostics of a severity reported for a node
+fn reported<'a>(
+    diagnostics: &'a [Diagnostic],
+    severity: Severity,
+    node: &str,
+) -> Vec<&'a Diagnostic> {
+    diagnostics
+        .iter()
+        .filter(|d| d.severity == severity && d.node.as_deref() == Some(node))
+        .collect()
+}
+
+/// The errors reported for a node
+fn errors_in<'a>(diagnostics: &'a [Diagnostic], node: &str) -> Vec<&'a Diagnostic> {
+    reported(diagnostics, Severity::Error, node)
+}
+
+/// Checks there is exactly one diagnostic of a severity for a node, and
+/// that its message contains `text`
+fn assert_reported(diagnostics: &[Diagnostic], severity: Severity, node: &str, text: &str) {
+    let found = reported(diagnostics, severity, node);
+    assert_eq!(found.len(), 1, "{:?}", diagnostics);
+    assert!(found[0].message.contains(text), "{}", found[0]);
+}
+
+#[test]
+fn valid_projects_have_no_diagnostics() {
+    let diagnostics = validate_graph(
+        json!([renderpass("Pass", &[]), output("Output")]),
+        json!([link("Pass", "col", "Output", "col")]),
+    );
+    assert_eq!(diagnostics, vec![]);
+}
+
+#[test]
+fn demos_have_no_errors() {
+    for path in common::demo_config_files() {
+        let folder = path.parent().unwrap();
+        let gamedata = GameData::from_source(Directory::new(folder)).unwrap();
+        let errors: Vec<Diagnostic> = validate(&gamedata)
+            .into_iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect();
+        assert_eq!(errors, vec![], "{:?}", folder);
+    }
+}
+
+#[test]
+fn duplicate_node_names_are_errors() {
+    let diagnostics = validate_graph(
+        json!([
+            renderpass("Pass", &[]),
+            renderpass("Pass", &[]),
+            output("Output")
+        ]),
+        json!([link("Pass", "col", "Output", "col")]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Error,
+        "Pass",
+        "more than one node has this name",
+    );
+}
+
+#[test]
+fn duplicate_slots_are_errors() {
+    let diagnostics = validate_graph(
+        json!([renderpass("Pass", &["a", "a"]), output("Output")]),
+        json!([
+            link("Pass", "col", "Output", "col"),
+            link("Pass", "col", "Pass", "a"),
+        ]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Error,
+        "Pass",
+        "more than one input slot is called \"a\"",
+    );
+}
+
+#[test]
+fn links_to_unknown_nodes_are_errors() {
+    let diagnostics = validate_graph(
+        json!([renderpass("Pass", &["a"]), output("Output")]),
+        json!([
+            link("Pass", "col", "Output", "col"),
+            link("Missing", "col", "Pass", "a"),
+        ]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Error,
+        "Pass",
+        "linked from \"Missing\", which doesn't exist",
+    );
+}
+
+#[test]
+fn links_to_unknown_slots_are_errors() {
+    let diagnostics = validate_graph(
+        json!([renderpass("Pass", &[]), output("Output")]),
+        json!([link("Pass", "nope", "Output", "col")]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Error,
+        "Pass",
+        "there is no output slot \"nope\"",
+    );
+
+    let diagnostics = validate_graph(
+        json!([renderpass("Pass", &[]), output("Output")]),
+        json!([link("Pass", "col", "Output", "nope")]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Error,
+        "Output",
+        "there is no input slot \"nope\"",
+    );
+}
+
+#[test]
+fn mismatched_texture_types_are_errors() {
+    let mut integers = renderpass("Integers", &[]);
+    integers["RenderPass"]["output_texture_slots"][0]["format"] = json!("RGBA32UI");
+    let diagnostics = validate_graph(
+        json!([integers, renderpass("Pass", &["a"]), output("Output")]),
+        json!([
+            link("Integers", "col", "Pass", "a"),
+            link("Pass", "col", "Output", "col"),
+        ]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Error,
+        "Pass",
+        "input slot \"a\" is linked to \"Integers\"",
+    );
+}
+
+#[test]
+fn second_outputs_are_errors() {
+    let diagnostics = validate_graph(
+        json!([renderpass("Pass", &[]), output("Output"), output("Output2")]),
+        json!([
+            link("Pass", "col", "Output", "col"),
+            link("Pass", "col", "Output2", "col"),
+        ]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Error,
+        "Output2",
+        "\"Output\" is already an Output node",
+    );
+    assert_eq!(errors_in(&diagnostics, "Output"), Vec::<&Diagnostic>::new());
+}
+
+#[test]
+fn missing_includes_are_errors() {
+    let gamedata = load_project(
+        json!([renderpass("Pass", &[]), output("Output")]),
+        json!([link("Pass", "col", "Output", "col")]),
+        &[("pass.frag", b"#include \"missing.glsl\"\nvoid main() {}")],
+    );
+    let diagnostics = validate(&gamedata);
+    assert_reported(
+        &diagnostics,
+        Severity::Error,
+        "Pass",
+        "included file \"missing.glsl\" not found",
+    );
+    assert_eq!(
+        errors_in(&diagnostics, "Pass")[0].file.as_deref(),
+        Some("pass.frag")
+    );
+}
+
+#[test]
+fn reading_later_nodes_is_reported() {
+    // A renderpass reading one that runs after it gets the previous frame
+    let nodes = json!([
+        renderpass("First", &["a"]),
+        renderpass("Second", &[]),
+        output("Output"),
+    ]);
+    let links = json!([
+        link("Second", "col", "First", "a"),
+        link("First", "col", "Output", "col"),
+    ]);
+    let diagnostics = validate_graph(nodes.clone(), links.clone());
+    assert_reported(
+        &diagnostics,
+        Severity::Info,
+        "First",
+        "reads the previous frame",
+    );
+
+    // Unless it only runs when created, when there is nothing to read
+    let mut first_once = nodes.clone();
+    first_once[0]["RenderPass"]["execution_mode"] = json!("CreationOrResized");
+    let diagnostics = validate_graph(first_once, links);
+    assert_reported(
+        &diagnostics,
+        Severity::Warning,
+        "First",
+        "only runs when created or resized",
+    );
+
+    // Other nodes are always a frame behind
+    let diagnostics = validate_graph(
+        json!([output("Output"), renderpass("Pass", &[])]),
+        json!([link("Pass", "col", "Output", "col")]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Warning,
+        "Output",
+        "always a frame behind",
+    );
+}
+
+#[test]
+fn unreachable_nodes_are_warnings() {
+    let diagnostics = validate_graph(
+        json!([
+            renderpass("Pass", &[]),
+            renderpass("Unused", &[]),
+            output("Output")
+        ]),
+        json!([link("Pass", "col", "Output", "col")]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Warning,
+        "Unused",
+        "doesn't contribute to any Output",
+    );
+    assert_eq!(
+        reported(&diagnostics, Severity::Warning, "Pass"),
+        Vec::<&Diagnostic>::new()
+    );
+}
+
+#[test]
+fn sprites_with_the_same_define_are_errors() {
+    let sheet = sprite_sheet(&["ship-1", "ship 1.png"]);
+    let gamedata = load_project(
+        json!([
+            atlas("Sprites", "sheet.json"),
+            renderpass("Pass", &["sprites"])
+        ]),
+        json!([link("Sprites", "tex", "Pass", "sprites")]),
+        &[
+            ("pass.frag", b"void main() {}"),
+            ("sprites.png", b""),
+            ("sheet.json", &sheet),
+        ],
+    );
+    let diagnostics = validate(&gamedata);
+    let errors = errors_in(&diagnostics, "Pass");
+    assert_eq!(errors.len(), 1, "{:?}", diagnostics);
+    assert!(errors[0].message.contains("SPRITE_SHIP_1"), "{}", errors[0]);
+    assert!(
+        errors[0].message.contains("sheet.json: ship-1"),
+        "{}",
+        errors[0]
+    );
+    assert!(
+        errors[0].message.contains("sheet.json: ship 1.png"),
+        "{}",
+        errors[0]
+    );
+}
+
+#[test]
+fn atlases_sharing_sprite_names_are_errors() {
+    let ships = sprite_sheet(&["ship"]);
+    let more_ships = sprite_sheet(&["rock", "ship"]);
+    let gamedata = load_project(
+        json!([
+            atlas("Ships", "ships.json"),
+            atlas("MoreShips", "more_ships.json"),
+            renderpass("Pass", &["ships", "more_ships"]),
+        ]),
+        json!([
+            link("Ships", "tex", "Pass", "ships"),
+            link("MoreShips", "tex", "Pass", "more_ships"),
+        ]),
+        &[
+            ("pass.frag", b"void main() {}"),
+            ("sprites.png", b""),
+            ("ships.json", &ships),
+            ("more_ships.json", &more_ships),
+        ],
+    );
+    let diagnostics = validate(&gamedata);
+    let errors = errors_in(&diagnostics, "Pass");
+    assert_eq!(errors.len(), 1, "{:?}", diagnostics);
+    assert!(
+        errors[0].message.contains("ships.json: ship"),
+        "{}",
+        errors[0]
+    );
+    assert!(
+        errors[0].message.contains("more_ships.json: ship"),
+        "{}",
+        errors[0]
+    );
+}
+
+#[test]
+fn unread_renderpass_outputs_are_warnings() {
+    let mut pass = renderpass("Pass", &[]);
+    let mut unread = pass["RenderPass"]["output_texture_slots"][0].clone();
+    unread["name"] = json!("unread");
+    pass["RenderPass"]["output_texture_slots"]
+        .as_array_mut()
+        .unwrap()
+        .push(unread);
+    let diagnostics = validate_graph(
+        json!([pass, output("Output")]),
+        json!([link("Pass", "col", "Output", "col")]),
+    );
+    assert_reported(
+        &diagnostics,
+        Severity::Warning,
+        "Pass",
+        "output slot \"unread\" isn't read by anything",
+    );
+}
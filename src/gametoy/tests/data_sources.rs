@@ -0,0 +1,245 @@
+//! Checks that a project loads the same from a folder, a tar and a zip, and
+//! how the paths of the files in it are handled
+mod common;
+
+use common::demo_folder;
+use gametoy::data_source::{self, Compression, Directory, GameDataSource};
+use gametoy::gamedata::{GameData, GameDataError};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Write};
+
+/// Every file in a source, by path
+fn read_all(source: &mut dyn GameDataSource) -> BTreeMap<String, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    source
+        .visit_files(&mut |path, file| {
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents).unwrap();
+            files.insert(path.to_string(), contents);
+            Ok(())
+        })
+        .unwrap();
+    files
+}
+
+fn make_tar(files: &BTreeMap<String, Vec<u8>>) -> Vec<u8> {
+    common::make_tar(
+        files
+            .iter()
+            .map(|(name, contents)| (name.as_str(), contents.as_slice())),
+    )
+}
+
+fn make_zip(files: &BTreeMap<String, Vec<u8>>) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, contents) in files {
+        writer
+            .start_file(name.as_str(), zip::write::FileOptions::default())
+            .unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+fn assert_same(a: &GameData, b: &GameData) {
+    assert_eq!(a.config_file, b.config_file);
+    assert_eq!(a.textures, b.textures);
+    assert_eq!(a.shader_sources, b.shader_sources);
+    assert_eq!(a.files, b.files);
+}
+
+#[test]
+fn directories_include_nested_folders() {
+    let files = read_all(&mut Directory::new(demo_folder("cavefly")));
+    assert!(files.contains_key("data.json"));
+    assert!(files.keys().any(|path| path.starts_with("sources/")));
+}
+
+#[cfg(unix)]
+#[test]
+fn directories_skip_symlinked_folders() {
+    use std::os::unix::fs::symlink;
+
+    let folder = std::env::temp_dir().join(format!("gametoy_symlinks_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(folder.join("levels")).unwrap();
+    std::fs::write(folder.join("levels/one.bin"), [1]).unwrap();
+    // Following this would visit the folder forever
+    symlink(&folder, folder.join("levels/loop")).unwrap();
+    symlink(folder.join("levels/one.bin"), folder.join("linked.bin")).unwrap();
+
+    let files = read_all(&mut Directory::new(&folder));
+    std::fs::remove_dir_all(&folder).unwrap();
+
+    let paths: Vec<&str> = files.keys().map(String::as_str).collect();
+    assert_eq!(paths, vec!["levels/one.bin", "linked.bin"]);
+}
+
+#[test]
+fn every_source_loads_the_same() {
+    let folder = demo_folder("cavefly");
+    let files = read_all(&mut Directory::new(&folder));
+
+    let from_folder = GameData::from_source(Directory::new(&folder)).unwrap();
+    let from_path = GameData::from_source(data_source::open_path(&folder).unwrap()).unwrap();
+    let tar = make_tar(&files);
+    let from_tar = GameData::from_source(tar::Archive::new(&tar[..])).unwrap();
+    let zip = make_zip(&files);
+    let from_zip = GameData::from_source(zip::ZipArchive::new(Cursor::new(zip)).unwrap()).unwrap();
+
+    assert_same(&from_folder, &from_path);
+    assert_same(&from_folder, &from_tar);
+    assert_same(&from_folder, &from_zip);
+}
+
+#[test]
+fn compressed_tars_load_the_same() {
+    let folder = demo_folder("image_mipmap");
+    let tar = make_tar(&read_all(&mut Directory::new(&folder)));
+    let from_folder = GameData::from_source(Directory::new(&folder)).unwrap();
+
+    for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+        let compressed = compression.compress(&tar).unwrap();
+        assert_eq!(Compression::detect(&compressed), compression);
+
+        let archive = data_source::open_tar(&compressed[..]).unwrap();
+        let from_tar = GameData::from_source(archive)
+            .unwrap_or_else(|e| panic!("{:?}: {}", compression, gametoy::describe_error(&e)));
+        assert_same(&from_folder, &from_tar);
+    }
+}
+
+#[test]
+fn short_datapacks_are_not_compressed() {
+    assert_eq!(Compression::detect(&[]), Compression::None);
+    assert_eq!(Compression::detect(&[0x1f]), Compression::None);
+    let mut reader = data_source::decompress(&[0x28, 0xb5][..]).unwrap();
+    let mut contents = Vec::new();
+    reader.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, vec![0x28, 0xb5]);
+}
+
+/// A reader that always fails, like a file on a disk that has gone away
+struct FailingReader;
+
+impl Read for FailingReader {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("disk gone"))
+    }
+}
+
+#[test]
+fn unreadable_datapacks_are_decompress_errors() {
+    match data_source::decompress(FailingReader) {
+        Err(GameDataError::DecompressError(err)) => assert_eq!(err.to_string(), "disk gone"),
+        Err(err) => panic!("Expected DecompressError, got {}", err),
+        Ok(_) => panic!("Expected DecompressError"),
+    }
+}
+
+#[test]
+fn paths_are_normalised() {
+    let normalise = |path| data_source::normalise_path(path).unwrap();
+    assert_eq!(normalise("data.json"), "data.json");
+    assert_eq!(normalise("./data.json"), "data.json");
+    assert_eq!(normalise("shaders//./common.glsl"), "shaders/common.glsl");
+    assert_eq!(
+        normalise("textures\\ui\\button.png"),
+        "textures/ui/button.png"
+    );
+    assert_eq!(
+        normalise("textures/old/../ui/button.png"),
+        "textures/ui/button.png"
+    );
+}
+
+#[test]
+fn paths_outside_the_root_are_errors() {
+    for path in ["../data.json", "textures/../../button.png", "./.."] {
+        match data_source::normalise_path(path) {
+            Err(GameDataError::PathOutsideRoot(err_path)) => assert_eq!(err_path, path),
+            other => panic!("Expected PathOutsideRoot for {}, got {:?}", path, other),
+        }
+    }
+}
+
+#[test]
+fn files_with_the_same_normalised_path_are_errors() {
+    let config_file = std::fs::read(demo_folder("clock").join("data.json")).unwrap();
+    let mut files = BTreeMap::new();
+    files.insert("data.json".to_string(), config_file);
+    files.insert("levels/one.bin".to_string(), vec![1]);
+    files.insert("levels\\one.bin".to_string(), vec![2]);
+
+    match GameData::from_source(tar::Archive::new(&make_tar(&files)[..])) {
+        Err(GameDataError::DuplicatePath(first, second)) => {
+            assert_eq!(
+                (first.as_str(), second.as_str()),
+                ("levels/one.bin", "levels\\one.bin")
+            )
+        }
+        Err(err) => panic!("Expected DuplicatePath, got {}", err),
+        Ok(_) => panic!("Expected DuplicatePath"),
+    }
+}
+
+#[test]
+fn files_are_found_by_unnormalised_paths() {
+    let config_file = std::fs::read(demo_folder("clock").join("data.json")).unwrap();
+    let mut files = BTreeMap::new();
+    files.insert("data.json".to_string(), config_file);
+    files.insert("shaders/common.glsl".to_string(), b"float x;".to_vec());
+    files.insert("textures/ui/button.png".to_string(), vec![1, 2, 3]);
+    files.insert("levels/one.bin".to_string(), vec![4, 5, 6]);
+    let gamedata = GameData::from_source(tar::Archive::new(&make_tar(&files)[..])).unwrap();
+
+    let texture = gamedata.get_texture("textures\\ui\\button.png");
+    assert_eq!(texture, Some(&vec![1, 2, 3]));
+    let shader = gamedata.get_shader_source("./shaders//common.glsl");
+    assert_eq!(shader.map(String::as_str), Some("float x;"));
+    assert_eq!(
+        gamedata.get_file("levels/old/../one.bin"),
+        Some(&vec![4, 5, 6])
+    );
+    assert_eq!(gamedata.get_file("../levels/one.bin"), None);
+}
+
+#[test]
+fn nested_files_of_any_type_are_kept() {
+    let config_file = std::fs::read(demo_folder("clock").join("data.json")).unwrap();
+    let mut files = BTreeMap::new();
+    files.insert("./data.json".to_string(), config_file);
+    files.insert("shaders/common.glsl".to_string(), b"float x;".to_vec());
+    files.insert("textures/ui/button.png".to_string(), vec![1, 2, 3]);
+    files.insert("levels/one.bin".to_string(), vec![4, 5, 6]);
+
+    let zip = make_zip(&files);
+    let from_zip = GameData::from_source(zip::ZipArchive::new(Cursor::new(zip)).unwrap()).unwrap();
+    let tar = make_tar(&files);
+    let from_tar = GameData::from_source(tar::Archive::new(&tar[..])).unwrap();
+    assert_same(&from_zip, &from_tar);
+
+    assert_eq!(from_tar.shader_sources["shaders/common.glsl"], "float x;");
+    assert_eq!(from_tar.textures["textures/ui/button.png"], vec![1, 2, 3]);
+    assert_eq!(from_tar.files["levels/one.bin"], vec![4, 5, 6]);
+    assert!(!from_tar.files.contains_key("data.json"));
+}
+
+#[test]
+fn non_utf8_paths_are_errors() {
+    let name: &[u8] = b"textures/bad\xff.png";
+    let mut header = tar::Header::new_gnu();
+    header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name);
+    header.set_size(0);
+    header.set_mode(0o644);
+    header.set_cksum();
+    let mut builder = tar::Builder::new(Vec::new());
+    builder.append(&header, &[][..]).unwrap();
+    let tar = builder.into_inner().unwrap();
+
+    match GameData::from_source(tar::Archive::new(&tar[..])) {
+        Err(GameDataError::InvalidPath(path)) => assert_eq!(path, "textures/bad\u{fffd}.png"),
+        Err(err) => panic!("Expected InvalidPath, got {}", err),
+        Ok(_) => panic!("Expected InvalidPath"),
+    }
+}
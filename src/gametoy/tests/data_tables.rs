@@ -0,0 +1,69 @@
+//! Checks how data tables are read from CSV and JSON files
+mod common;
+
+use common::load_project;
+use gametoy::config_file::{DataTableConfig, DataTableFormat, DataTableLayout};
+use gametoy::nodes::{DataTable, NodeError};
+use serde_json::json;
+
+/// Reads a table from a file with the given path and contents
+fn read(path: &str, contents: &str, has_header: bool) -> Result<Vec<Vec<f32>>, NodeError> {
+    let gamedata = load_project(json!([]), json!([]), &[(path, contents.as_bytes())]);
+    let config = DataTableConfig {
+        name: "Table".to_string(),
+        path: path.to_string(),
+        format: DataTableFormat::R32F,
+        layout: DataTableLayout::Rows,
+        has_header,
+    };
+    DataTable::read_rows(&gamedata, &config)
+}
+
+fn assert_non_numeric(
+    result: Result<Vec<Vec<f32>>, NodeError>,
+    path: &str,
+    row: usize,
+    column: usize,
+) {
+    match result {
+        Err(NodeError::NonNumericCell(err_path, err_row, err_column)) => {
+            assert_eq!(
+                (err_path.as_str(), err_row, err_column),
+                (path, row, column)
+            )
+        }
+        other => panic!("Expected NonNumericCell, got {:?}", other),
+    }
+}
+
+#[test]
+fn csv_cells_must_be_numbers() {
+    // Blank lines still count towards the row
+    let csv = "1, 2, 3\n\n4, five, 6\n";
+    assert_non_numeric(read("stats.csv", csv, false), "stats.csv", 3, 2);
+}
+
+#[test]
+fn json_cells_must_be_numbers() {
+    let text = "[[1, 2, 3], [4, 5, \"6\"]]";
+    assert_non_numeric(read("stats.json", text, false), "stats.json", 2, 3);
+
+    let text = "[1, null]";
+    assert_non_numeric(read("row.json", text, false), "row.json", 1, 2);
+}
+
+#[test]
+fn csv_headers_are_skipped() {
+    let csv = "health, speed\n10, 2.5\n20\n";
+    assert_eq!(
+        read("stats.csv", csv, true).unwrap(),
+        vec![vec![10.0, 2.5], vec![20.0, 0.0]]
+    );
+
+    // Without skipping it, the header is the first non-numeric cell
+    assert_non_numeric(read("stats.csv", csv, false), "stats.csv", 1, 1);
+
+    // Rows are counted from the top of the file, header included
+    let csv = "health, speed\n10, fast\n";
+    assert_non_numeric(read("stats.csv", csv, true), "stats.csv", 2, 2);
+}
@@ -0,0 +1,98 @@
+//! Checks that the config files of the demos, which were written before
+//! `format_version` existed, are upgraded and load.
+mod common;
+
+use common::demo_config_files;
+use gametoy::config_file::{ConfigFile, ConfigFileFormat};
+use gametoy::migration::{self, MigrationError, CURRENT_FORMAT_VERSION, FORMAT_VERSION_KEY};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+fn read_document(path: &Path) -> Value {
+    let text = fs::read_to_string(path).unwrap();
+    serde_json::from_str(&text).unwrap_or_else(|e| panic!("{:?}: {}", path, e))
+}
+
+#[test]
+fn demos_are_unversioned() {
+    for path in demo_config_files() {
+        let document = read_document(&path);
+        assert_eq!(
+            migration::format_version(&document).unwrap(),
+            0,
+            "{:?}",
+            path
+        );
+    }
+}
+
+#[test]
+fn demos_migrate_to_current_version() {
+    for path in demo_config_files() {
+        let mut document = read_document(&path);
+        migration::migrate(&mut document).unwrap_or_else(|e| panic!("{:?}: {}", path, e));
+        assert_eq!(
+            document[FORMAT_VERSION_KEY],
+            Value::from(CURRENT_FORMAT_VERSION),
+            "{:?}",
+            path
+        );
+    }
+}
+
+#[test]
+fn demos_load() {
+    for path in demo_config_files() {
+        let config_file =
+            ConfigFile::from_reader(fs::File::open(&path).unwrap(), ConfigFileFormat::Json)
+                .unwrap_or_else(|e| panic!("{:?}: {}", path, gametoy::describe_error(&e)));
+        assert_eq!(config_file.format_version, CURRENT_FORMAT_VERSION);
+        assert!(!config_file.graph.nodes.is_empty(), "{:?}", path);
+    }
+}
+
+#[test]
+fn migrating_current_version_changes_nothing() {
+    for path in demo_config_files() {
+        let config_file =
+            ConfigFile::from_reader(fs::File::open(&path).unwrap(), ConfigFileFormat::Json)
+                .unwrap();
+        let saved = serde_json::to_value(&config_file).unwrap();
+
+        let mut migrated = saved.clone();
+        migration::migrate(&mut migrated).unwrap();
+        assert_eq!(migrated, saved, "{:?}", path);
+
+        let reloaded =
+            ConfigFile::from_reader(saved.to_string().as_bytes(), ConfigFileFormat::Json).unwrap();
+        assert_eq!(reloaded, config_file, "{:?}", path);
+    }
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let mut document = read_document(&demo_config_files()[0]);
+    document[FORMAT_VERSION_KEY] = Value::from(CURRENT_FORMAT_VERSION + 1);
+    match migration::migrate(&mut document) {
+        Err(MigrationError::NewerVersion(version)) => {
+            assert_eq!(version, CURRENT_FORMAT_VERSION as u64 + 1)
+        }
+        other => panic!("Expected NewerVersion, got {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_versions_are_rejected() {
+    let mut document = read_document(&demo_config_files()[0]);
+    document[FORMAT_VERSION_KEY] = Value::from("1");
+    assert!(matches!(
+        migration::migrate(&mut document),
+        Err(MigrationError::InvalidVersion(_))
+    ));
+
+    assert!(matches!(
+        migration::migrate(&mut Value::from(vec![1, 2, 3])),
+        Err(MigrationError::NotAnObject)
+    ));
+}
@@ -0,0 +1,153 @@
+//! Checks how the shader preprocessor finds and expands includes
+use gametoy::nodes::NodeError;
+use gametoy::preprocessor::Preprocessor;
+use gametoy::source_map::ShaderSource;
+use std::collections::HashMap;
+
+fn sources(files: &[(&str, &str)]) -> HashMap<String, String> {
+    files
+        .iter()
+        .map(|(path, source)| (path.to_string(), source.to_string()))
+        .collect()
+}
+
+fn expand(files: &[(&str, &str)], path: &str) -> Result<String, NodeError> {
+    Preprocessor::new(&sources(files)).expand(path)
+}
+
+#[test]
+fn includes_are_relative_before_the_root() {
+    let files = [
+        (
+            "shaders/main.frag",
+            "#include \"common.glsl\"\n#include \"root.glsl\"\n#include <../up.glsl>\n",
+        ),
+        ("shaders/common.glsl", "relative\n"),
+        ("common.glsl", "not this one\n"),
+        ("root.glsl", "root\n"),
+        ("up.glsl", "up\n"),
+    ];
+    assert_eq!(
+        expand(&files, "shaders/main.frag").unwrap(),
+        "relative\nroot\nup\n"
+    );
+}
+
+#[test]
+fn pragma_once_files_are_included_once() {
+    let files = [
+        (
+            "main.frag",
+            "#include \"once.glsl\"\n#include \"once.glsl\"\n",
+        ),
+        ("once.glsl", "#pragma once\nonce\n"),
+    ];
+    // The pragma is left as a blank line
+    assert_eq!(expand(&files, "main.frag").unwrap(), "\nonce\n");
+}
+
+#[test]
+fn guarded_files_are_included_once() {
+    let guarded = "// A comment before the guard\n#ifndef GUARD\n#define GUARD\nguarded\n#endif\n";
+    let files = [
+        (
+            "main.frag",
+            "#include \"guarded.glsl\"\n#include \"guarded.glsl\"\n#include \"plain.glsl\"\n#include \"plain.glsl\"\n",
+        ),
+        ("guarded.glsl", guarded),
+        ("plain.glsl", "plain\n"),
+    ];
+    assert_eq!(
+        expand(&files, "main.frag").unwrap(),
+        format!("{}plain\nplain\n", guarded)
+    );
+}
+
+#[test]
+fn once_applies_across_expansions() {
+    let files = [
+        ("main.frag", "#include \"once.glsl\"\nmain\n"),
+        ("once.glsl", "#pragma once\nonce\n"),
+    ];
+    let shader_sources = sources(&files);
+    let mut preprocessor = Preprocessor::new(&shader_sources);
+    assert_eq!(preprocessor.expand("once.glsl").unwrap(), "\nonce\n");
+    assert_eq!(preprocessor.expand("main.frag").unwrap(), "main\n");
+}
+
+#[test]
+fn include_cycles_are_errors() {
+    let files = [
+        ("main.frag", "#include \"a.glsl\"\n"),
+        ("a.glsl", "#include \"b.glsl\"\n"),
+        ("b.glsl", "#include \"a.glsl\"\n"),
+    ];
+    match expand(&files, "main.frag") {
+        Err(NodeError::IncludeCycle(cycle)) => assert_eq!(cycle, ["a.glsl", "b.glsl", "a.glsl"]),
+        other => panic!("Expected IncludeCycle, got {:?}", other),
+    }
+
+    let files = [("self.glsl", "#include \"./self.glsl\"\n")];
+    match expand(&files, "self.glsl") {
+        Err(NodeError::IncludeCycle(cycle)) => assert_eq!(cycle, ["self.glsl", "self.glsl"]),
+        other => panic!("Expected IncludeCycle, got {:?}", other),
+    }
+}
+
+#[test]
+fn missing_files_are_errors() {
+    let files = [("main.frag", "#include \"missing.glsl\"\n")];
+    match expand(&files, "main.frag") {
+        Err(NodeError::MissingResource(path)) => assert_eq!(path, "missing.glsl"),
+        other => panic!("Expected MissingResource, got {:?}", other),
+    }
+    match expand(&files, "other.frag") {
+        Err(NodeError::MissingResource(path)) => assert_eq!(path, "other.frag"),
+        other => panic!("Expected MissingResource, got {:?}", other),
+    }
+}
+
+#[test]
+fn malformed_includes_are_errors() {
+    for include in [
+        "#include missing.glsl",
+        "#include \"unclosed.glsl",
+        "#include \"\"",
+    ] {
+        let source = format!("void main() {{}}\n{}\n", include);
+        let files = [("main.frag", source.as_str())];
+        match expand(&files, "main.frag") {
+            Err(NodeError::MalformedInclude(path, line)) => {
+                assert_eq!((path.as_str(), line), ("main.frag", 2), "{}", include)
+            }
+            other => panic!("Expected MalformedInclude for {}, got {:?}", include, other),
+        }
+    }
+}
+
+#[test]
+fn source_map_follows_includes() {
+    let files = [
+        ("main.frag", "first\n#include \"inc.glsl\"\nlast\n"),
+        ("inc.glsl", "inc one\ninc two\n"),
+    ];
+    let shader_sources = sources(&files);
+    let mut output = ShaderSource::new();
+    output.push_generated("#version 300 es\n");
+    Preprocessor::new(&shader_sources)
+        .expand_into("main.frag", &mut output)
+        .unwrap();
+
+    assert_eq!(
+        output.text,
+        "#version 300 es\nfirst\ninc one\ninc two\nlast\n"
+    );
+    let map = &output.source_map;
+    assert_eq!(map.lookup(1), Some((None, 1)));
+    assert_eq!(map.lookup(2), Some((Some("main.frag"), 1)));
+    assert_eq!(map.lookup(3), Some((Some("inc.glsl"), 1)));
+    assert_eq!(map.lookup(4), Some((Some("inc.glsl"), 2)));
+    // The include line itself isn't in the output
+    assert_eq!(map.lookup(5), Some((Some("main.frag"), 3)));
+    assert_eq!(map.lookup(6), None);
+}
@@ -0,0 +1,114 @@
+//! Checks that shader compiler logs from each driver are understood, and
+//! that their lines are mapped back to the files the user wrote
+mod common;
+
+use common::load_project;
+use gametoy::config_file::Node;
+use gametoy::diagnostics::{parse_info_log, Diagnostic, Severity};
+use gametoy::nodes::generate_shader_text;
+use serde_json::json;
+
+/// Parses a log that should have a single diagnostic in it
+fn parse_one(log: &str) -> Diagnostic {
+    let mut diagnostics = parse_info_log(log);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    diagnostics.remove(0)
+}
+
+fn at_line(severity: Severity, message: &str, line: usize, column: Option<usize>) -> Diagnostic {
+    let mut diagnostic = Diagnostic::new(severity, message.to_string());
+    diagnostic.line = Some(line);
+    diagnostic.column = column;
+    diagnostic
+}
+
+#[test]
+fn mesa_logs_are_parsed() {
+    assert_eq!(
+        parse_one("0:12(5): error: `foo' undeclared\n"),
+        at_line(Severity::Error, "`foo' undeclared", 12, Some(5))
+    );
+    assert_eq!(
+        parse_one("0:3(10): warning: `x' used uninitialized"),
+        at_line(Severity::Warning, "`x' used uninitialized", 3, Some(10))
+    );
+}
+
+#[test]
+fn nvidia_logs_are_parsed() {
+    assert_eq!(
+        parse_one("0(12) : error C1008: undefined variable \"foo\""),
+        at_line(Severity::Error, "undefined variable \"foo\"", 12, None)
+    );
+    assert_eq!(
+        parse_one("0(7) : warning C7050: \"x\" might be used before being initialized"),
+        at_line(
+            Severity::Warning,
+            "\"x\" might be used before being initialized",
+            7,
+            None
+        )
+    );
+}
+
+#[test]
+fn angle_logs_are_parsed() {
+    let diagnostics = parse_info_log(
+        "ERROR: 0:12: 'foo' : undeclared identifier\n\
+         WARNING: 0:4: 'x' : unused variable\n\
+         ERROR: 1 compilation errors.  No code generated.\n",
+    );
+    assert_eq!(
+        diagnostics,
+        vec![
+            at_line(Severity::Error, "'foo' : undeclared identifier", 12, None),
+            at_line(Severity::Warning, "'x' : unused variable", 4, None),
+            Diagnostic::new(
+                Severity::Error,
+                "1 compilation errors.  No code generated.".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn lines_map_back_to_the_users_file() {
+    let shader = "void main() {\n    col = vec4(1.0);\n    oops;\n}\n";
+    let gamedata = load_project(
+        json!([{"RenderPass": {
+            "name": "Pass",
+            "output_texture_slots": [{"name": "col", "format": "RGBA8"}],
+            "input_texture_slots": [{"name": "tex"}],
+            "resolution_scaling_mode": {"ViewportScale": [1.0, 1.0]},
+            "fragment_shader_paths": ["pass.frag"],
+            "execution_mode": "Always",
+        }}]),
+        json!([]),
+        &[("pass.frag", shader.as_bytes())],
+    );
+    let config = match &gamedata.config_file.graph.nodes[0] {
+        Node::RenderPass(config) => config,
+        other => panic!("Expected a RenderPass, got {:?}", other),
+    };
+    let shader_source = generate_shader_text(config, &gamedata).unwrap();
+
+    // Where the compiler sees the line, after the generated preamble
+    let generated_line = shader_source
+        .text
+        .lines()
+        .position(|line| line == "    oops;")
+        .unwrap()
+        + 1;
+    assert!(generated_line > 3, "the preamble should come first");
+
+    let mut diagnostic = parse_one(&format!("0:{}(5): error: syntax error", generated_line));
+    shader_source.source_map.map_diagnostic(&mut diagnostic);
+    assert_eq!(diagnostic.file.as_deref(), Some("pass.frag"));
+    assert_eq!(diagnostic.line, Some(3));
+    assert_eq!(diagnostic.column, Some(5));
+
+    // Errors in the preamble are left pointing at the generated shader
+    let mut diagnostic = parse_one("0:1(1): error: bad version");
+    shader_source.source_map.map_diagnostic(&mut diagnostic);
+    assert_eq!((diagnostic.file, diagnostic.line), (None, Some(1)));
+}
@@ -0,0 +1,43 @@
+//! Checks that uniform values are limited to their `min` and `max`
+use gametoy::config_file::{UniformConfig, UniformValue};
+
+fn uniform(value: UniformValue, min: Option<f32>, max: Option<f32>) -> UniformConfig {
+    UniformConfig {
+        name: "u".to_string(),
+        value,
+        min,
+        max,
+        step: None,
+    }
+}
+
+#[test]
+fn ints_without_bounds_are_unchanged() {
+    let config = uniform(UniformValue::Int(0), None, None);
+    // Not representable as an f32
+    let value = UniformValue::Int(16_777_217);
+    assert_eq!(config.clamp(&value), value);
+}
+
+#[test]
+fn ints_are_clamped_to_whole_bounds() {
+    let config = uniform(UniformValue::Int(0), Some(-1.5), Some(2.5));
+    assert_eq!(config.clamp(&UniformValue::Int(-5)), UniformValue::Int(-1));
+    assert_eq!(config.clamp(&UniformValue::Int(1)), UniformValue::Int(1));
+    assert_eq!(config.clamp(&UniformValue::Int(5)), UniformValue::Int(2));
+
+    let config = uniform(UniformValue::Int(0), Some(16_777_217.0), None);
+    assert_eq!(
+        config.clamp(&UniformValue::Int(i32::MAX)),
+        UniformValue::Int(i32::MAX)
+    );
+}
+
+#[test]
+fn vectors_are_clamped_per_component() {
+    let config = uniform(UniformValue::Vec2([0.0, 0.0]), Some(0.0), Some(1.0));
+    assert_eq!(
+        config.clamp(&UniformValue::Vec2([-1.0, 2.0])),
+        UniformValue::Vec2([0.0, 1.0])
+    );
+}
@@ -1,9 +1,11 @@
 use eframe::{egui, epi};
+use gametoy::config_file::ConfigFileFormat;
 use gametoy::gamedata::CONFIG_FILE_NAME;
 use rfd::FileDialog;
 use std::env;
 use std::error::Error;
 use std::fs::{File, OpenOptions};
+use std::io::Write;
 use std::path::PathBuf;
 
 use super::metadata;
@@ -42,7 +44,7 @@ impl GametoyGraphEditor {
         exe_dir.pop();
 
         let mut dialog = FileDialog::new();
-        dialog = dialog.add_filter("json", &["json"]).set_directory(exe_dir);
+        dialog = add_config_file_filters(dialog).set_directory(exe_dir);
 
         let project_path = dialog.pick_file();
         if let Some(filepath) = project_path {
@@ -86,8 +88,7 @@ impl GametoyGraphEditor {
             }
         };
 
-        dialog = dialog
-            .add_filter("json", &["json"])
+        dialog = add_config_file_filters(dialog)
             .set_file_name(CONFIG_FILE_NAME)
             .set_directory(start_folder);
         if let Some(output_file) = dialog.save_file() {
@@ -108,10 +109,27 @@ impl GametoyGraphEditor {
     }
 }
 
+// Lets file dialogs pick config files in any of the formats GameToy reads
+fn add_config_file_filters(mut dialog: FileDialog) -> FileDialog {
+    for format in ConfigFileFormat::ALL.iter() {
+        dialog = dialog.add_filter(format.extension(), &[format.extension()]);
+    }
+    dialog
+}
+
+// The format of a config file is picked from it's extension, falling back
+// to JSON
+fn config_file_format(pathbuf: &PathBuf) -> ConfigFileFormat {
+    ConfigFileFormat::from_path(pathbuf).unwrap_or(ConfigFileFormat::Json)
+}
+
 // Read a config file from disk
 fn load_data_file(pathbuf: &PathBuf) -> Result<gametoy::config_file::ConfigFile, Box<dyn Error>> {
     let data_file = File::open(pathbuf)?;
-    Ok(serde_json::from_reader(data_file)?)
+    Ok(gametoy::config_file::ConfigFile::from_reader(
+        data_file,
+        config_file_format(pathbuf),
+    )?)
 }
 
 // Write a config file to disk
@@ -119,12 +137,13 @@ fn save_data_file(
     data: &gametoy::config_file::ConfigFile,
     pathbuf: &PathBuf,
 ) -> Result<(), Box<dyn Error>> {
-    let data_file = OpenOptions::new()
+    let mut data_file = OpenOptions::new()
         .write(true)
         .create(true)
         .truncate(true)
         .open(pathbuf)?;
-    serde_json::to_writer(data_file, data)?;
+    let text = data.to_string_pretty(config_file_format(pathbuf))?;
+    data_file.write_all(text.as_bytes())?;
     Ok(())
 }
 
@@ -245,7 +264,7 @@ impl epi::App for GametoyGraphEditor {
                     }
                 }
                 Err(err) => {
-                    println!("{:?}", err);
+                    println!("{}", gametoy::describe_error(err));
                 }
             }
         } else {
@@ -307,6 +326,30 @@ impl epi::App for GametoyGraphEditor {
                                     ));
                             }
                         });
+
+                    // A running toy also knows about problems found on the GPU
+                    let instance = &self.state.gametoy_instance;
+                    let diagnostics: &[gametoy::diagnostics::Diagnostic] = match instance {
+                        Some(Ok(gametoy)) => gametoy.diagnostics(),
+                        Some(Err(gametoy::GameToyError::InvalidConfig(diagnostics))) => diagnostics,
+                        _ => &[],
+                    };
+                    let error = match instance {
+                        // The errors are already in the diagnostics
+                        Some(Err(gametoy::GameToyError::InvalidConfig(_))) => None,
+                        Some(Err(err)) => Some(gametoy::describe_error(err)),
+                        _ => None,
+                    };
+                    if !diagnostics.is_empty() || error.is_some() {
+                        ui.separator();
+                        ui.heading("Problems:");
+                        if let Some(error) = error {
+                            ui.label(error);
+                        }
+                        for diagnostic in diagnostics.iter() {
+                            ui.label(diagnostic.to_string());
+                        }
+                    }
                 });
             });
 
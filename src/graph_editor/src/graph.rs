@@ -6,7 +6,7 @@ use std::collections::HashMap;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
-use crate::nodes::{get_input_slots, get_node_name, get_output_slots};
+use crate::nodes::get_node_name;
 
 pub fn draw_rendergraph_editor(
     ui: &mut egui::Ui,
@@ -26,7 +26,7 @@ pub fn draw_rendergraph_editor(
             let mut node_constructor = NodeConstructor::new(node_id, Default::default())
                 .with_title(move |ui| ui.label(title));
 
-            let input_slots = get_input_slots(&node);
+            let input_slots = node.get_input_slots();
             for (slot_id, input_name) in input_slots.iter().enumerate() {
                 let slot_name = input_name.to_string();
                 let pin_id = pairing_function(node_id, slot_id);
@@ -35,7 +35,7 @@ pub fn draw_rendergraph_editor(
                         ui.label(slot_name)
                     });
             }
-            for (slot_id, output_name) in get_output_slots(&node).iter().enumerate() {
+            for (slot_id, output_name) in node.get_output_slots().iter().enumerate() {
                 let slot_name = output_name.clone();
                 let pin_id = pairing_function(node_id, slot_id + input_slots.len());
                 node_constructor =
@@ -61,15 +61,17 @@ pub fn draw_rendergraph_editor(
     for link in graph_links {
         if let Some(start_node_id) = node_name_to_id.get(&link.start_node) {
             if let Some(end_node_id) = node_name_to_id.get(&link.end_node) {
-                if let Some(start_slot_id) = get_output_slots(&graph_nodes[*start_node_id])
+                if let Some(start_slot_id) = graph_nodes[*start_node_id]
+                    .get_output_slots()
                     .iter()
                     .position(|x| *x == link.start_output_slot)
                 {
-                    if let Some(end_slot_id) = get_input_slots(&graph_nodes[*end_node_id])
+                    if let Some(end_slot_id) = graph_nodes[*end_node_id]
+                        .get_input_slots()
                         .iter()
                         .position(|x| *x == link.end_input_slot)
                     {
-                        let input_slots = get_input_slots(&graph_nodes[*start_node_id]);
+                        let input_slots = graph_nodes[*start_node_id].get_input_slots();
                         let start_pin =
                             pairing_function(*start_node_id, start_slot_id + input_slots.len());
                         let end_pin = pairing_function(*end_node_id, end_slot_id);
@@ -104,12 +106,12 @@ pub fn draw_rendergraph_editor(
         let (end_node_id, end_slot_id) = unpairing_function(end);
         let start_node = &graph_nodes[start_node_id];
         let end_node = &graph_nodes[end_node_id];
-        let start_slot_id = start_slot_id_and_len - get_input_slots(&start_node).len();
+        let start_slot_id = start_slot_id_and_len - start_node.get_input_slots().len();
 
         let start_node_name = get_node_name(&start_node).to_string();
         let end_node_name = get_node_name(&end_node).to_string();
-        let start_output_slot_name = get_output_slots(&start_node)[start_slot_id].clone();
-        let end_input_slot_name = get_input_slots(&end_node)[end_slot_id].clone();
+        let start_output_slot_name = start_node.get_output_slots()[start_slot_id].clone();
+        let end_input_slot_name = end_node.get_input_slots()[end_slot_id].clone();
 
         let link_to_create = gametoy::config_file::Link {
             start_node: start_node_name,
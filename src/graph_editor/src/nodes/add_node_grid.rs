@@ -13,12 +13,15 @@ pub fn add_node_widget(ui: &mut egui::Ui, node_list: &Vec<Node>, reactor: &mut R
                 }],
                 input_texture_slots: vec![gametoy::config_file::InputBufferConfig {
                     name: "KeyboardInput".to_string(),
+                    sampler_type: gametoy::config_file::SamplerType::Sampler2D,
                 }],
                 resolution_scaling_mode: gametoy::config_file::ResolutionScalingMode::ViewportScale(
                     1.0, 1.0,
                 ),
                 fragment_shader_paths: vec![],
                 execution_mode: gametoy::config_file::ExecutionMode::Always,
+                uniforms: vec![],
+                shadertoy_compat: false,
             }),
         ));
     }
@@ -29,6 +32,99 @@ pub fn add_node_widget(ui: &mut egui::Ui, node_list: &Vec<Node>, reactor: &mut R
                 name: format!("Image {}", node_list.len()),
                 path: String::new(),
                 generate_mipmap: false,
+                color_space: gametoy::config_file::ColorSpace::Linear,
+            }),
+        ));
+    }
+    if ui.button("Cubemap").clicked() {
+        reactor.queue_operation(StateOperation::CreateNode(
+            gametoy::config_file::Node::Cubemap(gametoy::config_file::CubemapConfig {
+                name: format!("Cubemap {}", node_list.len()),
+                faces: gametoy::config_file::CubemapFaces::Cross(String::new()),
+                generate_mipmap: false,
+                color_space: gametoy::config_file::ColorSpace::Linear,
+            }),
+        ));
+    }
+    if ui.button("Texture Array").clicked() {
+        reactor.queue_operation(StateOperation::CreateNode(
+            gametoy::config_file::Node::TextureArray(gametoy::config_file::TextureArrayConfig {
+                name: format!("Texture Array {}", node_list.len()),
+                paths: vec![],
+                generate_mipmap: false,
+                color_space: gametoy::config_file::ColorSpace::Linear,
+            }),
+        ));
+    }
+    if ui.button("Animated Image").clicked() {
+        reactor.queue_operation(StateOperation::CreateNode(
+            gametoy::config_file::Node::AnimatedImage(gametoy::config_file::AnimatedImageConfig {
+                name: format!("Animated Image {}", node_list.len()),
+                source: gametoy::config_file::AnimationSource::Apng(String::new()),
+                fps: 12.0,
+                mode: gametoy::config_file::AnimationMode::Loop,
+                generate_mipmap: false,
+                color_space: gametoy::config_file::ColorSpace::Linear,
+            }),
+        ));
+    }
+    if ui.button("Atlas").clicked() {
+        reactor.queue_operation(StateOperation::CreateNode(
+            gametoy::config_file::Node::Atlas(gametoy::config_file::AtlasConfig {
+                name: format!("Atlas {}", node_list.len()),
+                path: String::new(),
+                sheet_path: String::new(),
+                generate_mipmap: false,
+                color_space: gametoy::config_file::ColorSpace::Linear,
+            }),
+        ));
+    }
+    if ui.button("Font").clicked() {
+        reactor.queue_operation(StateOperation::CreateNode(
+            gametoy::config_file::Node::Font(gametoy::config_file::FontConfig {
+                name: format!("Font {}", node_list.len()),
+                source: gametoy::config_file::FontSource::Proportional,
+                size: 16.0,
+                characters: (32..=126u8).map(|c| c as char).collect(),
+            }),
+        ));
+    }
+    if ui.button("Tiled Map").clicked() {
+        reactor.queue_operation(StateOperation::CreateNode(
+            gametoy::config_file::Node::TiledMap(gametoy::config_file::TiledMapConfig {
+                name: format!("Tiled Map {}", node_list.len()),
+                path: String::new(),
+                layers: vec![],
+            }),
+        ));
+    }
+    if ui.button("Data Table").clicked() {
+        reactor.queue_operation(StateOperation::CreateNode(
+            gametoy::config_file::Node::DataTable(gametoy::config_file::DataTableConfig {
+                name: format!("Data Table {}", node_list.len()),
+                path: String::new(),
+                format: gametoy::config_file::DataTableFormat::R32F,
+                layout: gametoy::config_file::DataTableLayout::Rows,
+                has_header: false,
+            }),
+        ));
+    }
+    if ui.button("Host Data").clicked() {
+        reactor.queue_operation(StateOperation::CreateNode(
+            gametoy::config_file::Node::HostData(gametoy::config_file::HostDataConfig {
+                name: format!("Host Data {}", node_list.len()),
+                size: [256, 256],
+                format: gametoy::config_file::OutputBufferFormat::RGBA8,
+            }),
+        ));
+    }
+    if ui.button("Readback").clicked() {
+        reactor.queue_operation(StateOperation::CreateNode(
+            gametoy::config_file::Node::Readback(gametoy::config_file::ReadbackConfig {
+                name: format!("Readback {}", node_list.len()),
+                offset: [0, 0],
+                size: [1, 1],
+                format: gametoy::config_file::ReadbackFormat::RGBA8,
             }),
         ));
     }
@@ -43,6 +139,7 @@ pub fn add_node_widget(ui: &mut egui::Ui, node_list: &Vec<Node>, reactor: &mut R
         reactor.queue_operation(StateOperation::CreateNode(
             gametoy::config_file::Node::Output(gametoy::config_file::OutputConfig {
                 name: format!("Output {}", node_list.len()),
+                color_space: gametoy::config_file::ColorSpace::Srgb,
             }),
         ));
     }
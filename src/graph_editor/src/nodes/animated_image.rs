@@ -0,0 +1,65 @@
+use crate::helpers::path_widget;
+use gametoy::config_file::{AnimatedImageConfig, AnimationMode, AnimationSource};
+
+use super::color_space::color_space_widget;
+
+pub fn edit_animated_image(ui: &mut egui::Ui, node: &mut AnimatedImageConfig) {
+    ui.label("Name:");
+    ui.text_edit_singleline(&mut node.name)
+        .on_hover_text("Name of the node");
+    ui.end_row();
+
+    ui.label("Source:");
+    let mut is_sequence = matches!(node.source, AnimationSource::Sequence(_));
+    egui::ComboBox::from_id_source("Animation Source")
+        .selected_text(if is_sequence { "Sequence" } else { "APNG" })
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut is_sequence, false, "APNG");
+            ui.selectable_value(&mut is_sequence, true, "Sequence");
+        });
+    ui.end_row();
+
+    if is_sequence != matches!(node.source, AnimationSource::Sequence(_)) {
+        node.source = if is_sequence {
+            AnimationSource::Sequence(String::new())
+        } else {
+            AnimationSource::Apng(String::new())
+        };
+    }
+
+    match &mut node.source {
+        AnimationSource::Apng(path) => {
+            ui.label("Path:");
+            path_widget(path, ui);
+            ui.end_row();
+        }
+        AnimationSource::Sequence(pattern) => {
+            ui.label("Pattern:");
+            ui.text_edit_singleline(pattern)
+                .on_hover_text("Path with a run of # replaced by the frame number");
+            ui.end_row();
+        }
+    }
+
+    ui.label("FPS:");
+    ui.add(egui::DragValue::new(&mut node.fps).clamp_range(0.0..=240.0));
+    ui.end_row();
+
+    ui.label("Mode:");
+    egui::ComboBox::from_id_source("Animation Mode")
+        .selected_text(format!("{:?}", node.mode))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut node.mode, AnimationMode::Loop, "Loop");
+            ui.selectable_value(&mut node.mode, AnimationMode::PingPong, "PingPong");
+            ui.selectable_value(&mut node.mode, AnimationMode::Once, "Once");
+        });
+    ui.end_row();
+
+    ui.label("Mipmap:");
+    ui.checkbox(&mut node.generate_mipmap, "");
+    ui.end_row();
+
+    ui.label("Color Space:");
+    color_space_widget(ui, &mut node.color_space);
+    ui.end_row();
+}
@@ -0,0 +1,25 @@
+use gametoy::config_file::ColorSpace;
+
+pub fn color_space_widget(ui: &mut egui::Ui, color_space: &mut ColorSpace) {
+    egui::ComboBox::from_id_source("Color Space")
+        .selected_text(color_space_to_str(color_space))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(
+                color_space,
+                ColorSpace::Linear,
+                color_space_to_str(&ColorSpace::Linear),
+            );
+            ui.selectable_value(
+                color_space,
+                ColorSpace::Srgb,
+                color_space_to_str(&ColorSpace::Srgb),
+            );
+        });
+}
+
+pub fn color_space_to_str(color_space: &ColorSpace) -> &str {
+    match color_space {
+        ColorSpace::Linear => "Linear",
+        ColorSpace::Srgb => "sRGB",
+    }
+}
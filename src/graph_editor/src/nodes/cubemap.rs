@@ -0,0 +1,73 @@
+use crate::helpers::path_widget;
+use gametoy::config_file::{CubemapConfig, CubemapFaces};
+
+use super::color_space::color_space_widget;
+
+pub fn edit_cubemap(ui: &mut egui::Ui, node: &mut CubemapConfig) {
+    ui.label("Name:");
+    ui.text_edit_singleline(&mut node.name)
+        .on_hover_text("Name of the node");
+    ui.end_row();
+
+    ui.label("Layout:");
+    let mut is_cross = matches!(node.faces, CubemapFaces::Cross(_));
+    egui::ComboBox::from_id_source("Cubemap Layout")
+        .selected_text(if is_cross { "Cross" } else { "Separate" })
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut is_cross, false, "Separate");
+            ui.selectable_value(&mut is_cross, true, "Cross");
+        });
+    ui.end_row();
+
+    if is_cross != matches!(node.faces, CubemapFaces::Cross(_)) {
+        node.faces = if is_cross {
+            CubemapFaces::Cross(String::new())
+        } else {
+            CubemapFaces::Separate {
+                positive_x: String::new(),
+                negative_x: String::new(),
+                positive_y: String::new(),
+                negative_y: String::new(),
+                positive_z: String::new(),
+                negative_z: String::new(),
+            }
+        };
+    }
+
+    match &mut node.faces {
+        CubemapFaces::Cross(path) => {
+            ui.label("Path:");
+            path_widget(path, ui);
+            ui.end_row();
+        }
+        CubemapFaces::Separate {
+            positive_x,
+            negative_x,
+            positive_y,
+            negative_y,
+            positive_z,
+            negative_z,
+        } => {
+            for (label, path) in [
+                ("+X:", positive_x),
+                ("-X:", negative_x),
+                ("+Y:", positive_y),
+                ("-Y:", negative_y),
+                ("+Z:", positive_z),
+                ("-Z:", negative_z),
+            ] {
+                ui.label(label);
+                path_widget(path, ui);
+                ui.end_row();
+            }
+        }
+    }
+
+    ui.label("Mipmap:");
+    ui.checkbox(&mut node.generate_mipmap, "");
+    ui.end_row();
+
+    ui.label("Color Space:");
+    color_space_widget(ui, &mut node.color_space);
+    ui.end_row();
+}
@@ -0,0 +1,36 @@
+use crate::helpers::path_widget;
+use gametoy::config_file::{DataTableConfig, DataTableFormat, DataTableLayout};
+
+pub fn edit_data_table(ui: &mut egui::Ui, node: &mut DataTableConfig) {
+    ui.label("Name:");
+    ui.text_edit_singleline(&mut node.name)
+        .on_hover_text("Name of the node");
+    ui.end_row();
+
+    ui.label("Path:");
+    path_widget(&mut node.path, ui);
+    ui.end_row();
+
+    ui.label("Format:");
+    egui::ComboBox::from_id_source("Data Table Format")
+        .selected_text(format!("{:?}", node.format))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut node.format, DataTableFormat::R32F, "R32F");
+            ui.selectable_value(&mut node.format, DataTableFormat::RGBA32F, "RGBA32F");
+        });
+    ui.end_row();
+
+    ui.label("Layout:");
+    egui::ComboBox::from_id_source("Data Table Layout")
+        .selected_text(format!("{:?}", node.layout))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut node.layout, DataTableLayout::Rows, "Rows");
+            ui.selectable_value(&mut node.layout, DataTableLayout::Columns, "Columns");
+        });
+    ui.end_row();
+
+    ui.label("CSV Header:");
+    ui.checkbox(&mut node.has_header, "")
+        .on_hover_text("Skip the first line of a CSV file");
+    ui.end_row();
+}
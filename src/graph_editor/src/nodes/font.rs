@@ -0,0 +1,51 @@
+use crate::helpers::path_widget;
+use gametoy::config_file::{FontConfig, FontSource};
+
+fn font_source_to_str(source: &FontSource) -> &'static str {
+    match source {
+        FontSource::File(_) => "File",
+        FontSource::Proportional => "Proportional",
+        FontSource::Monospace => "Monospace",
+    }
+}
+
+pub fn edit_font(ui: &mut egui::Ui, node: &mut FontConfig) {
+    ui.label("Name:");
+    ui.text_edit_singleline(&mut node.name)
+        .on_hover_text("Name of the node");
+    ui.end_row();
+
+    ui.label("Font:");
+    let mut source_name = font_source_to_str(&node.source);
+    egui::ComboBox::from_id_source("Font Source")
+        .selected_text(source_name)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut source_name, "File", "File");
+            ui.selectable_value(&mut source_name, "Proportional", "Proportional");
+            ui.selectable_value(&mut source_name, "Monospace", "Monospace");
+        });
+    ui.end_row();
+
+    if source_name != font_source_to_str(&node.source) {
+        node.source = match source_name {
+            "File" => FontSource::File(String::new()),
+            "Monospace" => FontSource::Monospace,
+            _ => FontSource::Proportional,
+        };
+    }
+
+    if let FontSource::File(path) = &mut node.source {
+        ui.label("Path:");
+        path_widget(path, ui);
+        ui.end_row();
+    }
+
+    ui.label("Size:");
+    ui.add(egui::DragValue::new(&mut node.size).clamp_range(1.0..=256.0));
+    ui.end_row();
+
+    ui.label("Characters:");
+    ui.text_edit_multiline(&mut node.characters)
+        .on_hover_text("The glyph index of a character is it's position in this list");
+    ui.end_row();
+}
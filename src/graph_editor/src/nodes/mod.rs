@@ -1,10 +1,20 @@
 mod add_node_grid;
+mod animated_image;
+mod color_space;
+mod cubemap;
+mod data_table;
 mod execution_mode;
+mod font;
 mod output_buffer_format;
+mod readback;
 mod renderpass;
 mod resolution_scaling_mode;
+mod sampler_type;
+mod uniforms;
 
-use super::helpers::path_widget;
+use super::helpers::{list_edit_mut, path_widget};
+use color_space::color_space_widget;
+use output_buffer_format::output_buffer_format_selector;
 
 pub use add_node_grid::add_node_widget;
 
@@ -35,6 +45,10 @@ pub fn draw_node_properties(
                 ui.label("Mipmap:");
                 ui.checkbox(&mut node.generate_mipmap, "");
                 ui.end_row();
+
+                ui.label("Color Space:");
+                color_space_widget(ui, &mut node.color_space);
+                ui.end_row();
             }
             Node::RenderPass(node) => {
                 renderpass::edit_render_pass(ui, node);
@@ -44,6 +58,10 @@ pub fn draw_node_properties(
                 ui.text_edit_singleline(&mut node.name)
                     .on_hover_text("Name of the node");
                 ui.end_row();
+
+                ui.label("Color Space:");
+                color_space_widget(ui, &mut node.color_space);
+                ui.end_row();
             }
             Node::Keyboard(node) => {
                 ui.label("Name:");
@@ -51,6 +69,117 @@ pub fn draw_node_properties(
                     .on_hover_text("Name of the node");
                 ui.end_row();
             }
+            Node::Cubemap(node) => {
+                cubemap::edit_cubemap(ui, node);
+            }
+            Node::AnimatedImage(node) => {
+                animated_image::edit_animated_image(ui, node);
+            }
+            Node::Font(node) => {
+                font::edit_font(ui, node);
+            }
+            Node::DataTable(node) => {
+                data_table::edit_data_table(ui, node);
+            }
+            Node::Readback(node) => {
+                readback::edit_readback(ui, node);
+            }
+            Node::HostData(node) => {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut node.name)
+                    .on_hover_text("Name of the node");
+                ui.end_row();
+
+                ui.label("Size:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut node.size[0]).suffix("px"));
+                    ui.add(egui::DragValue::new(&mut node.size[1]).suffix("px"));
+                });
+                node.size = [node.size[0].max(1), node.size[1].max(1)];
+                ui.end_row();
+
+                ui.label("Format:");
+                output_buffer_format_selector(ui, &mut node.format, 0);
+                ui.end_row();
+            }
+            Node::TiledMap(node) => {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut node.name)
+                    .on_hover_text("Name of the node");
+                ui.end_row();
+
+                ui.label("Path:");
+                path_widget(&mut node.path, ui);
+                ui.end_row();
+
+                ui.label("Layers:");
+                ui.vertical(|ui| {
+                    list_edit_mut(
+                        ui,
+                        &mut node.layers,
+                        |ui, _item_id, layer| {
+                            ui.text_edit_singleline(layer);
+                        },
+                        "tiled_map_layer_grid",
+                    );
+                    if ui.button("Add Layer").clicked() {
+                        node.layers.push(String::new());
+                    }
+                });
+                ui.end_row();
+            }
+            Node::Atlas(node) => {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut node.name)
+                    .on_hover_text("Name of the node");
+                ui.end_row();
+
+                ui.label("Path:");
+                path_widget(&mut node.path, ui);
+                ui.end_row();
+
+                ui.label("Sprite Sheet:");
+                path_widget(&mut node.sheet_path, ui);
+                ui.end_row();
+
+                ui.label("Mipmap:");
+                ui.checkbox(&mut node.generate_mipmap, "");
+                ui.end_row();
+
+                ui.label("Color Space:");
+                color_space_widget(ui, &mut node.color_space);
+                ui.end_row();
+            }
+            Node::TextureArray(node) => {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut node.name)
+                    .on_hover_text("Name of the node");
+                ui.end_row();
+
+                ui.label("Layers:");
+                ui.vertical(|ui| {
+                    list_edit_mut(
+                        ui,
+                        &mut node.paths,
+                        |ui, _item_id, path| {
+                            path_widget(path, ui);
+                        },
+                        "texture_array_layer_grid",
+                    );
+                    if ui.button("Add Layer").clicked() {
+                        node.paths.push(String::new());
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Mipmap:");
+                ui.checkbox(&mut node.generate_mipmap, "");
+                ui.end_row();
+
+                ui.label("Color Space:");
+                color_space_widget(ui, &mut node.color_space);
+                ui.end_row();
+            }
         });
 
     if &new_node_data != node_data {
@@ -64,6 +193,15 @@ pub fn get_node_name(node_data: &Node) -> &str {
         Node::RenderPass(pass_data) => &pass_data.name,
         Node::Output(output_data) => &output_data.name,
         Node::Keyboard(keyboard_data) => &keyboard_data.name,
+        Node::Cubemap(cubemap_data) => &cubemap_data.name,
+        Node::TextureArray(array_data) => &array_data.name,
+        Node::AnimatedImage(anim_data) => &anim_data.name,
+        Node::Atlas(atlas_data) => &atlas_data.name,
+        Node::Font(font_data) => &font_data.name,
+        Node::TiledMap(map_data) => &map_data.name,
+        Node::DataTable(table_data) => &table_data.name,
+        Node::HostData(host_data) => &host_data.name,
+        Node::Readback(readback_data) => &readback_data.name,
     }
 }
 
@@ -73,37 +211,14 @@ pub fn get_node_type_name(node_data: &Node) -> &str {
         Node::RenderPass(_) => "RenderPass",
         Node::Output(_) => "Output",
         Node::Keyboard(_) => "Keyboard",
-    }
-}
-
-pub fn get_input_slots(node: &gametoy::config_file::Node) -> Vec<String> {
-    match node {
-        gametoy::config_file::Node::Image(_image_data) => vec![],
-        gametoy::config_file::Node::Keyboard(_keyboard_data) => vec![],
-        gametoy::config_file::Node::Output(_output_data) => {
-            vec![gametoy::nodes::Output::INPUT_BUFFER_NAME.to_string()]
-        }
-        gametoy::config_file::Node::RenderPass(renderpass_data) => renderpass_data
-            .input_texture_slots
-            .iter()
-            .map(|x| x.name.clone())
-            .collect(),
-    }
-}
-
-pub fn get_output_slots(node: &gametoy::config_file::Node) -> Vec<String> {
-    match node {
-        gametoy::config_file::Node::Image(_image_data) => {
-            vec![gametoy::nodes::Image::OUTPUT_BUFFER_NAME.to_string()]
-        }
-        gametoy::config_file::Node::Keyboard(_keyboard_data) => {
-            vec![gametoy::nodes::Keyboard::OUTPUT_BUFFER_NAME.to_string()]
-        }
-        gametoy::config_file::Node::Output(_output_data) => vec![],
-        gametoy::config_file::Node::RenderPass(renderpass_data) => renderpass_data
-            .output_texture_slots
-            .iter()
-            .map(|x| x.name.clone())
-            .collect(),
+        Node::Cubemap(_) => "Cubemap",
+        Node::TextureArray(_) => "TextureArray",
+        Node::AnimatedImage(_) => "AnimatedImage",
+        Node::Atlas(_) => "Atlas",
+        Node::Font(_) => "Font",
+        Node::TiledMap(_) => "TiledMap",
+        Node::DataTable(_) => "DataTable",
+        Node::HostData(_) => "HostData",
+        Node::Readback(_) => "Readback",
     }
 }
@@ -0,0 +1,34 @@
+use gametoy::config_file::{ReadbackConfig, ReadbackFormat};
+
+pub fn edit_readback(ui: &mut egui::Ui, node: &mut ReadbackConfig) {
+    ui.label("Name:");
+    ui.text_edit_singleline(&mut node.name)
+        .on_hover_text("Name of the node");
+    ui.end_row();
+
+    ui.label("Offset:");
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut node.offset[0]).suffix("px"));
+        ui.add(egui::DragValue::new(&mut node.offset[1]).suffix("px"));
+    });
+    ui.end_row();
+
+    ui.label("Size:");
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut node.size[0]).suffix("px"));
+        ui.add(egui::DragValue::new(&mut node.size[1]).suffix("px"));
+    });
+    node.size = [node.size[0].max(1), node.size[1].max(1)];
+    ui.end_row();
+
+    ui.label("Format:");
+    egui::ComboBox::from_id_source("Readback Format")
+        .selected_text(format!("{:?}", node.format))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut node.format, ReadbackFormat::RGBA8, "RGBA8");
+            ui.selectable_value(&mut node.format, ReadbackFormat::RGBA32F, "RGBA32F");
+            ui.selectable_value(&mut node.format, ReadbackFormat::RGBA32I, "RGBA32I");
+            ui.selectable_value(&mut node.format, ReadbackFormat::RGBA32UI, "RGBA32UI");
+        });
+    ui.end_row();
+}
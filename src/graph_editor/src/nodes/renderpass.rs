@@ -1,11 +1,13 @@
 use crate::helpers::{list_edit_mut, path_widget};
 use gametoy::config_file::{
-    InputBufferConfig, OutputBufferConfig, OutputBufferFormat, RenderPassConfig,
+    InputBufferConfig, OutputBufferConfig, OutputBufferFormat, RenderPassConfig, SamplerType,
 };
 
 use super::execution_mode::execution_mode_widget;
 use super::output_buffer_format::output_buffer_format_selector;
 use super::resolution_scaling_mode::resolution_scaling_mode_widget;
+use super::sampler_type::sampler_type_selector;
+use super::uniforms::edit_uniforms;
 use egui::TextEdit;
 
 pub fn edit_render_pass(ui: &mut egui::Ui, node: &mut RenderPassConfig) {
@@ -23,6 +25,11 @@ pub fn edit_render_pass(ui: &mut egui::Ui, node: &mut RenderPassConfig) {
     ui.label("Execution Mode:");
     execution_mode_widget(ui, &mut node.execution_mode);
     ui.end_row();
+
+    ui.label("Shadertoy:");
+    ui.checkbox(&mut node.shadertoy_compat, "")
+        .on_hover_text("The shader defines mainImage and reads iChannel0..3");
+    ui.end_row();
     ui.separator();
     ui.end_row();
 
@@ -56,6 +63,9 @@ pub fn edit_render_pass(ui: &mut egui::Ui, node: &mut RenderPassConfig) {
                         ui.label("Name:");
                         ui.add_sized([110.0, 20.0], TextEdit::singleline(&mut input_config.name));
                         ui.end_row();
+                        ui.label("Sampler:");
+                        sampler_type_selector(ui, &mut input_config.sampler_type, item_id);
+                        ui.end_row();
                     });
             },
             "input_texture_grid",
@@ -63,6 +73,7 @@ pub fn edit_render_pass(ui: &mut egui::Ui, node: &mut RenderPassConfig) {
         if ui.button("Add Input").clicked() {
             node.input_texture_slots.push(InputBufferConfig {
                 name: String::new(),
+                sampler_type: SamplerType::Sampler2D,
             });
         }
     });
@@ -100,4 +111,11 @@ pub fn edit_render_pass(ui: &mut egui::Ui, node: &mut RenderPassConfig) {
             });
         }
     });
+    ui.end_row();
+    ui.separator();
+    ui.end_row();
+
+    ui.label("Uniforms: ");
+    edit_uniforms(ui, &mut node.uniforms);
+    ui.end_row();
 }
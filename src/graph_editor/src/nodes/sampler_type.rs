@@ -0,0 +1,26 @@
+use gametoy::config_file::SamplerType;
+
+pub fn sampler_type_selector(ui: &mut egui::Ui, sampler_type: &mut SamplerType, slot_id: usize) {
+    egui::ComboBox::from_id_source(format!("in_sampler_type{}", slot_id))
+        .selected_text(sampler_type_to_str(sampler_type))
+        .show_ui(ui, |ui| {
+            sampler_type_entry(ui, sampler_type, SamplerType::Sampler2D);
+            sampler_type_entry(ui, sampler_type, SamplerType::SamplerCube);
+            sampler_type_entry(ui, sampler_type, SamplerType::Sampler2DArray);
+            sampler_type_entry(ui, sampler_type, SamplerType::USampler2D);
+        });
+}
+
+fn sampler_type_entry(ui: &mut egui::Ui, sampler_type: &mut SamplerType, value: SamplerType) {
+    let text = sampler_type_to_str(&value);
+    ui.selectable_value(sampler_type, value, text);
+}
+
+pub fn sampler_type_to_str(sampler_type: &SamplerType) -> &'static str {
+    match sampler_type {
+        SamplerType::Sampler2D => "sampler2D",
+        SamplerType::SamplerCube => "samplerCube",
+        SamplerType::Sampler2DArray => "sampler2DArray",
+        SamplerType::USampler2D => "usampler2D",
+    }
+}
@@ -0,0 +1,142 @@
+use crate::helpers::list_edit_mut;
+use egui::TextEdit;
+use gametoy::config_file::{UniformConfig, UniformValue};
+
+/// The types a uniform can be switched between, with a default value for each
+const UNIFORM_TYPES: [(&str, UniformValue); 7] = [
+    ("Float", UniformValue::Float(0.0)),
+    ("Int", UniformValue::Int(0)),
+    ("Vec2", UniformValue::Vec2([0.0; 2])),
+    ("Vec3", UniformValue::Vec3([0.0; 3])),
+    ("Vec4", UniformValue::Vec4([0.0; 4])),
+    ("Color", UniformValue::Color([1.0; 4])),
+    ("Bool", UniformValue::Bool(false)),
+];
+
+pub fn edit_uniforms(ui: &mut egui::Ui, uniforms: &mut Vec<UniformConfig>) {
+    ui.vertical(|ui| {
+        list_edit_mut(
+            ui,
+            uniforms,
+            |ui, item_id, uniform| {
+                egui::Grid::new(format!("uniform_grid{}", item_id))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        ui.add_sized([110.0, 20.0], TextEdit::singleline(&mut uniform.name));
+                        ui.end_row();
+
+                        ui.label("Type:");
+                        uniform_type_selector(ui, &mut uniform.value, item_id);
+                        ui.end_row();
+
+                        ui.label("Value:");
+                        uniform_value_widget(ui, uniform);
+                        ui.end_row();
+
+                        ui.label("Min:");
+                        optional_f32_widget(ui, &mut uniform.min);
+                        ui.end_row();
+
+                        ui.label("Max:");
+                        optional_f32_widget(ui, &mut uniform.max);
+                        ui.end_row();
+
+                        ui.label("Step:");
+                        optional_f32_widget(ui, &mut uniform.step);
+                        ui.end_row();
+                    });
+            },
+            "uniform_list_grid",
+        );
+        if ui.button("Add Uniform").clicked() {
+            uniforms.push(UniformConfig {
+                name: String::new(),
+                value: UniformValue::Float(0.0),
+                min: None,
+                max: None,
+                step: None,
+            });
+        }
+    });
+}
+
+fn uniform_type_name(value: &UniformValue) -> &'static str {
+    UNIFORM_TYPES
+        .iter()
+        .find(|(_, default)| default.is_same_type(value))
+        .map(|(name, _)| *name)
+        .unwrap_or_default()
+}
+
+fn uniform_type_selector(ui: &mut egui::Ui, value: &mut UniformValue, item_id: usize) {
+    egui::ComboBox::from_id_source(format!("uniform_type{}", item_id))
+        .selected_text(uniform_type_name(value))
+        .show_ui(ui, |ui| {
+            for (name, default) in UNIFORM_TYPES.iter() {
+                if ui
+                    .selectable_label(default.is_same_type(value), *name)
+                    .clicked()
+                    && !default.is_same_type(value)
+                {
+                    *value = default.clone();
+                }
+            }
+        });
+}
+
+fn uniform_value_widget(ui: &mut egui::Ui, uniform: &mut UniformConfig) {
+    let min = uniform.min.unwrap_or(f32::NEG_INFINITY);
+    let max = uniform.max.unwrap_or(f32::INFINITY);
+    let speed = uniform.step.unwrap_or(0.1);
+    ui.horizontal(|ui| match &mut uniform.value {
+        UniformValue::Float(x) => {
+            ui.add(range_drag_value(x, min, max, speed));
+        }
+        UniformValue::Int(x) => {
+            ui.add(range_drag_value(x, min, max, speed));
+        }
+        UniformValue::Vec2(v) => component_drag_values(ui, v, min, max, speed),
+        UniformValue::Vec3(v) => component_drag_values(ui, v, min, max, speed),
+        UniformValue::Vec4(v) => component_drag_values(ui, v, min, max, speed),
+        UniformValue::Color(rgba) => {
+            ui.color_edit_button_rgba_unmultiplied(rgba);
+        }
+        UniformValue::Bool(x) => {
+            ui.checkbox(x, "");
+        }
+    });
+}
+
+fn range_drag_value<Num: egui::emath::Numeric>(
+    value: &mut Num,
+    min: f32,
+    max: f32,
+    speed: f32,
+) -> egui::DragValue<'_> {
+    egui::DragValue::new(value)
+        .clamp_range(min..=max)
+        .speed(speed)
+}
+
+fn component_drag_values(ui: &mut egui::Ui, values: &mut [f32], min: f32, max: f32, speed: f32) {
+    for value in values.iter_mut() {
+        ui.add(range_drag_value(value, min, max, speed));
+    }
+}
+
+/// A checkbox that enables a value, followed by the value
+fn optional_f32_widget(ui: &mut egui::Ui, value: &mut Option<f32>) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        ui.checkbox(&mut enabled, "");
+        if !enabled {
+            *value = None;
+        } else if value.is_none() {
+            *value = Some(0.0);
+        }
+        if let Some(x) = value {
+            ui.add(egui::DragValue::new(x).speed(0.1));
+        }
+    });
+}
@@ -176,8 +176,8 @@ pub fn perform_operation(state: &mut EditorState, operation: StateOperation, gl:
 
                 {
                     // Changing Output Link Names
-                    let old_link_names = crate::nodes::get_output_slots(&old_node_data);
-                    let new_link_names = crate::nodes::get_output_slots(&new_node_data);
+                    let old_link_names = old_node_data.get_output_slots();
+                    let new_link_names = new_node_data.get_output_slots();
                     if old_link_names.len() == new_link_names.len() {
                         for (old, new) in old_link_names.iter().zip(new_link_names.iter()) {
                             if old != new && !new_link_names.contains(old) {
@@ -195,8 +195,8 @@ pub fn perform_operation(state: &mut EditorState, operation: StateOperation, gl:
 
                 {
                     // Changing Input Link Names
-                    let old_link_names = crate::nodes::get_input_slots(&old_node_data);
-                    let new_link_names = crate::nodes::get_input_slots(&new_node_data);
+                    let old_link_names = old_node_data.get_input_slots();
+                    let new_link_names = new_node_data.get_input_slots();
                     if old_link_names.len() == new_link_names.len() {
                         for (old, new) in old_link_names.iter().zip(new_link_names.iter()) {
                             if old != new && !new_link_names.contains(old) {
@@ -246,11 +246,13 @@ pub fn perform_operation(state: &mut EditorState, operation: StateOperation, gl:
                     if let Some(start_node_id) = node_name_to_id.get(&existing_link.start_node) {
                         if let Some(end_node_id) = node_name_to_id.get(&existing_link.end_node) {
                             let start_node = &graph_nodes[*start_node_id];
-                            if crate::nodes::get_output_slots(start_node)
+                            if start_node
+                                .get_output_slots()
                                 .contains(&existing_link.start_output_slot)
                             {
                                 let end_node = &graph_nodes[*end_node_id];
-                                if crate::nodes::get_input_slots(end_node)
+                                if end_node
+                                    .get_input_slots()
                                     .contains(&existing_link.end_input_slot)
                                 {
                                     return true;
@@ -277,7 +279,10 @@ pub fn perform_operation(state: &mut EditorState, operation: StateOperation, gl:
             if state.gametoy_instance.is_some() {
                 todo!("Implement destruction of gametoy");
             }
-            let instance = create_gametoy_instance(&state.project_data, gl);
+            let instance = match create_game_data(&state.project_data) {
+                Ok(game_data) => gametoy::GameToy::from_game_data(gl, game_data, false),
+                Err(err) => Err(gametoy::GameToyError::DataLoadError(err)),
+            };
             unsafe {
                 use glow::HasContext;
                 gl.bind_framebuffer(glow::FRAMEBUFFER, None);
@@ -287,10 +292,9 @@ pub fn perform_operation(state: &mut EditorState, operation: StateOperation, gl:
     }
 }
 
-fn create_gametoy_instance(
+fn create_game_data(
     project_data: &ProjectData,
-    gl: &glow::Context,
-) -> Result<gametoy::GameToy, gametoy::GameToyError> {
+) -> Result<gametoy::gamedata::GameData, gametoy::gamedata::GameDataError> {
     let mut tarfile = gametoy::tar::Builder::new(Vec::new());
 
     for (filename, filedata) in project_data.files.iter() {
@@ -319,6 +323,5 @@ fn create_gametoy_instance(
     let tardata = tarfile.into_inner().expect("Failed to create archive");
     let tarchive = gametoy::tar::Archive::new(tardata.as_slice());
 
-    gametoy::GameToy::new(gl, tarchive, false)
-    //Err(gametoy::GameToyError::DuplicateNodeName("DuplicateNodeName".to_string()))
+    gametoy::gamedata::GameData::from_source(tarchive)
 }
@@ -4,15 +4,16 @@
 //! Creates using `parse(include_bytes!()` then they could fail.
 use super::{EditorState, GamePlayState, ProjectData};
 use gametoy::config_file::{
-    ConfigFile, ExecutionMode, GraphConfig, InputBufferConfig, KeyboardConfig, Link, MetaData,
-    Node, OutputBufferConfig, OutputBufferFormat, OutputConfig, RenderPassConfig,
-    ResolutionScalingMode,
+    ColorSpace, ConfigFile, ExecutionMode, GraphConfig, InputBufferConfig, KeyboardConfig, Link,
+    MetaData, Node, OutputBufferConfig, OutputBufferFormat, OutputConfig, RenderPassConfig,
+    ResolutionScalingMode, SamplerType,
 };
 use std::collections::HashMap;
 
 // A single render pass with keyboard input
 pub fn simple_project() -> EditorState {
     let config_file = ConfigFile {
+        format_version: gametoy::migration::CURRENT_FORMAT_VERSION,
         metadata: MetaData {
             game_name: "Your Awesome Game".to_string(),
             game_version: "0.0.0".to_string(),
@@ -35,13 +36,17 @@ pub fn simple_project() -> EditorState {
                     }],
                     input_texture_slots: vec![InputBufferConfig {
                         name: "KeyboardInput".to_string(),
+                        sampler_type: SamplerType::Sampler2D,
                     }],
                     resolution_scaling_mode: ResolutionScalingMode::ViewportScale(1.0, 1.0),
                     fragment_shader_paths: vec!["render.frag".to_string()],
                     execution_mode: ExecutionMode::Always,
+                    uniforms: vec![],
+                    shadertoy_compat: false,
                 }),
                 Node::Output(OutputConfig {
                     name: "Output".to_string(),
+                    color_space: ColorSpace::Srgb,
                 }),
             ],
             links: vec![
@@ -5,7 +5,6 @@ use web_sys::{HtmlCanvasElement, KeyboardEvent};
 
 use gametoy;
 use gametoy::glow;
-use gametoy::tar::Archive;
 
 // Pull in the console.log function so we can debug things more easily
 #[wasm_bindgen]
@@ -18,6 +17,9 @@ pub struct App {
     canvas: HtmlCanvasElement,
     toy: gametoy::GameToy,
     gl: glow::Context,
+
+    /// How many of the toy's diagnostics have been logged
+    num_diagnostics_shown: usize,
 }
 
 impl App {
@@ -41,13 +43,44 @@ impl App {
         };
         log("[OK] Got GL");
 
-        let tar = Archive::new(tar_data.as_slice());
+        // The datapack may be compressed, which is detected from it's contents
+        let tar = gametoy::data_source::open_tar(tar_data.as_slice())
+            .unwrap_or_else(|err| fail("Failed to open datapack", &err));
         log("[OK] Got Tar");
 
+        let game_data = gametoy::gamedata::GameData::from_source(tar)
+            .unwrap_or_else(|err| fail("Failed to load data", &err));
+
         // See https://github.com/emilk/egui/issues/93
-        let toy = gametoy::GameToy::new(&gl, tar, true).expect("Failed to create toy");
+        let toy = match gametoy::GameToy::from_game_data(&gl, game_data, true) {
+            Ok(toy) => toy,
+            Err(err) => {
+                // Warnings can explain the errors, so show everything found
+                if let gametoy::GameToyError::InvalidConfig(diagnostics) = &err {
+                    for diagnostic in diagnostics.iter() {
+                        log(&diagnostic.to_string());
+                    }
+                }
+                fail("Failed to create toy", &err)
+            }
+        };
+
+        let mut app = Self {
+            canvas,
+            toy,
+            gl,
+            num_diagnostics_shown: 0,
+        };
+        app.log_new_diagnostics();
+        app
+    }
 
-        Self { canvas, toy, gl }
+    fn log_new_diagnostics(&mut self) {
+        let diagnostics = self.toy.diagnostics();
+        for diagnostic in diagnostics.iter().skip(self.num_diagnostics_shown) {
+            log(&diagnostic.to_string());
+        }
+        self.num_diagnostics_shown = diagnostics.len();
     }
 
     fn check_resize(&mut self) {
@@ -76,7 +109,10 @@ impl App {
         self.check_resize();
 
         let time = Date::new_0().get_time() / 1000.0;
-        self.toy.render(&self.gl, time).expect("Failed to render toy");
+        if let Err(err) = self.toy.render(&self.gl, time) {
+            fail("Failed to render toy", &err);
+        }
+        self.log_new_diagnostics();
     }
 
     pub fn keydown_event(&mut self, event: KeyboardEvent) {
@@ -86,3 +122,14 @@ impl App {
         self.toy.set_key_state(event.which(), false);
     }
 }
+
+/// Logs an error and everything that caused it, then gives up. The panic
+/// message alone doesn't reach the console without a panic hook.
+fn fail(context: &str, err: &dyn std::error::Error) -> ! {
+    log(&format!(
+        "[ERR] {}: {}",
+        context,
+        gametoy::describe_error(err)
+    ));
+    panic!("{}", context);
+}